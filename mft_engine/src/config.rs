@@ -0,0 +1,465 @@
+//! Runtime configuration for the engine, shared between live mode and backtests.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::models::garch::{GarchInit, GarchKind};
+use crate::models::ou::{OuEstimatorKind, OuMuMode};
+
+/// Trailing stop: once unrealized PnL (close-marked) has reached
+/// `activation_frac`, exit when it retraces below `lock_frac` of its peak.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TrailingStop {
+    /// Peak unrealized PnL (fraction of entry notional) that arms the stop.
+    pub activation_frac: f64,
+    /// Share of the peak locked in once armed; exit fires when PnL drops
+    /// below `peak * lock_frac`.
+    pub lock_frac: f64,
+}
+
+/// One commission tier: the maker/taker fees earned once rolling 30-day
+/// traded notional reaches `volume`. See [`fees::FeeSchedule`](crate::fees::FeeSchedule).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeTier {
+    /// Rolling 30-day traded notional (quote currency) activating the tier.
+    pub volume: f64,
+    pub maker_fee: f64,
+    pub taker_fee: f64,
+}
+
+/// What to do when consecutive bars arrive farther apart than one interval
+/// (exchange maintenance, feed outages).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GapPolicy {
+    /// Process the late bar but restart return continuity at it, so the gap
+    /// never turns into one giant log-return (the default).
+    Skip,
+    /// Treat the missing bars as flat at the last close, preserving the
+    /// rolling windows' timing.
+    ForwardFill,
+    /// Panic on the first gap; for research runs where silently patched
+    /// data is worse than an abort.
+    Error,
+}
+
+/// Where the flow models' trade ticks come from during a replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TickSource {
+    /// Approximate flow from bars: the engine folds one aggregated tick per
+    /// kline into OFI/VPIN (the default, and all that kline-only data
+    /// allows).
+    Synthetic,
+    /// A real aggTrades stream (`<symbol>_trades.parquet` from the
+    /// complete-data collector) is fed in between bars; the engine then
+    /// skips the bar-level approximation so the same volume is not counted
+    /// twice.
+    Trades,
+}
+
+/// All tunable parameters for the MFT strategy.
+///
+/// Defaults correspond to the BTCUSDT 1m setup used during development; use
+/// [`AppConfig::from_env`] to pull API credentials and overrides from the
+/// environment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfig {
+    /// Trading symbol, e.g. "BTCUSDT".
+    pub symbol: String,
+    /// Kline interval, e.g. "1m".
+    pub kline_interval: String,
+    /// Quote (and settlement) currency of the traded symbols, e.g. "USDT"
+    /// or "USDC"; funds the backtest account and labels report figures.
+    pub quote_currency: String,
+    /// Starting capital in quote currency.
+    pub initial_capital: f64,
+    /// Leverage applied to position notional.
+    pub leverage: f64,
+    /// Cap on aggregate open exposure across all symbols, as a multiple of
+    /// equity. `INFINITY` disables the portfolio-level check.
+    pub max_portfolio_leverage: f64,
+
+    /// Maker fee (fraction, e.g. 0.0002 = 2 bps).
+    pub maker_fee: f64,
+    /// Taker fee (fraction).
+    pub taker_fee: f64,
+    /// Volume-tiered commission schedule. When non-empty it supersedes the
+    /// flat `maker_fee`/`taker_fee` with the tier earned by rolling 30-day
+    /// traded notional; empty keeps the flat fees.
+    #[serde(default)]
+    pub fee_tiers: Vec<FeeTier>,
+    /// Flat slippage assumption in basis points.
+    pub slippage_bps: f64,
+
+    /// OU estimation window in bars.
+    pub ou_window: usize,
+    /// |z| at which an entry is considered.
+    pub ou_entry_z: f64,
+    /// |z| at which a mean-reversion exit triggers.
+    pub ou_exit_z: f64,
+    /// Equilibrium estimator: `"ols"` (windowed) or `"kalman"` (recursive).
+    pub ou_estimator: OuEstimatorKind,
+    /// Equilibrium level for the z-score: `"regression"` (windowed
+    /// intercept) or `"ewma"` (tracks a drifting mean when price trends).
+    pub ou_mu_mode: OuMuMode,
+    /// Scale the entry threshold by the current GARCH σ relative to its
+    /// rolling mean, so |z| = 2 is demanded more of in high-vol regimes.
+    pub adaptive_entry_z: bool,
+    /// Higher-timeframe confirmation interval (e.g. "15m"). When set, a
+    /// second OU engine fed via `on_htf_bar` vetoes entries that fight an
+    /// extension on that timeframe. `None` disables the filter.
+    pub htf_interval: Option<String>,
+
+    /// GARCH(1,1) omega.
+    pub garch_omega: f64,
+    /// GARCH(1,1) alpha.
+    pub garch_alpha: f64,
+    /// GARCH(1,1) beta.
+    pub garch_beta: f64,
+    /// GJR asymmetry term γ (only used when `garch_kind` is `"gjr"`).
+    pub garch_gamma: f64,
+    /// Volatility recursion: `"garch"` (symmetric) or `"gjr"` (asymmetric).
+    pub garch_kind: GarchKind,
+    /// σ²_0 seed: `"sample"` (the first squared return, the historical
+    /// default) or `"unconditional"` (ω/(1−α−β), falling back to sample
+    /// when the parameters are non-stationary).
+    pub garch_init: GarchInit,
+
+    /// VPIN volume-bucket size (base asset units).
+    pub vpin_bucket_volume: f64,
+    /// Number of VPIN buckets in the rolling window.
+    pub vpin_n_buckets: usize,
+    /// VPIN above this blocks/confirms entries depending on direction.
+    pub vpin_threshold: f64,
+    /// Shrink position size as VPIN rises toward 1 (elevated
+    /// adverse-selection risk): `size *= (1 - vpin).clamp(0.25, 1.0)`,
+    /// applied after Kelly when VPIN is available on the signal.
+    pub vpin_size_scaling: bool,
+    /// OFI rolling window in ticks.
+    pub ofi_window: usize,
+    /// Exit an open position when normalized OFI swings this far against it
+    /// (strong opposing flow). 0 disables the flow-reversal exit.
+    pub ofi_exit_threshold: f64,
+    /// Fast OFI window (ticks) backing the flow-velocity signal
+    /// `ΔOFI = OFI_fast − OFI_slow`. 0 disables velocity tracking.
+    pub ofi_fast_window: usize,
+    /// Require `|ΔOFI|` at least this large before entering — accelerating
+    /// flow confirms the dislocation is being traded. 0 disables the gate.
+    pub min_ofi_velocity: f64,
+
+    /// Fraction of full Kelly to size with.
+    pub kelly_fraction: f64,
+    /// De-risk during drawdowns: scale the Kelly size by
+    /// `(1 - current_dd / max_allowed_dd).clamp(0.2, 1.0)`.
+    pub dd_throttle_enabled: bool,
+    /// Drawdown at which the throttle bottoms out (fraction of peak equity).
+    pub max_allowed_dd: f64,
+    /// Minimum expected value (after fees) required to emit a signal.
+    pub min_ev: f64,
+    /// Hard stop distance as a fraction of entry price.
+    pub stop_loss_frac: f64,
+    /// Take-profit distance as a fraction of entry price.
+    pub take_profit_frac: f64,
+    /// Maximum bars a position may be held before the time-stop closes it.
+    pub max_hold_bars: usize,
+    /// Give back protection once a trade has been well in profit. `None`
+    /// disables it.
+    pub trailing_stop: Option<TrailingStop>,
+    /// How many same-direction scale-ins a position may take on top of the
+    /// initial entry. 0 disables pyramiding.
+    pub max_scale_ins: usize,
+    /// Close a position once its entry conditions have failed to hold for
+    /// this many consecutive bars (thesis invalidation). 0 disables.
+    pub thesis_invalidation_bars: usize,
+    /// Refuse new entries for this many bars after a close, to stop
+    /// whipsaw re-entry into the same dislocation. 0 disables.
+    pub cooldown_bars: usize,
+    /// Debounce entries: after a signal, require the z-score to revert back
+    /// through `ou_exit_z` before another same-direction entry may fire, so
+    /// a dislocation that stays extreme for many bars is traded once rather
+    /// than re-entered on the bar after every exit.
+    pub signal_debounce: bool,
+    /// Stop-and-reverse: when an opposite-direction signal fires while a
+    /// position is open, close it and enter the other side on the same bar
+    /// (both legs pay fees). When off, such signals are dropped.
+    pub reverse_on_opposite_signal: bool,
+    /// Reject entries when the fitted OU half-life (bars) is below this.
+    pub min_half_life: f64,
+    /// Reject entries when the fitted OU half-life (bars) is above this —
+    /// slow reversion ties capital up too long. `INFINITY` disables.
+    pub max_half_life: f64,
+
+    /// Snap generated prices/quantities to the instrument's exchange filters
+    /// (`price_incr` / `size_incr`) so backtest fills are exchange-valid.
+    pub snap_to_filters: bool,
+
+    /// How a gap in the bar series (missing minutes) is handled.
+    pub gap_policy: GapPolicy,
+
+    /// Whether OFI/VPIN see real ticks or a per-bar approximation.
+    pub tick_source: TickSource,
+
+    /// Append every emitted [`TradeSignal`](crate::engine::TradeSignal) as a
+    /// JSON line to this file, executed or not, for post-hoc analysis.
+    /// `None` disables the log.
+    pub signal_log_path: Option<String>,
+
+    /// Per-symbol parameter overrides keyed by symbol then field name,
+    /// from `[symbol_overrides.BTCUSDT]`-style TOML tables. Applied by
+    /// [`AppConfig::for_symbol`] so a multi-symbol run can tune e.g.
+    /// `ou_window` per market.
+    #[serde(default)]
+    pub symbol_overrides: HashMap<String, HashMap<String, toml::Value>>,
+
+    /// Binance API key (live mode only).
+    #[serde(default)]
+    pub api_key: String,
+    /// Binance API secret (live mode only).
+    #[serde(default)]
+    pub api_secret: String,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            symbol: "BTCUSDT".to_string(),
+            kline_interval: "1m".to_string(),
+            quote_currency: "USDT".to_string(),
+            initial_capital: 5_000.0,
+            leverage: 3.0,
+            max_portfolio_leverage: f64::INFINITY,
+            maker_fee: 0.0002,
+            taker_fee: 0.0005,
+            fee_tiers: Vec::new(),
+            slippage_bps: 1.0,
+            ou_window: 120,
+            ou_entry_z: 2.0,
+            ou_exit_z: 0.5,
+            ou_estimator: OuEstimatorKind::Ols,
+            ou_mu_mode: OuMuMode::Regression,
+            adaptive_entry_z: false,
+            htf_interval: None,
+            garch_omega: 1e-6,
+            garch_alpha: 0.08,
+            garch_beta: 0.90,
+            garch_gamma: 0.05,
+            garch_kind: GarchKind::Garch,
+            garch_init: GarchInit::Sample,
+            vpin_bucket_volume: 50.0,
+            vpin_n_buckets: 50,
+            vpin_threshold: 0.7,
+            vpin_size_scaling: false,
+            ofi_window: 200,
+            ofi_exit_threshold: 0.0,
+            ofi_fast_window: 50,
+            min_ofi_velocity: 0.0,
+            kelly_fraction: 0.25,
+            dd_throttle_enabled: false,
+            max_allowed_dd: 0.2,
+            min_ev: 0.0,
+            stop_loss_frac: 0.005,
+            take_profit_frac: 0.01,
+            max_hold_bars: 60,
+            // The numbers the Nautilus adapter used when this rule was
+            // hardcoded there: arm at +0.4%, keep 70% of the peak.
+            trailing_stop: Some(TrailingStop {
+                activation_frac: 0.004,
+                lock_frac: 0.7,
+            }),
+            max_scale_ins: 0,
+            thesis_invalidation_bars: 0,
+            cooldown_bars: 0,
+            signal_debounce: false,
+            reverse_on_opposite_signal: false,
+            min_half_life: 0.0,
+            max_half_life: f64::INFINITY,
+            snap_to_filters: true,
+            gap_policy: GapPolicy::Skip,
+            tick_source: TickSource::Synthetic,
+            signal_log_path: None,
+            symbol_overrides: HashMap::new(),
+            api_key: String::new(),
+            api_secret: String::new(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Build a config from the environment, falling back to defaults.
+    ///
+    /// Recognised variables: `MFT_SYMBOL`, `MFT_INTERVAL`, `BINANCE_API_KEY`,
+    /// `BINANCE_API_SECRET`.
+    pub fn from_env() -> Self {
+        let mut cfg = Self::default();
+        if let Ok(sym) = std::env::var("MFT_SYMBOL") {
+            cfg.symbol = sym;
+        }
+        if let Ok(interval) = std::env::var("MFT_INTERVAL") {
+            cfg.kline_interval = interval;
+        }
+        if let Ok(key) = std::env::var("BINANCE_API_KEY") {
+            cfg.api_key = key;
+        }
+        if let Ok(secret) = std::env::var("BINANCE_API_SECRET") {
+            cfg.api_secret = secret;
+        }
+        cfg
+    }
+
+    /// Total one-way cost assumption: taker fee plus slippage.
+    pub fn one_way_cost(&self) -> f64 {
+        self.taker_fee + self.slippage_bps / 1e4
+    }
+
+    /// Check cross-field invariants that per-field parsing cannot.
+    ///
+    /// Currently: the hard stop must sit beyond a round trip of fees and
+    /// slippage — a tighter stop guarantees every stopped-out trade loses
+    /// money by construction (see the invariants in `risk.rs`).
+    pub fn validate(&self) -> Result<()> {
+        let round_trip_cost = 2.0 * self.one_way_cost();
+        anyhow::ensure!(
+            self.stop_loss_frac > round_trip_cost,
+            "stop_loss_frac {} does not cover the round-trip cost {} \
+             (2 x (taker_fee + slippage)); widen the stop",
+            self.stop_loss_frac,
+            round_trip_cost
+        );
+        Ok(())
+    }
+
+    /// Load a config from a TOML file. Missing keys keep their defaults,
+    /// unknown keys and mistyped values are errors, and API credentials
+    /// fall back to the environment so secrets can stay out of config
+    /// files.
+    pub fn from_toml_file(path: &str) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("reading config {path}"))?;
+        Self::from_toml_str(&content).with_context(|| format!("loading config {path}"))
+    }
+
+    /// Parse a TOML document into a config; see [`AppConfig::from_toml_file`].
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(content).context("not valid TOML")?;
+        let mut cfg = Self::default().with_overrides(&table.into_iter().collect())?;
+        if cfg.api_key.is_empty() {
+            if let Ok(key) = std::env::var("BINANCE_API_KEY") {
+                cfg.api_key = key;
+            }
+        }
+        if cfg.api_secret.is_empty() {
+            if let Ok(secret) = std::env::var("BINANCE_API_SECRET") {
+                cfg.api_secret = secret;
+            }
+        }
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// A copy of this config with `overrides` merged on top, keyed by
+    /// field name. Unknown keys and mistyped values are errors so typos
+    /// in a config file fail loudly instead of silently running the base
+    /// parameters.
+    pub fn with_overrides(&self, overrides: &HashMap<String, toml::Value>) -> Result<Self> {
+        let mut table =
+            toml::Table::try_from(self.clone()).context("serializing config for override merge")?;
+        for (key, value) in overrides {
+            anyhow::ensure!(
+                table.contains_key(key),
+                "unknown config key {key:?} in symbol override"
+            );
+            table.insert(key.clone(), value.clone());
+        }
+        toml::Value::Table(table)
+            .try_into()
+            .context("applying symbol overrides")
+    }
+
+    /// The config a symbol's engine should run with: the base config with
+    /// any `symbol_overrides` entry for `symbol` applied, and `symbol`
+    /// itself set.
+    pub fn for_symbol(&self, symbol: &str) -> Result<Self> {
+        let mut cfg = match self.symbol_overrides.get(symbol) {
+            Some(overrides) => self.with_overrides(overrides)?,
+            None => self.clone(),
+        };
+        cfg.symbol = symbol.to_string();
+        cfg.validate()?;
+        Ok(cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_overrides_produce_distinct_thresholds() {
+        let mut base = AppConfig::default();
+        base.symbol_overrides.insert(
+            "SOLUSDT".to_string(),
+            [("ou_entry_z".to_string(), toml::Value::Float(1.2))]
+                .into_iter()
+                .collect(),
+        );
+        let btc = base.for_symbol("BTCUSDT").unwrap();
+        let sol = base.for_symbol("SOLUSDT").unwrap();
+        assert_eq!(btc.ou_entry_z, base.ou_entry_z);
+        assert_eq!(sol.ou_entry_z, 1.2);
+        assert_eq!(sol.symbol, "SOLUSDT");
+        // Untouched fields carry over from the base.
+        assert_eq!(sol.ou_window, base.ou_window);
+        assert_eq!(sol.taker_fee, base.taker_fee);
+    }
+
+    #[test]
+    fn toml_document_round_trips_into_config() {
+        let doc = r#"
+            symbol = "ETHUSDT"
+            kline_interval = "15m"
+            ou_entry_z = 1.8
+            max_hold_bars = 30
+
+            [symbol_overrides.SOLUSDT]
+            ou_window = 90
+        "#;
+        let cfg = AppConfig::from_toml_str(doc).unwrap();
+        assert_eq!(cfg.symbol, "ETHUSDT");
+        assert_eq!(cfg.kline_interval, "15m");
+        assert_eq!(cfg.ou_entry_z, 1.8);
+        assert_eq!(cfg.max_hold_bars, 30);
+        assert_eq!(cfg.for_symbol("SOLUSDT").unwrap().ou_window, 90);
+        // Missing keys keep their defaults.
+        assert_eq!(cfg.taker_fee, AppConfig::default().taker_fee);
+        // Malformed documents error instead of silently using defaults.
+        assert!(AppConfig::from_toml_str("ou_entry_z = \"high\"").is_err());
+        assert!(AppConfig::from_toml_str("not toml [").is_err());
+    }
+
+    #[test]
+    fn stop_inside_round_trip_costs_is_rejected() {
+        let cfg = AppConfig {
+            stop_loss_frac: 0.0001,
+            taker_fee: 0.0013,
+            ..AppConfig::default()
+        };
+        let err = cfg.validate().unwrap_err();
+        assert!(err.to_string().contains("stop_loss_frac"), "{err}");
+        assert!(AppConfig::default().validate().is_ok());
+        // The TOML loader runs the same check.
+        assert!(AppConfig::from_toml_str("stop_loss_frac = 0.0001").is_err());
+    }
+
+    #[test]
+    fn unknown_override_key_is_rejected() {
+        let overrides: HashMap<String, toml::Value> =
+            [("ou_entry_zz".to_string(), toml::Value::Float(1.0))]
+                .into_iter()
+                .collect();
+        let err = AppConfig::default().with_overrides(&overrides).unwrap_err();
+        assert!(err.to_string().contains("ou_entry_zz"), "{err}");
+    }
+}
@@ -0,0 +1,204 @@
+//! Bar aggregation: build [`Kline`]s from a stream of [`TradeTick`]s, for
+//! users who only have tick data. Besides wall-clock bars this supports
+//! volume and tick-count bars, which pair naturally with VPIN's
+//! volume-bucket sampling.
+
+use super::{interval_ms, Kline, TradeTick};
+
+/// Where one bar ends and the next begins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarBoundary {
+    /// Fixed wall-clock bars of this many milliseconds, aligned to the
+    /// epoch grid (60_000 reproduces Binance 1m bars).
+    Time(i64),
+    /// Cut a bar once this much base-asset volume has accumulated.
+    Volume(f64),
+    /// Cut a bar every this many ticks.
+    TickCount(u64),
+}
+
+/// Incremental tick-to-bar aggregator. Feed ticks in ascending time order
+/// with [`BarAggregator::on_tick`]; a completed bar is returned as soon as
+/// its boundary is crossed. Call [`BarAggregator::flush`] at end of stream
+/// to emit the partial final bar.
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    boundary: BarBoundary,
+    current: Option<Kline>,
+}
+
+impl BarAggregator {
+    pub fn new(boundary: BarBoundary) -> Self {
+        Self {
+            boundary,
+            current: None,
+        }
+    }
+
+    /// Time-bar aggregator for a Binance-style interval string ("1m",
+    /// "15m", ...). `None` when the interval does not parse.
+    pub fn from_interval(interval: &str) -> Option<Self> {
+        interval_ms(interval).map(|ms| Self::new(BarBoundary::Time(ms)))
+    }
+
+    /// Consume one tick; returns a bar when the tick completes one.
+    ///
+    /// Time bars are emitted when a tick lands past the working bar's
+    /// window, so the emitted bar never includes the triggering tick.
+    /// Volume and tick-count bars are emitted inclusive of the tick that
+    /// reached the threshold. Quiet periods produce no empty bars.
+    pub fn on_tick(&mut self, tick: &TradeTick) -> Option<Kline> {
+        let finished = match self.boundary {
+            BarBoundary::Time(ms) => {
+                let open_time = tick.ts.div_euclid(ms) * ms;
+                let rolled = self
+                    .current
+                    .as_ref()
+                    .is_some_and(|bar| bar.open_time != open_time);
+                if rolled {
+                    self.current.take()
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        let bar = self.current.get_or_insert_with(|| {
+            let open_time = match self.boundary {
+                // Time bars sit on the epoch grid; event bars open at the
+                // first trade they contain.
+                BarBoundary::Time(ms) => tick.ts.div_euclid(ms) * ms,
+                _ => tick.ts,
+            };
+            let close_time = match self.boundary {
+                BarBoundary::Time(ms) => open_time + ms - 1,
+                _ => tick.ts,
+            };
+            Kline {
+                open_time,
+                open: tick.price,
+                high: tick.price,
+                low: tick.price,
+                close: tick.price,
+                volume: 0.0,
+                close_time,
+                quote_volume: 0.0,
+                n_trades: 0,
+                taker_buy_volume: 0.0,
+            }
+        });
+        bar.high = bar.high.max(tick.price);
+        bar.low = bar.low.min(tick.price);
+        bar.close = tick.price;
+        bar.volume += tick.qty;
+        bar.quote_volume += tick.price * tick.qty;
+        bar.n_trades += 1;
+        if tick.is_buy {
+            bar.taker_buy_volume += tick.qty;
+        }
+        match self.boundary {
+            BarBoundary::Time(_) => {}
+            // Event bars close at the last trade they contain.
+            _ => bar.close_time = tick.ts,
+        }
+
+        match self.boundary {
+            BarBoundary::Time(_) => finished,
+            BarBoundary::Volume(threshold) if bar.volume >= threshold => self.current.take(),
+            BarBoundary::TickCount(n) if bar.n_trades >= n => self.current.take(),
+            _ => None,
+        }
+    }
+
+    /// Emit the partial working bar, if any. For time bars its `close_time`
+    /// still marks the end of the full window.
+    pub fn flush(&mut self) -> Option<Kline> {
+        self.current.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One tick every 6 seconds, price cycling 100..=109, unit size,
+    /// alternating aggressor: ten ticks per minute.
+    fn tick(i: i64) -> TradeTick {
+        TradeTick {
+            ts: i * 6_000,
+            price: 100.0 + (i % 10) as f64,
+            qty: 1.0,
+            is_buy: i % 2 == 0,
+        }
+    }
+
+    #[test]
+    fn a_thousand_ticks_make_a_hundred_minute_bars() {
+        let mut agg = BarAggregator::from_interval("1m").unwrap();
+        let mut bars: Vec<Kline> = (0..1_000).filter_map(|i| agg.on_tick(&tick(i))).collect();
+        bars.extend(agg.flush());
+
+        assert_eq!(bars.len(), 100);
+        for (m, bar) in bars.iter().enumerate() {
+            let m = m as i64;
+            assert_eq!(bar.open_time, m * 60_000);
+            assert_eq!(bar.close_time, m * 60_000 + 59_999);
+            assert_eq!(bar.open, 100.0);
+            assert_eq!(bar.high, 109.0);
+            assert_eq!(bar.low, 100.0);
+            assert_eq!(bar.close, 109.0);
+            assert_eq!(bar.volume, 10.0);
+            assert_eq!(bar.quote_volume, (100..=109).sum::<i64>() as f64);
+            assert_eq!(bar.n_trades, 10);
+            assert_eq!(bar.taker_buy_volume, 5.0);
+        }
+    }
+
+    #[test]
+    fn quiet_gaps_produce_no_empty_bars() {
+        let mut agg = BarAggregator::new(BarBoundary::Time(60_000));
+        assert!(agg.on_tick(&TradeTick { ts: 0, price: 100.0, qty: 1.0, is_buy: true }).is_none());
+        // Next tick five minutes later: exactly one bar rolls out.
+        let bar = agg
+            .on_tick(&TradeTick { ts: 300_000, price: 101.0, qty: 1.0, is_buy: true })
+            .unwrap();
+        assert_eq!(bar.open_time, 0);
+        assert_eq!(bar.n_trades, 1);
+        assert_eq!(agg.flush().unwrap().open_time, 300_000);
+    }
+
+    #[test]
+    fn volume_bars_cut_on_the_threshold_tick() {
+        let mut agg = BarAggregator::new(BarBoundary::Volume(5.0));
+        let ticks: Vec<TradeTick> = (0..4)
+            .map(|i| TradeTick { ts: i * 1_000, price: 100.0 + i as f64, qty: 2.0, is_buy: true })
+            .collect();
+        assert!(agg.on_tick(&ticks[0]).is_none());
+        assert!(agg.on_tick(&ticks[1]).is_none());
+        // Third tick pushes accumulated volume to 6 ≥ 5 and is included.
+        let bar = agg.on_tick(&ticks[2]).unwrap();
+        assert_eq!(bar.volume, 6.0);
+        assert_eq!(bar.open_time, 0);
+        assert_eq!(bar.close_time, 2_000);
+        assert_eq!(bar.open, 100.0);
+        assert_eq!(bar.close, 102.0);
+        // The fourth tick opens a fresh bar at its own timestamp.
+        assert!(agg.on_tick(&ticks[3]).is_none());
+        assert_eq!(agg.flush().unwrap().open_time, 3_000);
+    }
+
+    #[test]
+    fn tick_count_bars_hold_exactly_n_ticks() {
+        let mut agg = BarAggregator::new(BarBoundary::TickCount(3));
+        let bars: Vec<Kline> = (0..9).filter_map(|i| agg.on_tick(&tick(i))).collect();
+        assert_eq!(bars.len(), 3);
+        assert!(bars.iter().all(|b| b.n_trades == 3));
+        assert!(agg.flush().is_none());
+    }
+
+    #[test]
+    fn bad_interval_gives_no_aggregator() {
+        assert!(BarAggregator::from_interval("banana").is_none());
+    }
+}
@@ -0,0 +1,315 @@
+//! Market-data types, the Binance REST/WebSocket clients, and a local-file
+//! replay source for offline runs.
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub mod aggregator;
+pub mod file;
+pub mod synthetic;
+pub mod ws;
+
+pub use aggregator::{BarAggregator, BarBoundary};
+pub use file::FileDataClient;
+pub use ws::{BinanceWsClient, WsConfig};
+
+/// A source of historical klines, so callers can swap the Binance REST
+/// client for a local-file replay (tests, CI, offline runs).
+pub trait KlineSource {
+    /// Fetch klines for `[start_ms, end_ms)` in ascending `open_time` order.
+    fn fetch_klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> impl std::future::Future<Output = Result<Vec<Kline>>> + Send;
+}
+
+/// One OHLCV candle as returned by the Binance klines endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Kline {
+    /// Bar open time, unix epoch milliseconds.
+    pub open_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Base-asset volume.
+    pub volume: f64,
+    /// Bar close time, unix epoch milliseconds.
+    pub close_time: i64,
+    /// Quote-asset volume.
+    pub quote_volume: f64,
+    /// Number of trades in the bar.
+    pub n_trades: u64,
+    /// Taker-buy base-asset volume.
+    pub taker_buy_volume: f64,
+}
+
+/// A single trade print, the unit consumed by the flow models.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TradeTick {
+    /// Trade time, unix epoch milliseconds.
+    pub ts: i64,
+    pub price: f64,
+    pub qty: f64,
+    /// True when the aggressor was a buyer.
+    pub is_buy: bool,
+}
+
+impl Kline {
+    /// Collapse the bar into a single representative tick at the close.
+    ///
+    /// The aggressor flag is a bar-level heuristic: the bar is "buy" when
+    /// taker-buy volume exceeds half the total volume. The tick is stamped
+    /// just past `close_time`, so it is correct for any bar interval.
+    pub fn to_tick(&self) -> TradeTick {
+        TradeTick {
+            ts: self.close_time + 1,
+            price: self.close,
+            qty: self.volume,
+            is_buy: self.taker_buy_volume > self.volume * 0.5,
+        }
+    }
+
+    /// Simple per-bar log return against a previous close.
+    pub fn log_return(&self, prev_close: f64) -> f64 {
+        (self.close / prev_close).ln()
+    }
+}
+
+/// Bar length in milliseconds for a Binance-style interval string
+/// ("1m", "15m", "4h", "1d", ...). `None` when the string does not parse,
+/// so callers pick their own fallback.
+pub fn interval_ms(interval: &str) -> Option<i64> {
+    if interval.len() < 2 {
+        return None;
+    }
+    let (num, unit) = interval.split_at(interval.len() - 1);
+    let n: i64 = num.parse().ok()?;
+    if n <= 0 {
+        return None;
+    }
+    let unit_ms = match unit {
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        "w" => 604_800_000,
+        _ => return None,
+    };
+    Some(n * unit_ms)
+}
+
+/// Bars per (365-day) year for `interval`, derived from [`interval_ms`] so
+/// annualization factors always match the bar length of the data.
+pub fn bars_per_year(interval: &str) -> Option<f64> {
+    const MS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0 * 1000.0;
+    interval_ms(interval).map(|ms| MS_PER_YEAR / ms as f64)
+}
+
+/// Thin async client for the Binance Futures REST API (public endpoints).
+pub struct BinanceDataClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Default for BinanceDataClient {
+    fn default() -> Self {
+        Self::new("https://fapi.binance.com")
+    }
+}
+
+impl BinanceDataClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+        }
+    }
+
+    /// Fetch klines for `[start_ms, end_ms)`, paginating past the 1500-bar
+    /// per-request limit. Bars are returned in ascending `open_time` order.
+    pub async fn fetch_klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> Result<Vec<Kline>> {
+        fetch_klines_paged(start_ms, end_ms, |cursor| async move {
+            let url = format!(
+                "{}/fapi/v1/klines?symbol={}&interval={}&startTime={}&endTime={}&limit=1500",
+                self.base_url, symbol, interval, cursor, end_ms
+            );
+            let raw: Vec<serde_json::Value> = self
+                .client
+                .get(&url)
+                .send()
+                .await
+                .context("klines request failed")?
+                .json()
+                .await
+                .context("klines response was not JSON")?;
+            raw.iter().map(parse_kline_row).collect()
+        })
+        .await
+    }
+}
+
+/// Binance caps klines responses at this many bars per request.
+const KLINES_PAGE_LIMIT: usize = 1500;
+
+/// Drive the pagination cursor over `fetch_page` calls until `end_ms` is
+/// reached or a short page signals the end of the data. Each page must be
+/// ascending by `open_time` (Binance's natural order); the cursor advances
+/// past the newest bar, so pages cannot overlap.
+async fn fetch_klines_paged<F, Fut>(start_ms: i64, end_ms: i64, mut fetch_page: F) -> Result<Vec<Kline>>
+where
+    F: FnMut(i64) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<Kline>>>,
+{
+    let mut all: Vec<Kline> = Vec::new();
+    let mut cursor = start_ms;
+    while cursor < end_ms {
+        let bars = fetch_page(cursor).await?;
+        if bars.is_empty() {
+            break;
+        }
+        ensure!(
+            bars.windows(2).all(|w| w[0].open_time < w[1].open_time),
+            "kline page is not ascending by open_time"
+        );
+        let page_len = bars.len();
+        cursor = bars[page_len - 1].open_time + 1;
+        all.extend(bars);
+        if page_len < KLINES_PAGE_LIMIT {
+            break;
+        }
+    }
+    Ok(all)
+}
+
+impl KlineSource for BinanceDataClient {
+    async fn fetch_klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> Result<Vec<Kline>> {
+        BinanceDataClient::fetch_klines(self, symbol, interval, start_ms, end_ms).await
+    }
+}
+
+fn parse_kline_row(row: &serde_json::Value) -> Result<Kline> {
+    let arr = match row.as_array() {
+        Some(a) if a.len() >= 11 => a,
+        _ => bail!("malformed kline row: {row}"),
+    };
+    let f = |i: usize| -> Result<f64> {
+        arr[i]
+            .as_str()
+            .map(|s| s.parse::<f64>().map_err(Into::into))
+            .unwrap_or_else(|| arr[i].as_f64().context("expected number"))
+    };
+    Ok(Kline {
+        open_time: arr[0].as_i64().context("open_time")?,
+        open: f(1)?,
+        high: f(2)?,
+        low: f(3)?,
+        close: f(4)?,
+        volume: f(5)?,
+        close_time: arr[6].as_i64().context("close_time")?,
+        quote_volume: f(7)?,
+        n_trades: arr[8].as_u64().context("n_trades")?,
+        taker_buy_volume: f(9)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minute_bar(open_time: i64) -> Kline {
+        Kline {
+            open_time,
+            open: 100.0,
+            high: 100.5,
+            low: 99.5,
+            close: 100.0,
+            volume: 10.0,
+            close_time: open_time + 59_999,
+            quote_volume: 1_000.0,
+            n_trades: 10,
+            taker_buy_volume: 5.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn pagination_is_monotone_across_page_boundaries() {
+        // 3000 one-minute bars served as two full pages of 1500.
+        let end_ms = 3_000 * 60_000;
+        let bars = fetch_klines_paged(0, end_ms, |cursor| async move {
+            // Like the exchange, serve bars on the minute grid at or after
+            // the cursor.
+            let first = (cursor + 59_999) / 60_000 * 60_000;
+            let page: Vec<Kline> = (0..KLINES_PAGE_LIMIT as i64)
+                .map(|i| minute_bar(first + i * 60_000))
+                .filter(|k| k.open_time < end_ms)
+                .collect();
+            Ok(page)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(bars.len(), 3_000);
+        // Strictly increasing implies no duplicates at the boundary.
+        assert!(bars.windows(2).all(|w| w[0].open_time < w[1].open_time));
+        assert_eq!(bars[1499].open_time + 60_000, bars[1500].open_time);
+    }
+
+    #[tokio::test]
+    async fn non_ascending_page_is_rejected() {
+        let err = fetch_klines_paged(0, 120_000, |_| async {
+            Ok(vec![minute_bar(60_000), minute_bar(0)])
+        })
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("ascending"), "{err}");
+    }
+
+    #[test]
+    fn to_tick_classifies_buy_dominant_bar() {
+        let k = Kline {
+            open_time: 0,
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.5,
+            volume: 10.0,
+            close_time: 59_999,
+            quote_volume: 1_000.0,
+            n_trades: 42,
+            taker_buy_volume: 7.0,
+        };
+        let t = k.to_tick();
+        assert!(t.is_buy);
+        assert_eq!(t.price, 100.5);
+        assert_eq!(t.qty, 10.0);
+        assert_eq!(t.ts, 60_000);
+    }
+
+    #[test]
+    fn interval_ms_parses_common_intervals() {
+        assert_eq!(interval_ms("1m"), Some(60_000));
+        assert_eq!(interval_ms("15m"), Some(900_000));
+        assert_eq!(interval_ms("4h"), Some(14_400_000));
+        assert_eq!(interval_ms("1d"), Some(86_400_000));
+        assert_eq!(interval_ms("banana"), None);
+        assert_eq!(interval_ms(""), None);
+        assert_eq!(bars_per_year("1m"), Some(525_600.0));
+        assert_eq!(bars_per_year("1d"), Some(365.0));
+    }
+}
@@ -0,0 +1,188 @@
+//! WebSocket kline stream for live mode.
+//!
+//! Connects to `wss://fstream.binance.com/ws/<symbol>@kline_<interval>`,
+//! forwards **closed** bars (`k.x == true`) over an mpsc channel, and
+//! reconnects with exponential backoff when the connection drops. This
+//! replaces REST polling, which wasted most of a bar in reaction latency.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{info, warn};
+
+use super::Kline;
+
+/// Connection tuning for [`BinanceWsClient`].
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    /// Stream endpoint, e.g. `wss://fstream.binance.com`.
+    pub base_url: String,
+    /// How often to send a protocol ping to keep the connection alive.
+    pub ping_interval: Duration,
+    /// First reconnect delay; doubles per failure up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "wss://fstream.binance.com".to_string(),
+            ping_interval: Duration::from_secs(180),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// The `kline` event envelope pushed by the stream.
+#[derive(Debug, Deserialize)]
+struct KlineEvent {
+    k: WsKline,
+}
+
+/// The `k` payload; prices/volumes arrive as strings.
+#[derive(Debug, Deserialize)]
+struct WsKline {
+    #[serde(rename = "t")]
+    open_time: i64,
+    #[serde(rename = "T")]
+    close_time: i64,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    #[serde(rename = "c")]
+    close: String,
+    #[serde(rename = "v")]
+    volume: String,
+    #[serde(rename = "q")]
+    quote_volume: String,
+    #[serde(rename = "n")]
+    n_trades: u64,
+    #[serde(rename = "V")]
+    taker_buy_volume: String,
+    /// True once the bar has closed.
+    #[serde(rename = "x")]
+    closed: bool,
+}
+
+impl WsKline {
+    fn to_kline(&self) -> Result<Kline> {
+        let f = |s: &str, name: &str| -> Result<f64> {
+            s.parse::<f64>().with_context(|| format!("ws kline field {name}: {s:?}"))
+        };
+        Ok(Kline {
+            open_time: self.open_time,
+            open: f(&self.open, "o")?,
+            high: f(&self.high, "h")?,
+            low: f(&self.low, "l")?,
+            close: f(&self.close, "c")?,
+            volume: f(&self.volume, "v")?,
+            close_time: self.close_time,
+            quote_volume: f(&self.quote_volume, "q")?,
+            n_trades: self.n_trades,
+            taker_buy_volume: f(&self.taker_buy_volume, "V")?,
+        })
+    }
+}
+
+/// Streaming kline client for the Binance Futures WebSocket API.
+pub struct BinanceWsClient {
+    cfg: WsConfig,
+}
+
+impl Default for BinanceWsClient {
+    fn default() -> Self {
+        Self::new(WsConfig::default())
+    }
+}
+
+impl BinanceWsClient {
+    pub fn new(cfg: WsConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// Spawn the stream task and return the receiving end. Only closed bars
+    /// are delivered; the task reconnects forever until the receiver drops.
+    pub fn stream_klines(&self, symbol: &str, interval: &str) -> mpsc::Receiver<Kline> {
+        let (tx, rx) = mpsc::channel(64);
+        let url = format!(
+            "{}/ws/{}@kline_{}",
+            self.cfg.base_url,
+            symbol.to_lowercase(),
+            interval
+        );
+        let cfg = self.cfg.clone();
+        tokio::spawn(run_stream(url, cfg, tx));
+        rx
+    }
+}
+
+async fn run_stream(url: String, cfg: WsConfig, tx: mpsc::Sender<Kline>) {
+    let mut backoff = cfg.initial_backoff;
+    loop {
+        match connect_async(&url).await {
+            Ok((mut ws, _)) => {
+                info!(%url, "kline stream connected");
+                backoff = cfg.initial_backoff;
+                let mut ping = tokio::time::interval(cfg.ping_interval);
+                ping.tick().await; // first tick fires immediately
+                loop {
+                    tokio::select! {
+                        _ = ping.tick() => {
+                            if ws.send(Message::Ping(Vec::new())).await.is_err() {
+                                break;
+                            }
+                        }
+                        msg = ws.next() => match msg {
+                            Some(Ok(Message::Text(text))) => {
+                                let ev: KlineEvent = match serde_json::from_str(&text) {
+                                    Ok(ev) => ev,
+                                    Err(e) => {
+                                        warn!(error = %e, "unrecognised ws message");
+                                        continue;
+                                    }
+                                };
+                                if !ev.k.closed {
+                                    continue;
+                                }
+                                match ev.k.to_kline() {
+                                    Ok(kline) => {
+                                        if tx.send(kline).await.is_err() {
+                                            // Receiver dropped: shut the task down.
+                                            return;
+                                        }
+                                    }
+                                    Err(e) => warn!(error = %e, "dropped malformed bar"),
+                                }
+                            }
+                            Some(Ok(Message::Ping(payload))) => {
+                                let _ = ws.send(Message::Pong(payload)).await;
+                            }
+                            Some(Ok(_)) => {}
+                            Some(Err(e)) => {
+                                warn!(error = %e, "ws stream error");
+                                break;
+                            }
+                            None => {
+                                warn!("ws stream closed by peer");
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!(error = %e, "ws connect failed"),
+        }
+        warn!(backoff_secs = backoff.as_secs(), "reconnecting kline stream");
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(cfg.max_backoff);
+    }
+}
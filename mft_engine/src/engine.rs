@@ -0,0 +1,1492 @@
+//! The strategy engine: consumes bars/ticks, emits entry signals and exit
+//! decisions, and tracks the currently open position.
+//!
+//! Entry logic (mean-reversion leg):
+//! 1. OU z-score beyond `±ou_entry_z`,
+//! 2. VPIN below `vpin_threshold` (avoid toxic flow),
+//! 3. positive EV after costs,
+//!
+//! then size with fractional Kelly.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::config::{AppConfig, GapPolicy, TickSource};
+use crate::data::{Kline, TradeTick};
+use crate::models::garch::VolModel;
+use crate::models::ofi::{FlowAnalyser, FlowSignal};
+use crate::models::ou::{OuEstimatorKind, OuSignalEngine};
+use crate::risk::{self, RiskLevels};
+
+/// Trade direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Long,
+    Short,
+}
+
+impl Direction {
+    pub fn sign(&self) -> f64 {
+        match self {
+            Direction::Long => 1.0,
+            Direction::Short => -1.0,
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Long => Direction::Short,
+            Direction::Short => Direction::Long,
+        }
+    }
+}
+
+/// Volatility regime from the GARCH σ's position in its recent history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolRegime {
+    /// σ below its rolling 25th percentile.
+    Low,
+    Normal,
+    /// σ above its rolling 75th percentile.
+    High,
+}
+
+/// Why a position was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExitReason {
+    StopLoss,
+    TakeProfit,
+    /// An armed trailing stop gave back more than the locked share of its
+    /// peak PnL.
+    TrailingStop,
+    /// Z-score reverted through `ou_exit_z`.
+    ZReversion,
+    /// Held longer than `max_hold_bars`.
+    TimeStop,
+    /// OFI swung beyond `ofi_exit_threshold` against the position.
+    FlowReversal,
+    /// The entry conditions stopped holding for `thesis_invalidation_bars`
+    /// consecutive bars while no other exit fired.
+    ThesisInvalidated,
+    /// Flattened to flip into an opposite-direction signal
+    /// (stop-and-reverse).
+    Reversal,
+}
+
+/// Why an entry the models wanted was not taken. Counted instead of being
+/// silently dropped, so a run can report how much more the strategy wanted
+/// to trade than it did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// The order quantity floored below the exchange minimum.
+    BelowMinSize,
+    /// Not enough free capital to margin the order.
+    InsufficientMargin,
+    /// An extreme z-score arrived inside the post-exit cooldown.
+    CooldownActive,
+    /// An entry gate was reached before every model was warm.
+    NotReady,
+}
+
+/// Which sub-model produced an entry signal. Mirrors [`ExitReason`] on the
+/// close side so the blotter records both ends of every trade's rationale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignalReason {
+    /// An OU z-score extreme being faded back toward equilibrium — every
+    /// entry [`StrategyEngine::on_bar`] itself emits.
+    OuReversion,
+    /// Strong one-sided order flow ridden in its own direction (the
+    /// momentum overlay in the backtest adapter).
+    Momentum,
+}
+
+/// An entry signal emitted by [`StrategyEngine::on_bar`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeSignal {
+    /// Bar open time (ms) of the bar that produced the signal.
+    pub ts: i64,
+    pub direction: Direction,
+    /// Signal price (the bar close the models saw).
+    pub price: f64,
+    /// OU z-score at signal time.
+    pub z_score: f64,
+    /// Expected value after costs, as a fraction of notional.
+    pub ev: f64,
+    /// VPIN at signal time, if flow models are warm.
+    pub vpin: Option<f64>,
+    /// OFI at signal time, if flow models are warm.
+    pub ofi: Option<f64>,
+    /// GARCH conditional σ per bar at signal time.
+    pub garch_sigma_bar: f64,
+    /// Sized fraction of equity (post-Kelly) to deploy.
+    pub size_frac: f64,
+    /// Protective levels to attach on entry.
+    pub risk: RiskLevels,
+    /// Which sub-model triggered the entry.
+    pub reason: SignalReason,
+}
+
+/// The position the engine believes is open.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivePosition {
+    pub direction: Direction,
+    pub entry_price: f64,
+    pub entry_ts: i64,
+    pub size_frac: f64,
+    pub bars_held: usize,
+    pub risk: RiskLevels,
+    /// Consecutive bars for which the entry thesis has not held.
+    pub thesis_gone_bars: usize,
+    /// Z-score of the most recent entry or scale-in.
+    pub entry_z: f64,
+    /// Scale-ins taken so far (excludes the initial entry).
+    pub scale_ins: usize,
+    /// Maximum adverse excursion so far (most negative unrealized
+    /// fraction, intrabar).
+    pub mae_frac: f64,
+    /// Maximum favorable excursion so far (largest unrealized fraction,
+    /// intrabar).
+    pub mfe_frac: f64,
+    /// Highest close-marked unrealized fraction so far — the high-water
+    /// mark the trailing stop measures retracement against.
+    pub peak_pnl_frac: f64,
+}
+
+impl ActivePosition {
+    /// Unrealized PnL as a fraction of entry notional at `price`.
+    pub fn unrealized_frac(&self, price: f64) -> f64 {
+        self.direction.sign() * (price - self.entry_price) / self.entry_price
+    }
+
+    /// Fold a bar's high/low into the MAE/MFE running extremes.
+    pub fn update_excursions(&mut self, high: f64, low: f64) {
+        let a = self.unrealized_frac(low);
+        let b = self.unrealized_frac(high);
+        self.mae_frac = self.mae_frac.min(a.min(b));
+        self.mfe_frac = self.mfe_frac.max(a.max(b));
+    }
+}
+
+/// Serializable snapshot of the engine's mutable state — models, buffers
+/// and the open position — for checkpointing long replays. The config and
+/// the signal-log handle are deliberately excluded: restore into an engine
+/// built with the same config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    ou: OuSignalEngine,
+    htf_ou: Option<OuSignalEngine>,
+    garch: VolModel,
+    flow: FlowAnalyser,
+    position: Option<ActivePosition>,
+    equity: f64,
+    peak_equity: f64,
+    last_close: Option<f64>,
+    last_open_time: Option<i64>,
+    sigma_ewma: f64,
+    vpin_threshold_hits: usize,
+    sigma_hist: VecDeque<f64>,
+    low_vol_bars: usize,
+    high_vol_bars: usize,
+    vol_calibration: Vec<(f64, f64)>,
+    pending_sigma_forecast: Option<f64>,
+    bars_seen: usize,
+    bars_since_exit: Option<usize>,
+    last_signal_z: Option<f64>,
+    skip_counts: HashMap<SkipReason, usize>,
+}
+
+/// Orchestrates all models and produces signals/exits.
+pub struct StrategyEngine {
+    pub cfg: AppConfig,
+    ou: OuSignalEngine,
+    /// Higher-timeframe OU engine; present when `cfg.htf_interval` is set.
+    htf_ou: Option<OuSignalEngine>,
+    garch: VolModel,
+    flow: FlowAnalyser,
+    position: Option<ActivePosition>,
+    /// Equity as a multiple of initial capital; updated on closed trades.
+    pub equity: f64,
+    /// Highest equity seen, for the drawdown throttle.
+    peak_equity: f64,
+    last_close: Option<f64>,
+    /// Open time of the last bar processed, for gap detection.
+    last_open_time: Option<i64>,
+    /// EW mean of the GARCH σ, the baseline for the adaptive threshold.
+    sigma_ewma: f64,
+    /// Bars where VPIN exceeded `cfg.vpin_threshold` (for reporting).
+    vpin_threshold_hits: usize,
+    /// Recent GARCH σ history backing the regime percentile.
+    sigma_hist: VecDeque<f64>,
+    /// Bars classified Low/High so far (for reporting).
+    low_vol_bars: usize,
+    high_vol_bars: usize,
+    /// `(σ forecast, realized |log return|)` per bar, for the calibration
+    /// report. The forecast is the GARCH σ emitted on the *previous* bar.
+    vol_calibration: Vec<(f64, f64)>,
+    /// The σ that will be scored against the next bar's realized move.
+    pending_sigma_forecast: Option<f64>,
+    /// JSONL sink for emitted signals; present when `cfg.signal_log_path`
+    /// is set and the file could be opened.
+    signal_log: Option<std::io::BufWriter<std::fs::File>>,
+    /// Bars processed so far (drives the GARCH burn-in check).
+    bars_seen: usize,
+    /// Bars since the last close; `None` until a position has been closed.
+    bars_since_exit: Option<usize>,
+    /// z at the last emitted signal, while the debounce is armed; cleared
+    /// once z reverts through the exit band. Only consulted when
+    /// `cfg.signal_debounce` is set.
+    last_signal_z: Option<f64>,
+    /// Entries the models wanted but a gate refused, by reason.
+    skip_counts: HashMap<SkipReason, usize>,
+    /// One-shot flags so each model's "ready" line is logged once.
+    ou_ready_logged: bool,
+    garch_ready_logged: bool,
+    vpin_ready_logged: bool,
+}
+
+/// Bars of σ history the regime classifier ranks against.
+const REGIME_WINDOW: usize = 250;
+/// Below this much history the classifier stays `Normal`.
+const REGIME_MIN_OBS: usize = 20;
+/// Bars of returns the GARCH recursion needs before σ has forgotten its
+/// seed variance.
+const GARCH_BURN_IN: usize = 50;
+
+impl StrategyEngine {
+    pub fn new(mut cfg: AppConfig) -> Self {
+        // Enforce the risk.rs invariant for configs built in code, which
+        // bypass AppConfig::validate: a stop inside round-trip costs loses
+        // money on every trigger, so warn and widen it instead.
+        let round_trip_cost = 2.0 * cfg.one_way_cost();
+        if cfg.stop_loss_frac <= round_trip_cost {
+            warn!(
+                stop_loss_frac = cfg.stop_loss_frac,
+                round_trip_cost,
+                "stop_loss_frac does not cover round-trip costs; raising it to twice the cost"
+            );
+            cfg.stop_loss_frac = 2.0 * round_trip_cost;
+        }
+        // The OLS fit is maintained incrementally in production — O(1) per
+        // bar instead of re-summing the window. MLE needs the raw prices
+        // and Kalman replaces the fitted mu anyway, so both keep the batch
+        // constructor.
+        let build_ou = |cfg: &AppConfig| {
+            let engine = if cfg.ou_estimator == OuEstimatorKind::Ols {
+                OuSignalEngine::new_incremental(cfg.ou_window)
+            } else {
+                OuSignalEngine::new(cfg.ou_window)
+            };
+            engine
+                .with_estimator(cfg.ou_estimator)
+                .with_mu_mode(cfg.ou_mu_mode)
+        };
+        let ou = build_ou(&cfg);
+        let htf_ou = cfg.htf_interval.as_ref().map(|_| build_ou(&cfg));
+        let garch = VolModel::with_init(
+            cfg.garch_kind,
+            cfg.garch_omega,
+            cfg.garch_alpha,
+            cfg.garch_beta,
+            cfg.garch_gamma,
+            cfg.garch_init,
+        );
+        let flow = FlowAnalyser::new(cfg.ofi_window, cfg.vpin_bucket_volume, cfg.vpin_n_buckets)
+            .with_fast_window(cfg.ofi_fast_window);
+        let signal_log = cfg.signal_log_path.as_ref().and_then(|path| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map(std::io::BufWriter::new)
+                .map_err(|e| warn!(path, error = %e, "cannot open signal log"))
+                .ok()
+        });
+        Self {
+            cfg,
+            ou,
+            htf_ou,
+            garch,
+            flow,
+            position: None,
+            equity: 1.0,
+            peak_equity: 1.0,
+            last_close: None,
+            last_open_time: None,
+            sigma_ewma: 0.0,
+            vpin_threshold_hits: 0,
+            sigma_hist: VecDeque::with_capacity(REGIME_WINDOW),
+            low_vol_bars: 0,
+            high_vol_bars: 0,
+            vol_calibration: Vec::new(),
+            pending_sigma_forecast: None,
+            signal_log,
+            bars_seen: 0,
+            bars_since_exit: None,
+            last_signal_z: None,
+            skip_counts: HashMap::new(),
+            ou_ready_logged: false,
+            garch_ready_logged: false,
+            vpin_ready_logged: false,
+        }
+    }
+
+    /// Bars of history wanted before trading: the largest of the OU window,
+    /// the VPIN bucket count (at best one bucket completes per bar), and
+    /// the GARCH burn-in.
+    pub fn required_warmup(&self) -> usize {
+        self.cfg
+            .ou_window
+            .max(self.cfg.vpin_n_buckets)
+            .max(GARCH_BURN_IN)
+    }
+
+    /// Capture the mutable model state for a checkpoint.
+    pub fn snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot {
+            ou: self.ou.clone(),
+            htf_ou: self.htf_ou.clone(),
+            garch: self.garch.clone(),
+            flow: self.flow.clone(),
+            position: self.position.clone(),
+            equity: self.equity,
+            peak_equity: self.peak_equity,
+            last_close: self.last_close,
+            last_open_time: self.last_open_time,
+            sigma_ewma: self.sigma_ewma,
+            vpin_threshold_hits: self.vpin_threshold_hits,
+            sigma_hist: self.sigma_hist.clone(),
+            low_vol_bars: self.low_vol_bars,
+            high_vol_bars: self.high_vol_bars,
+            vol_calibration: self.vol_calibration.clone(),
+            pending_sigma_forecast: self.pending_sigma_forecast,
+            bars_seen: self.bars_seen,
+            bars_since_exit: self.bars_since_exit,
+            last_signal_z: self.last_signal_z,
+            skip_counts: self.skip_counts.clone(),
+        }
+    }
+
+    /// Restore state captured by [`snapshot`](Self::snapshot). The engine's
+    /// config is left untouched; the one-shot "ready" log flags are
+    /// re-derived so restoring never re-announces warm models.
+    pub fn restore(&mut self, snap: EngineSnapshot) {
+        self.ou = snap.ou;
+        self.htf_ou = snap.htf_ou;
+        self.garch = snap.garch;
+        self.flow = snap.flow;
+        self.position = snap.position;
+        self.equity = snap.equity;
+        self.peak_equity = snap.peak_equity;
+        self.last_close = snap.last_close;
+        self.last_open_time = snap.last_open_time;
+        self.sigma_ewma = snap.sigma_ewma;
+        self.vpin_threshold_hits = snap.vpin_threshold_hits;
+        self.sigma_hist = snap.sigma_hist;
+        self.low_vol_bars = snap.low_vol_bars;
+        self.high_vol_bars = snap.high_vol_bars;
+        self.vol_calibration = snap.vol_calibration;
+        self.pending_sigma_forecast = snap.pending_sigma_forecast;
+        self.bars_seen = snap.bars_seen;
+        self.bars_since_exit = snap.bars_since_exit;
+        self.last_signal_z = snap.last_signal_z;
+        self.skip_counts = snap.skip_counts;
+        self.ou_ready_logged = self.ou.params().is_some();
+        self.garch_ready_logged = self.bars_seen >= GARCH_BURN_IN;
+        self.vpin_ready_logged = self.flow.vpin_engine().completed_buckets() > 0;
+    }
+
+    /// True once every model is warm: OU parameters fitted, the GARCH
+    /// recursion burned in, and at least one VPIN bucket completed. Entries
+    /// are suppressed until then.
+    pub fn is_ready(&self) -> bool {
+        self.ou.params().is_some()
+            && self.bars_seen >= GARCH_BURN_IN
+            && self.flow.vpin_engine().completed_buckets() > 0
+    }
+
+    /// Feed a tick to the flow models (live tick stream or bar approximation).
+    pub fn on_tick(&mut self, tick: &TradeTick) -> FlowSignal {
+        self.flow.push_tick(tick)
+    }
+
+    /// Feed a close from the higher-timeframe series (`cfg.htf_interval`).
+    /// No-op when the confirmation filter is disabled.
+    pub fn on_htf_bar(&mut self, close: f64) {
+        if let Some(htf) = &mut self.htf_ou {
+            htf.push(close);
+        }
+    }
+
+    /// Process one closed bar; may emit an entry signal when flat.
+    pub fn on_bar(&mut self, kline: &Kline) -> Option<TradeSignal> {
+        self.handle_gap(kline);
+        self.bars_seen += 1;
+        if let Some(n) = &mut self.bars_since_exit {
+            *n += 1;
+        }
+        if !self.garch_ready_logged && self.bars_seen >= GARCH_BURN_IN {
+            self.garch_ready_logged = true;
+            debug!(bars = self.bars_seen, "GARCH burn-in complete");
+        }
+        if let Some(prev) = self.last_close {
+            let realized = kline.log_return(prev).abs();
+            if let Some(forecast) = self.pending_sigma_forecast {
+                self.vol_calibration.push((forecast, realized));
+            }
+            let sigma = self.garch.update(kline.log_return(prev));
+            self.pending_sigma_forecast = Some(sigma);
+            // Rolling baseline for the adaptive threshold (λ = 0.99,
+            // ~100-bar memory); seeded with the first σ observed.
+            if self.sigma_ewma == 0.0 {
+                self.sigma_ewma = sigma;
+            } else {
+                self.sigma_ewma = 0.99 * self.sigma_ewma + 0.01 * sigma;
+            }
+            if self.sigma_hist.len() == REGIME_WINDOW {
+                self.sigma_hist.pop_front();
+            }
+            self.sigma_hist.push_back(sigma);
+            match self.current_regime() {
+                VolRegime::Low => self.low_vol_bars += 1,
+                VolRegime::High => self.high_vol_bars += 1,
+                VolRegime::Normal => {}
+            }
+        }
+        self.last_close = Some(kline.close);
+        // Bar-level flow approximation, unless a real tick feed drives the
+        // flow models through `on_tick` — folding the bar in as well would
+        // count the same volume twice.
+        let flow = match self.cfg.tick_source {
+            TickSource::Synthetic => self.flow.push_tick(&kline.to_tick()),
+            TickSource::Trades => self.flow.signal(),
+        };
+        if flow.vpin.is_some_and(|v| v > self.cfg.vpin_threshold) {
+            self.vpin_threshold_hits += 1;
+        }
+        if !self.vpin_ready_logged && flow.vpin.is_some() {
+            self.vpin_ready_logged = true;
+            debug!(bars = self.bars_seen, "VPIN bucket window warm");
+        }
+        let z = self.ou.push(kline.close)?;
+        if !self.ou_ready_logged {
+            self.ou_ready_logged = true;
+            debug!(bars = self.bars_seen, "OU window full");
+        }
+        // Re-arm the debounce once z has come back through the exit band:
+        // the dislocation that was signalled has resolved, so whatever
+        // shows up next is a new one.
+        if let Some(prev) = self.last_signal_z {
+            let reverted = if prev < 0.0 {
+                z >= -self.cfg.ou_exit_z
+            } else {
+                z <= self.cfg.ou_exit_z
+            };
+            if reverted {
+                self.last_signal_z = None;
+            }
+        }
+
+        if let Some(pos) = &mut self.position {
+            pos.bars_held += 1;
+            pos.update_excursions(kline.high, kline.low);
+            // The trailing high-water mark is marked at closes, not intrabar
+            // extremes, so one long wick cannot arm and trip it at once.
+            let marked = pos.unrealized_frac(kline.close);
+            if marked > pos.peak_pnl_frac {
+                pos.peak_pnl_frac = marked;
+            }
+            // Thesis check: is the z-score still extreme in the entry
+            // direction? (VPIN only gates entries, so it is not part of
+            // the thesis.)
+            let thesis_holds = match pos.direction {
+                Direction::Long => z <= -self.cfg.ou_entry_z,
+                Direction::Short => z >= self.cfg.ou_entry_z,
+            };
+            if thesis_holds {
+                pos.thesis_gone_bars = 0;
+            } else {
+                pos.thesis_gone_bars += 1;
+            }
+            let held = pos.direction;
+            if !self.cfg.reverse_on_opposite_signal {
+                return None;
+            }
+            // Stop-and-reverse: only an *opposite* signal escapes while a
+            // position is open; the caller closes and flips on this bar.
+            return self
+                .evaluate_entry(kline, z, flow)
+                .filter(|s| s.direction == held.opposite());
+        }
+
+        self.evaluate_entry(kline, z, flow)
+    }
+
+    /// Detect a hole in the bar series and apply `cfg.gap_policy`. Binance
+    /// history is missing the odd minute (maintenance, outages); without
+    /// this, the first bar after a gap would be treated as adjacent and the
+    /// whole gap collapses into one spurious log-return.
+    fn handle_gap(&mut self, kline: &Kline) {
+        let span = kline.close_time - kline.open_time + 1;
+        let missing = match self.last_open_time {
+            Some(prev) if span > 0 => (kline.open_time - prev) / span - 1,
+            _ => 0,
+        };
+        self.last_open_time = Some(kline.open_time);
+        if missing <= 0 {
+            return;
+        }
+        match self.cfg.gap_policy {
+            GapPolicy::Skip => {
+                // Restart return continuity: this bar re-seeds `last_close`
+                // instead of producing a gap-wide return.
+                warn!(missing, open_time = kline.open_time, "bar gap, resetting returns");
+                self.last_close = None;
+                self.pending_sigma_forecast = None;
+            }
+            GapPolicy::ForwardFill => {
+                // Hold the last close flat through the hole so the rolling
+                // windows keep their timing without a price jump.
+                if let Some(prev) = self.last_close {
+                    for _ in 0..missing {
+                        let _ = self.ou.push(prev);
+                        self.garch.update(0.0);
+                    }
+                }
+            }
+            GapPolicy::Error => panic!(
+                "bar gap: {missing} missing bar(s) before open_time {}",
+                kline.open_time
+            ),
+        }
+    }
+
+    /// The entry threshold currently in force: `ou_entry_z`, optionally
+    /// scaled by the volatility regime when `adaptive_entry_z` is set.
+    pub fn effective_entry_z(&self) -> f64 {
+        if !self.cfg.adaptive_entry_z || self.sigma_ewma <= 0.0 {
+            return self.cfg.ou_entry_z;
+        }
+        self.cfg.ou_entry_z * (self.garch.sigma() / self.sigma_ewma).clamp(0.5, 2.0)
+    }
+
+    fn evaluate_entry(&mut self, kline: &Kline, z: f64, flow: FlowSignal) -> Option<TradeSignal> {
+        if !self.is_ready() {
+            self.count_skip(SkipReason::NotReady);
+            return None;
+        }
+        let entry_z = self.effective_entry_z();
+        if z.abs() < entry_z {
+            return None;
+        }
+        // Cool-down: stay out for `cooldown_bars` bars after a close so a
+        // half-resolved dislocation cannot be re-entered immediately.
+        // Checked after the threshold so the skip counter only records
+        // entries the models actually wanted.
+        if self.bars_since_exit.is_some_and(|n| n < self.cfg.cooldown_bars) {
+            self.count_skip(SkipReason::CooldownActive);
+            return None;
+        }
+        // Debounce: a dislocation that stays extreme keeps its z-sign for
+        // many bars, and without this the engine would re-signal it on
+        // every one of them (and re-enter right after every exit).
+        if self.cfg.signal_debounce
+            && self
+                .last_signal_z
+                .is_some_and(|prev| prev.signum() == z.signum())
+        {
+            return None;
+        }
+        if let Some(vpin) = flow.vpin {
+            if vpin > self.cfg.vpin_threshold {
+                debug!(vpin, "entry blocked by VPIN");
+                return None;
+            }
+        }
+        // Flow-acceleration confirm: only fade a dislocation while flow is
+        // actually accelerating (`|ΔOFI|` large), in either direction.
+        if self.cfg.min_ofi_velocity > 0.0
+            && !flow
+                .ofi_velocity
+                .is_some_and(|v| v.abs() >= self.cfg.min_ofi_velocity)
+        {
+            debug!(ofi_velocity = ?flow.ofi_velocity, "entry blocked by OFI velocity");
+            return None;
+        }
+        let params = self.ou.params()?;
+        // Regime filter: a half-life outside the band means reversion is
+        // either noise-fast or too slow to pay for the holding period.
+        if params.half_life < self.cfg.min_half_life || params.half_life > self.cfg.max_half_life {
+            debug!(half_life = params.half_life, "entry blocked by half-life band");
+            return None;
+        }
+        let ev = risk::evaluate_ev(z, params.sigma_eq, kline.close, &self.cfg);
+        if ev <= self.cfg.min_ev {
+            return None;
+        }
+        let direction = if z < 0.0 { Direction::Long } else { Direction::Short };
+        // Higher-timeframe veto: do not fade a 1m dislocation when the HTF
+        // says price is extended in the opposite direction.
+        if let Some(htf_z) = self.htf_ou.as_ref().and_then(|h| h.z_score(kline.close)) {
+            let htf_opposes = match direction {
+                Direction::Long => htf_z >= self.cfg.ou_entry_z,
+                Direction::Short => htf_z <= -self.cfg.ou_entry_z,
+            };
+            if htf_opposes {
+                debug!(htf_z, "entry blocked by higher-timeframe z");
+                return None;
+            }
+        }
+        // Win-probability proxy from the z-score magnitude; payoff from levels.
+        let p_win = 0.5 + 0.1 * (z.abs() - entry_z).min(1.5);
+        let b = self.cfg.take_profit_frac / self.cfg.stop_loss_frac;
+        let size_frac = risk::kelly_size(p_win, b, &self.cfg)
+            * self.dd_throttle()
+            * risk::vpin_size_multiplier(flow.vpin, &self.cfg);
+        if size_frac <= 0.0 {
+            return None;
+        }
+        let signal = TradeSignal {
+            ts: kline.open_time,
+            direction,
+            price: kline.close,
+            z_score: z,
+            ev,
+            vpin: flow.vpin,
+            ofi: flow.ofi,
+            garch_sigma_bar: self.garch.sigma(),
+            size_frac,
+            risk: RiskLevels::from_entry(kline.close, direction, &self.cfg),
+            reason: SignalReason::OuReversion,
+        };
+        self.last_signal_z = Some(z);
+        self.log_signal(&signal);
+        Some(signal)
+    }
+
+    /// Append `signal` to the JSONL log when one is configured. Logging is
+    /// best-effort: an I/O failure must not break the trading path.
+    fn log_signal(&mut self, signal: &TradeSignal) {
+        use std::io::Write;
+        let Some(log) = &mut self.signal_log else {
+            return;
+        };
+        match serde_json::to_string(signal) {
+            Ok(line) => {
+                let _ = writeln!(log, "{line}");
+            }
+            Err(e) => warn!(error = %e, "cannot serialize signal"),
+        }
+    }
+
+    /// Flush the signal log to disk (call at end of a run / on shutdown).
+    pub fn flush_signal_log(&mut self) {
+        use std::io::Write;
+        if let Some(log) = &mut self.signal_log {
+            let _ = log.flush();
+        }
+    }
+
+    /// Record that a signal was acted on (fills tracked by the caller).
+    pub fn open_position(&mut self, signal: &TradeSignal) {
+        self.position = Some(ActivePosition {
+            direction: signal.direction,
+            entry_price: signal.price,
+            entry_ts: signal.ts,
+            size_frac: signal.size_frac,
+            bars_held: 0,
+            risk: signal.risk,
+            thesis_gone_bars: 0,
+            entry_z: signal.z_score,
+            scale_ins: 0,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            peak_pnl_frac: 0.0,
+        });
+    }
+
+    /// Add to the open position on a stronger same-direction signal
+    /// (pyramiding). The entry price becomes the size-weighted blend, so
+    /// exits and PnL are measured against the averaged basis, and the
+    /// protective levels are recomputed from it. Returns `false` when
+    /// there is no position, the direction differs, the new |z| is not
+    /// larger, or `max_scale_ins` is exhausted.
+    pub fn scale_in(&mut self, signal: &TradeSignal) -> bool {
+        let Some(pos) = &mut self.position else {
+            return false;
+        };
+        if signal.direction != pos.direction
+            || signal.z_score.abs() <= pos.entry_z.abs()
+            || pos.scale_ins >= self.cfg.max_scale_ins
+        {
+            return false;
+        }
+        let total = pos.size_frac + signal.size_frac;
+        pos.entry_price =
+            (pos.entry_price * pos.size_frac + signal.price * signal.size_frac) / total;
+        pos.size_frac = total;
+        pos.entry_z = signal.z_score;
+        pos.scale_ins += 1;
+        pos.risk = RiskLevels::from_entry(pos.entry_price, pos.direction, &self.cfg);
+        // The high-water mark restarts against the blended basis, like the
+        // protective levels.
+        pos.peak_pnl_frac = 0.0;
+        true
+    }
+
+    /// Check whether the open position should be closed at `price`.
+    pub fn check_exit(&self, price: f64) -> Option<ExitReason> {
+        let pos = self.position.as_ref()?;
+        if pos.risk.is_stopped(price, pos.direction) {
+            return Some(ExitReason::StopLoss);
+        }
+        if pos.risk.is_profit_taken(price, pos.direction) {
+            return Some(ExitReason::TakeProfit);
+        }
+        if let Some(trail) = &self.cfg.trailing_stop {
+            if pos.peak_pnl_frac >= trail.activation_frac
+                && pos.unrealized_frac(price) < pos.peak_pnl_frac * trail.lock_frac
+            {
+                return Some(ExitReason::TrailingStop);
+            }
+        }
+        // Flow reversal: strong flow against the position means the reversion
+        // is being run over; get out before the hard stop.
+        if self.cfg.ofi_exit_threshold > 0.0 {
+            if let Some(ofi) = self.flow.signal().ofi {
+                let opposed = match pos.direction {
+                    Direction::Long => ofi < -self.cfg.ofi_exit_threshold,
+                    Direction::Short => ofi > self.cfg.ofi_exit_threshold,
+                };
+                if opposed {
+                    return Some(ExitReason::FlowReversal);
+                }
+            }
+        }
+        if let Some(z) = self.ou.z_score(price) {
+            let reverted = match pos.direction {
+                Direction::Long => z >= -self.cfg.ou_exit_z,
+                Direction::Short => z <= self.cfg.ou_exit_z,
+            };
+            if reverted {
+                return Some(ExitReason::ZReversion);
+            }
+        }
+        if self.cfg.thesis_invalidation_bars > 0
+            && pos.thesis_gone_bars >= self.cfg.thesis_invalidation_bars
+        {
+            return Some(ExitReason::ThesisInvalidated);
+        }
+        if pos.bars_held >= self.cfg.max_hold_bars {
+            return Some(ExitReason::TimeStop);
+        }
+        None
+    }
+
+    /// Close the open position at `price`, compounding engine equity.
+    /// Returns the realized PnL fraction (of entry notional, after costs).
+    pub fn close_position(&mut self, price: f64) -> Option<f64> {
+        let pos = self.position.take()?;
+        self.bars_since_exit = Some(0);
+        let gross = pos.unrealized_frac(price);
+        let net = gross - 2.0 * self.cfg.one_way_cost();
+        self.equity *= 1.0 + net * pos.size_frac * self.cfg.leverage;
+        if self.equity > self.peak_equity {
+            self.peak_equity = self.equity;
+        }
+        Some(net)
+    }
+
+    /// Size multiplier from the drawdown throttle: 1.0 at the peak, shrinking
+    /// linearly to the 0.2 floor as drawdown approaches `max_allowed_dd`.
+    pub fn dd_throttle(&self) -> f64 {
+        if !self.cfg.dd_throttle_enabled || self.cfg.max_allowed_dd <= 0.0 {
+            return 1.0;
+        }
+        let current_dd = (self.peak_equity - self.equity) / self.peak_equity;
+        (1.0 - current_dd / self.cfg.max_allowed_dd).clamp(0.2, 1.0)
+    }
+
+    pub fn position(&self) -> Option<&ActivePosition> {
+        self.position.as_ref()
+    }
+
+    /// Equity marked to `price`: realized equity compounded with the open
+    /// position's unrealized PnL, gross of exit costs. Equals [`Self::equity`]
+    /// when flat.
+    pub fn marked_equity(&self, price: f64) -> f64 {
+        match &self.position {
+            Some(pos) => {
+                self.equity
+                    * (1.0 + pos.unrealized_frac(price) * pos.size_frac * self.cfg.leverage)
+            }
+            None => self.equity,
+        }
+    }
+
+    pub fn flow_signal(&self) -> FlowSignal {
+        self.flow.signal()
+    }
+
+    /// The OU z-score of `price` against the fitted window; `None` until
+    /// the window is full.
+    pub fn z_score(&self, price: f64) -> Option<f64> {
+        self.ou.z_score(price)
+    }
+
+    /// Bars seen so far where VPIN exceeded the configured threshold.
+    pub fn vpin_threshold_hits(&self) -> usize {
+        self.vpin_threshold_hits
+    }
+
+    /// Entries the models wanted but a gate refused, by reason.
+    pub fn skip_counts(&self) -> &HashMap<SkipReason, usize> {
+        &self.skip_counts
+    }
+
+    /// Record a refused entry, here or in an execution layer that sizes
+    /// and places the engine's signals.
+    pub fn count_skip(&mut self, reason: SkipReason) {
+        *self.skip_counts.entry(reason).or_insert(0) += 1;
+    }
+
+    /// Classify the current GARCH σ against its rolling history: below the
+    /// 25th percentile is `Low`, above the 75th is `High`. Stays `Normal`
+    /// until enough history has accumulated.
+    pub fn current_regime(&self) -> VolRegime {
+        if self.sigma_hist.len() < REGIME_MIN_OBS {
+            return VolRegime::Normal;
+        }
+        let sigma = self.garch.sigma();
+        let below = self.sigma_hist.iter().filter(|s| **s < sigma).count();
+        let rank = below as f64 / self.sigma_hist.len() as f64;
+        if rank < 0.25 {
+            VolRegime::Low
+        } else if rank > 0.75 {
+            VolRegime::High
+        } else {
+            VolRegime::Normal
+        }
+    }
+
+    /// Bars classified Low/High so far, for the report's regime summary.
+    pub fn regime_bar_counts(&self) -> (usize, usize) {
+        (self.low_vol_bars, self.high_vol_bars)
+    }
+
+    /// Per-bar `(σ forecast, realized |log return|)` pairs collected over
+    /// the run, for the GARCH calibration report.
+    pub fn vol_calibration(&self) -> &[(f64, f64)] {
+        &self.vol_calibration
+    }
+
+    pub fn ou(&self) -> &OuSignalEngine {
+        &self.ou
+    }
+
+    pub fn garch(&self) -> &VolModel {
+        &self.garch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TrailingStop;
+
+    fn bar(i: i64, close: f64) -> Kline {
+        Kline {
+            open_time: i * 60_000,
+            open: close,
+            high: close * 1.001,
+            low: close * 0.999,
+            close,
+            volume: 10.0,
+            close_time: i * 60_000 + 59_999,
+            quote_volume: close * 10.0,
+            n_trades: 10,
+            // Alternate the bar-level aggressor so the VPIN gate sees
+            // balanced flow instead of a fully one-sided tape.
+            taker_buy_volume: if i % 2 == 0 { 7.0 } else { 3.0 },
+        }
+    }
+
+    /// Close for bar `i` of a gentle oscillation of amplitude `amp` around
+    /// `level`. The slow sine keeps consecutive closes positively
+    /// correlated, which the AR(1) fit requires — a bar-by-bar alternation
+    /// would estimate a slope near −1 and `OuParams::estimate` rejects it.
+    fn osc(i: i64, level: f64, amp: f64) -> f64 {
+        level + amp * (i as f64 / 3.0).sin()
+    }
+
+    fn small_cfg() -> AppConfig {
+        AppConfig {
+            ou_window: 30,
+            // Buckets span 2.5 bars so alternating aggressors blend.
+            vpin_bucket_volume: 25.0,
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn required_warmup_tracks_the_slowest_model() {
+        let base = StrategyEngine::new(small_cfg()).required_warmup();
+
+        let wide_ou = StrategyEngine::new(AppConfig {
+            ou_window: 500,
+            ..small_cfg()
+        });
+        assert_eq!(wide_ou.required_warmup(), 500);
+        assert!(wide_ou.required_warmup() > base);
+
+        let many_buckets = StrategyEngine::new(AppConfig {
+            vpin_n_buckets: 400,
+            ..small_cfg()
+        });
+        assert_eq!(many_buckets.required_warmup(), 400);
+        assert!(many_buckets.required_warmup() > base);
+    }
+
+    #[test]
+    fn no_signal_before_warmup() {
+        let mut eng = StrategyEngine::new(small_cfg());
+        for i in 0..20 {
+            assert!(eng.on_bar(&bar(i, 100.0 + (i % 3) as f64 * 0.1)).is_none());
+        }
+        assert!(!eng.is_ready());
+    }
+
+    #[test]
+    fn ready_only_when_every_model_is_warm() {
+        let mut eng = StrategyEngine::new(small_cfg());
+        // ou_window = 30 fills first, but the GARCH burn-in is longer: the
+        // engine must stay not-ready (and signal-free) in between. A deep
+        // dip that would otherwise signal is suppressed.
+        for i in 0..40 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+        }
+        assert!(eng.ou().params().is_some());
+        assert!(!eng.is_ready());
+        assert!(eng.on_bar(&bar(40, 95.0)).is_none());
+
+        // Continue until well past the burn-in (and until the suppressed
+        // 95.0 print has rolled out of the OU window again).
+        for i in 41..80 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+        }
+        assert!(eng.is_ready());
+        assert!(eng.on_bar(&bar(80, 95.0)).is_some());
+    }
+
+    #[test]
+    fn deep_dip_triggers_long_signal() {
+        let mut eng = StrategyEngine::new(small_cfg());
+        // Oscillate around 100, then crash several sigma below.
+        for i in 0..60 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+        }
+        let sig = eng.on_bar(&bar(60, 95.0));
+        let sig = sig.expect("expected a long signal on the dip");
+        assert_eq!(sig.direction, Direction::Long);
+        assert_eq!(sig.reason, SignalReason::OuReversion);
+        assert!(sig.z_score < -2.0);
+    }
+
+    #[test]
+    fn vanished_thesis_closes_before_time_stop() {
+        let cfg = AppConfig {
+            thesis_invalidation_bars: 3,
+            // Wide enough that the idle price below cannot trip the stop.
+            stop_loss_frac: 0.03,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg.clone());
+        for i in 0..60 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+        }
+        let params = *eng.ou().params().expect("warm");
+        // A price ~2.5σ below equilibrium: pinning there drags the refit
+        // toward it, but |z| settles near 1.3 — not extreme enough to
+        // re-justify the entry, not reverted enough for the z-exit.
+        let idle_price = params.mu - 2.5 * params.sigma_eq;
+
+        let signal = TradeSignal {
+            ts: 0,
+            direction: Direction::Long,
+            price: 100.0,
+            z_score: -2.5,
+            ev: 0.001,
+            vpin: None,
+            ofi: None,
+            garch_sigma_bar: 0.001,
+            size_frac: 0.1,
+            risk: RiskLevels::from_entry(100.0, Direction::Long, &cfg),
+            reason: SignalReason::OuReversion,
+        };
+        eng.open_position(&signal);
+
+        let mut exit = None;
+        for i in 60..70 {
+            eng.on_bar(&bar(i, idle_price));
+            exit = eng.check_exit(idle_price);
+            if exit.is_some() {
+                break;
+            }
+        }
+        assert_eq!(exit, Some(ExitReason::ThesisInvalidated));
+        assert!(eng.position().unwrap().bars_held < cfg.max_hold_bars);
+    }
+
+    #[test]
+    fn retrace_past_the_lock_trips_the_trailing_stop() {
+        let cfg = AppConfig {
+            trailing_stop: Some(TrailingStop {
+                activation_frac: 0.004,
+                lock_frac: 0.7,
+            }),
+            // Keep the plain take-profit out of the way of the +1% run-up.
+            take_profit_frac: 0.05,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg.clone());
+        // 66 bars end the warm-up near the sine's mean, so the off-tape
+        // 101 print keeps the AR(1) slope in range and the bar is processed.
+        for i in 0..66 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+        }
+        eng.open_position(&long_signal(100.0, -2.5, &cfg));
+
+        // A +1% close sets the high-water mark and arms the stop.
+        eng.on_bar(&bar(66, 101.0));
+        let peak = eng.position().unwrap().peak_pnl_frac;
+        assert!((peak - 0.01).abs() < 1e-9);
+        // Above the locked level (70% of the peak) the trailing stop holds.
+        assert_ne!(eng.check_exit(100.9), Some(ExitReason::TrailingStop));
+        // Retracing below +0.7% trips it.
+        assert_eq!(eng.check_exit(100.5), Some(ExitReason::TrailingStop));
+
+        // With the config unset the same path never reports a trailing stop.
+        let cfg_off = AppConfig {
+            trailing_stop: None,
+            ..cfg
+        };
+        let mut off = StrategyEngine::new(cfg_off.clone());
+        for i in 0..66 {
+            let close = osc(i, 100.0, 0.5);
+            off.on_bar(&bar(i, close));
+        }
+        off.open_position(&long_signal(100.0, -2.5, &cfg_off));
+        off.on_bar(&bar(66, 101.0));
+        assert_ne!(off.check_exit(100.5), Some(ExitReason::TrailingStop));
+    }
+
+    #[test]
+    fn marked_equity_carries_open_pnl() {
+        let cfg = small_cfg();
+        let mut eng = StrategyEngine::new(cfg.clone());
+        // Flat, the mark is just realized equity at any price.
+        assert_eq!(eng.marked_equity(123.0), eng.equity);
+
+        eng.open_position(&long_signal(100.0, -2.5, &cfg));
+        // A long marked 2% above entry: +2% gross on the sized, levered
+        // fraction of equity.
+        let expected = eng.equity * (1.0 + 0.02 * 0.1 * cfg.leverage);
+        assert!((eng.marked_equity(102.0) - expected).abs() < 1e-9);
+        assert!(eng.marked_equity(102.0) > eng.equity);
+        assert!(eng.marked_equity(98.0) < eng.equity);
+    }
+
+    fn long_signal(price: f64, z: f64, cfg: &AppConfig) -> TradeSignal {
+        TradeSignal {
+            ts: 0,
+            direction: Direction::Long,
+            price,
+            z_score: z,
+            ev: 0.001,
+            vpin: None,
+            ofi: None,
+            garch_sigma_bar: 0.001,
+            size_frac: 0.1,
+            risk: RiskLevels::from_entry(price, Direction::Long, cfg),
+            reason: SignalReason::OuReversion,
+        }
+    }
+
+    /// A slow climb toward equilibrium plus a small oscillation: AR(1) with
+    /// b ≈ 0.99, i.e. a ~69-bar half-life.
+    fn slow_reverting_closes(n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|t| osc(t as i64, 100.0 - 10.0 * 0.99f64.powi(t as i32), 0.05))
+            .collect()
+    }
+
+    #[test]
+    fn long_half_life_regime_is_rejected() {
+        let cfg = AppConfig {
+            max_half_life: 10.0,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        let closes = slow_reverting_closes(101);
+        for (i, close) in closes[..100].iter().enumerate() {
+            eng.on_bar(&bar(i as i64, *close));
+        }
+        let hl = eng.ou().params().expect("warm").half_life;
+        assert!(hl > 10.0, "half-life = {hl}");
+        // Mid-climb the tape sits several σ below the extrapolated
+        // equilibrium — an extreme dislocation by z — but the regime must
+        // not signal.
+        assert!(eng.on_bar(&bar(100, closes[100])).is_none());
+
+        // Control: with the band open the same series does signal.
+        let mut control = StrategyEngine::new(small_cfg());
+        for (i, close) in closes[..100].iter().enumerate() {
+            control.on_bar(&bar(i as i64, *close));
+        }
+        assert!(control.on_bar(&bar(100, closes[100])).is_some());
+    }
+
+    #[test]
+    fn vol_spike_transitions_regime_to_high() {
+        // Seed σ² at the unconditional variance: from a sample seed the
+        // recursion climbs monotonically for hundreds of bars, which would
+        // keep the current σ at the top of its own history ("High") through
+        // the whole calm stretch.
+        let cfg = AppConfig {
+            garch_init: crate::models::garch::GarchInit::Unconditional,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        // A long calm stretch builds the σ history…
+        for i in 0..120 {
+            let close = osc(i, 100.0, 0.05);
+            eng.on_bar(&bar(i, close));
+        }
+        assert_ne!(eng.current_regime(), VolRegime::High);
+
+        // …then violent swings push σ above its 75th percentile.
+        for i in 120..130 {
+            let close = osc(i, 100.0, 3.0);
+            eng.on_bar(&bar(i, close));
+        }
+        assert_eq!(eng.current_regime(), VolRegime::High);
+        let (_, high_bars) = eng.regime_bar_counts();
+        assert!(high_bars > 0);
+    }
+
+    #[test]
+    fn drawdown_throttle_halves_size_at_half_the_allowance() {
+        let cfg = AppConfig {
+            dd_throttle_enabled: true,
+            max_allowed_dd: 0.2,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        let mut control = StrategyEngine::new(small_cfg());
+        for i in 0..60 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+            control.on_bar(&bar(i, close));
+        }
+        // Simulate a 10% drawdown from the 1.0 peak: half the allowance,
+        // so the throttle should be (1 - 0.1/0.2) = 0.5.
+        eng.equity = 0.9;
+        assert!((eng.dd_throttle() - 0.5).abs() < 1e-12);
+
+        let sized = eng.on_bar(&bar(60, 95.0)).expect("signal");
+        let unthrottled = control.on_bar(&bar(60, 95.0)).expect("signal");
+        assert!((sized.size_frac - 0.5 * unthrottled.size_frac).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reversal_mode_lets_an_opposite_signal_through() {
+        let cfg = AppConfig {
+            reverse_on_opposite_signal: true,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg.clone());
+        let mut control = StrategyEngine::new(small_cfg());
+        // 70 bars end the warm-up on a below-mean phase, which keeps the
+        // AR(1) slope in range when the spike print joins the window.
+        for i in 0..70 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+            control.on_bar(&bar(i, close));
+        }
+        eng.open_position(&long_signal(100.0, -2.5, &cfg));
+        control.open_position(&long_signal(100.0, -2.5, &small_cfg()));
+
+        // A spike far above equilibrium is a short signal against the
+        // open long: emitted under reversal mode, swallowed otherwise.
+        let sig = eng.on_bar(&bar(70, 105.0)).expect("reversal signal");
+        assert_eq!(sig.direction, Direction::Short);
+        assert!(control.on_bar(&bar(70, 105.0)).is_none());
+    }
+
+    #[test]
+    fn htf_disagreement_suppresses_long_signal() {
+        let cfg = AppConfig {
+            htf_interval: Some("15m".to_string()),
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        // HTF equilibrium near 80: a 95 print is far *above* it, so the
+        // 15m view opposes a long.
+        for i in 0..40 {
+            let close = osc(i, 80.0, 0.5);
+            eng.on_htf_bar(close);
+        }
+        // 1m oscillates around 100, then dips — normally a long signal.
+        for i in 0..60 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+        }
+        assert!(eng.on_bar(&bar(60, 95.0)).is_none());
+
+        // Control: without the filter the same series signals long.
+        let mut control = StrategyEngine::new(small_cfg());
+        for i in 0..60 {
+            let close = osc(i, 100.0, 0.5);
+            control.on_bar(&bar(i, close));
+        }
+        assert!(control.on_bar(&bar(60, 95.0)).is_some());
+    }
+
+    #[test]
+    fn scale_ins_blend_entry_and_respect_cap() {
+        let cfg = AppConfig {
+            max_scale_ins: 2,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg.clone());
+        eng.open_position(&long_signal(100.0, -2.5, &cfg));
+
+        assert!(eng.scale_in(&long_signal(98.0, -3.0, &cfg)));
+        assert!(eng.scale_in(&long_signal(96.0, -3.5, &cfg)));
+        let pos = eng.position().unwrap();
+        // (100·0.1 + 98·0.1 + 96·0.1) / 0.3
+        assert!((pos.entry_price - 98.0).abs() < 1e-12);
+        assert!((pos.size_frac - 0.3).abs() < 1e-12);
+
+        // Cap reached: a yet-stronger signal is rejected.
+        assert!(!eng.scale_in(&long_signal(94.0, -4.0, &cfg)));
+        // A weaker |z| would be rejected even below the cap.
+        assert!(!eng.scale_in(&long_signal(97.0, -1.0, &cfg)));
+    }
+
+    #[test]
+    fn adaptive_threshold_widens_on_vol_spike() {
+        let cfg = AppConfig {
+            adaptive_entry_z: true,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg.clone());
+        // Calm regime: tiny oscillation around 100.
+        for i in 0..200 {
+            let close = osc(i, 100.0, 0.02);
+            eng.on_bar(&bar(i, close));
+        }
+        let calm_z = eng.effective_entry_z();
+        // Volatility burst: swings 50x larger.
+        for i in 200..220 {
+            let close = osc(i, 100.0, 1.0);
+            eng.on_bar(&bar(i, close));
+        }
+        let burst_z = eng.effective_entry_z();
+        assert!(
+            burst_z > calm_z && burst_z > cfg.ou_entry_z,
+            "calm={calm_z} burst={burst_z}"
+        );
+    }
+
+    #[test]
+    fn signal_log_has_one_line_per_emitted_signal() {
+        let dir = std::env::temp_dir().join("mft_signal_log_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("signals.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let cfg = AppConfig {
+            signal_log_path: Some(path.to_string_lossy().to_string()),
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        // 200 bars oscillating around 100 with a deep dip every 10th bar
+        // once warm; the engine stays flat so each dip can signal.
+        let mut emitted = 0;
+        for i in 0..200 {
+            let close = if i > 60 && i % 10 == 0 {
+                95.0
+            } else {
+                osc(i, 100.0, 0.5)
+            };
+            if eng.on_bar(&bar(i, close)).is_some() {
+                emitted += 1;
+            }
+        }
+        eng.flush_signal_log();
+
+        assert!(emitted > 0, "the series should produce signals");
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), emitted);
+        // Each line round-trips through serde.
+        let first: TradeSignal = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(first.direction, Direction::Long);
+    }
+
+    #[test]
+    fn cooldown_suppresses_reentry_until_it_expires() {
+        let cfg = AppConfig {
+            cooldown_bars: 5,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg.clone());
+        let mut control = StrategyEngine::new(small_cfg());
+        for i in 0..80 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+            control.on_bar(&bar(i, close));
+        }
+        // A round trip on the cooldown engine only; both stay flat after,
+        // so from bar +5 on their state is identical except the counter.
+        eng.open_position(&long_signal(100.0, -2.5, &cfg));
+        eng.close_position(100.0);
+
+        for k in 1..=6i64 {
+            let got = eng.on_bar(&bar(80 + k, 95.0)).is_some();
+            let would = control.on_bar(&bar(80 + k, 95.0)).is_some();
+            if k == 1 {
+                assert!(would, "the dip must signal without a cooldown");
+            }
+            if k < 5 {
+                assert!(!got, "bar +{k} should be inside the cooldown");
+            } else {
+                assert_eq!(got, would, "bar +{k} is past the cooldown");
+            }
+        }
+    }
+
+    #[test]
+    fn debounce_signals_a_persistent_dislocation_once() {
+        let cfg = AppConfig {
+            signal_debounce: true,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        for i in 0..80 {
+            let close = osc(i, 100.0, 0.5);
+            eng.on_bar(&bar(i, close));
+        }
+        // Price pins at a deep dip: the z-score stays extreme bar after
+        // bar, but only the first bar may signal.
+        let signals = (0..6)
+            .filter(|k| eng.on_bar(&bar(80 + k, 95.0)).is_some())
+            .count();
+        assert_eq!(signals, 1, "a persistent dislocation must signal once");
+
+        // Let the window absorb the dip so z reverts through the exit
+        // band; the debounce re-arms and a fresh dislocation signals. The
+        // tape ends at bar 140, a phase where the 90 print keeps the AR(1)
+        // slope in range.
+        for k in 0..55 {
+            let close = osc(86 + k, 95.0, 0.5);
+            eng.on_bar(&bar(86 + k, close));
+        }
+        let again = eng.on_bar(&bar(141, 90.0));
+        assert!(again.is_some(), "a new dislocation after reversion must signal");
+    }
+
+    #[test]
+    fn sell_burst_exits_a_long_on_flow_reversal() {
+        let cfg = AppConfig {
+            ofi_exit_threshold: 0.6,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg.clone());
+        eng.open_position(&long_signal(100.0, -2.5, &cfg));
+        // Flat price keeps the stop/TP/z exits quiet; the flow alone decides.
+        assert_eq!(eng.check_exit(100.0), None);
+
+        for i in 0..50 {
+            eng.on_tick(&TradeTick {
+                ts: i,
+                price: 100.0,
+                qty: 5.0,
+                is_buy: false,
+            });
+        }
+        assert!(eng.flow_signal().ofi.unwrap() < -0.6);
+        assert_eq!(eng.check_exit(100.0), Some(ExitReason::FlowReversal));
+
+        // Disabled threshold: the same flow is ignored.
+        let mut off = StrategyEngine::new(small_cfg());
+        off.open_position(&long_signal(100.0, -2.5, &small_cfg()));
+        for i in 0..50 {
+            off.on_tick(&TradeTick {
+                ts: i,
+                price: 100.0,
+                qty: 5.0,
+                is_buy: false,
+            });
+        }
+        assert_eq!(off.check_exit(100.0), None);
+    }
+
+    #[test]
+    fn stop_loss_exit_fires() {
+        let mut eng = StrategyEngine::new(small_cfg());
+        let signal = TradeSignal {
+            ts: 0,
+            direction: Direction::Long,
+            price: 100.0,
+            z_score: -2.5,
+            ev: 0.001,
+            vpin: None,
+            ofi: None,
+            garch_sigma_bar: 0.001,
+            size_frac: 0.1,
+            risk: RiskLevels::from_entry(100.0, Direction::Long, &small_cfg()),
+            reason: SignalReason::OuReversion,
+        };
+        eng.open_position(&signal);
+        assert_eq!(eng.check_exit(99.0), Some(ExitReason::StopLoss));
+    }
+
+    #[test]
+    fn a_bar_gap_does_not_produce_a_giant_log_return() {
+        let mut eng = StrategyEngine::new(small_cfg());
+        for i in 0..60 {
+            eng.on_bar(&bar(i, 100.0 + (i % 5) as f64 * 0.1));
+        }
+        // Ten missing minutes, then the series resumes much higher. Without
+        // gap handling the whole hole would collapse into one ~9% return;
+        // under the default `Skip` policy the post-gap bar only re-seeds
+        // return continuity.
+        eng.on_bar(&bar(70, 110.0));
+        let spike = eng
+            .vol_calibration()
+            .iter()
+            .map(|(_, realized)| *realized)
+            .fold(0.0, f64::max);
+        assert!(spike < 0.01, "max realized |log return| {spike}");
+    }
+
+    #[test]
+    #[should_panic(expected = "bar gap")]
+    fn the_error_policy_aborts_on_a_gap() {
+        let cfg = AppConfig {
+            gap_policy: GapPolicy::Error,
+            ..small_cfg()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        eng.on_bar(&bar(0, 100.0));
+        eng.on_bar(&bar(5, 100.0));
+    }
+}
@@ -0,0 +1,148 @@
+//! Volume-tiered commission schedule (Binance VIP levels).
+//!
+//! Binance prices maker/taker fees off rolling 30-day traded notional, so a
+//! flat fee misrepresents high-volume accounts. [`FeeSchedule`] tracks the
+//! notional of every fill inside a 30-day window and reports the fees of the
+//! tier that volume currently earns.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::FeeTier;
+
+/// The window Binance ranks VIP volume over.
+const VOLUME_WINDOW_MS: i64 = 30 * 86_400_000;
+
+/// Rolling 30-day volume tracker that resolves the active [`FeeTier`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    /// Tiers sorted by ascending volume threshold; the lowest must start
+    /// at 0 so a fee is always defined.
+    tiers: Vec<FeeTier>,
+    /// `(ts ms, notional)` per fill still inside the window.
+    fills: VecDeque<(i64, f64)>,
+    rolling_notional: f64,
+}
+
+impl FeeSchedule {
+    /// `tiers` must be non-empty; they are sorted by threshold here.
+    pub fn new(mut tiers: Vec<FeeTier>) -> Self {
+        assert!(!tiers.is_empty(), "a fee schedule needs at least one tier");
+        tiers.sort_by(|a, b| a.volume.total_cmp(&b.volume));
+        Self {
+            tiers,
+            fills: VecDeque::new(),
+            rolling_notional: 0.0,
+        }
+    }
+
+    /// A single-tier schedule: flat maker/taker fees regardless of volume.
+    pub fn flat(maker_fee: f64, taker_fee: f64) -> Self {
+        Self::new(vec![FeeTier {
+            volume: 0.0,
+            maker_fee,
+            taker_fee,
+        }])
+    }
+
+    /// The first few Binance USDT-futures VIP levels. VIP 0 matches the
+    /// flat defaults in [`AppConfig`](crate::config::AppConfig).
+    pub fn binance_vip() -> Self {
+        let tier = |volume, maker_fee, taker_fee| FeeTier {
+            volume,
+            maker_fee,
+            taker_fee,
+        };
+        Self::new(vec![
+            tier(0.0, 0.0002, 0.0005),
+            tier(15_000_000.0, 0.00016, 0.0004),
+            tier(50_000_000.0, 0.00014, 0.00035),
+            tier(100_000_000.0, 0.00012, 0.00032),
+        ])
+    }
+
+    /// Drop fills that have aged out of the window ending at `now_ms`.
+    pub fn roll_to(&mut self, now_ms: i64) {
+        while let Some(&(ts, notional)) = self.fills.front() {
+            if ts > now_ms - VOLUME_WINDOW_MS {
+                break;
+            }
+            self.fills.pop_front();
+            self.rolling_notional -= notional;
+        }
+    }
+
+    /// Record one fill's traded notional (quote currency). The fee for the
+    /// fill itself should be read *before* recording it, matching the
+    /// exchange: a fill counts toward the tier of later trades, not its own.
+    pub fn record_fill(&mut self, ts_ms: i64, notional: f64) {
+        self.roll_to(ts_ms);
+        self.fills.push_back((ts_ms, notional));
+        self.rolling_notional += notional;
+    }
+
+    /// The highest tier the rolling volume has reached.
+    fn tier(&self) -> &FeeTier {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|t| self.rolling_notional >= t.volume)
+            .unwrap_or(&self.tiers[0])
+    }
+
+    pub fn maker_fee(&self) -> f64 {
+        self.tier().maker_fee
+    }
+
+    pub fn taker_fee(&self) -> f64 {
+        self.tier().taker_fee
+    }
+
+    /// Traded notional currently inside the 30-day window.
+    pub fn rolling_notional(&self) -> f64 {
+        self.rolling_notional
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_tier() -> FeeSchedule {
+        FeeSchedule::new(vec![
+            FeeTier {
+                volume: 0.0,
+                maker_fee: 0.0002,
+                taker_fee: 0.0005,
+            },
+            FeeTier {
+                volume: 1_000.0,
+                maker_fee: 0.00016,
+                taker_fee: 0.0004,
+            },
+        ])
+    }
+
+    #[test]
+    fn volume_promotes_to_the_next_tier() {
+        let mut fees = two_tier();
+        assert_eq!(fees.taker_fee(), 0.0005);
+        fees.record_fill(0, 600.0);
+        assert_eq!(fees.taker_fee(), 0.0005, "below the threshold");
+        fees.record_fill(1, 400.0);
+        assert_eq!(fees.taker_fee(), 0.0004);
+        assert_eq!(fees.maker_fee(), 0.00016);
+    }
+
+    #[test]
+    fn volume_ages_out_of_the_window() {
+        let mut fees = two_tier();
+        fees.record_fill(0, 1_500.0);
+        assert_eq!(fees.taker_fee(), 0.0004);
+        // 31 days later the fill has aged out and the base tier applies.
+        fees.roll_to(31 * 86_400_000);
+        assert_eq!(fees.rolling_notional(), 0.0);
+        assert_eq!(fees.taker_fee(), 0.0005);
+    }
+}
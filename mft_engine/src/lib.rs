@@ -0,0 +1,27 @@
+//! MFT engine: the model core shared by the live runner and the backtests.
+//!
+//! The pipeline is:
+//!
+//! ```text
+//! Kline / TradeTick
+//!     ├── models::ou      Ornstein-Uhlenbeck fit → z-score
+//!     ├── models::garch   GARCH(1,1) conditional volatility
+//!     └── models::{ofi, vpin}  order-flow imbalance / flow toxicity
+//!             │
+//!             ▼
+//!     engine::StrategyEngine  → TradeSignal / exit decisions
+//!             │
+//!             ▼
+//!     risk                 EV gate, Kelly sizing, stop/target levels
+//! ```
+
+pub mod config;
+pub mod covariance;
+pub mod data;
+pub mod engine;
+pub mod fees;
+pub mod live;
+pub mod metrics;
+pub mod models;
+pub mod risk;
+
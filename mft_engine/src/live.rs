@@ -0,0 +1,759 @@
+//! Binance USDⓈ-M Futures order client for live mode.
+//!
+//! Only the endpoints the live runner needs: market orders (optionally
+//! reduce-only), protective stop/take-profit brackets, leverage, position
+//! risk and a blanket close.
+//! All signed requests use HMAC-SHA256 over the query string per the
+//! Binance API docs.
+
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use tracing::{error, info, warn};
+
+use crate::engine::{Direction, ExitReason};
+use crate::risk::RiskLevels;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The exchange rejected a protective order because it would trigger
+/// immediately (Binance error code -2021). Callers can downcast to this to
+/// distinguish "price already through the level" from transport failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImmediatelyTriggerable;
+
+impl std::fmt::Display for ImmediatelyTriggerable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "order would trigger immediately (Binance -2021)")
+    }
+}
+
+impl std::error::Error for ImmediatelyTriggerable {}
+
+/// The exchange rejected an order for insufficient margin (Binance error
+/// code -2019). The live runner halves the quantity and retries once when
+/// it sees this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarginInsufficient;
+
+impl std::fmt::Display for MarginInsufficient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "margin is insufficient (Binance -2019)")
+    }
+}
+
+impl std::error::Error for MarginInsufficient {}
+
+/// Exchange-side view of a position, from `/fapi/v2/positionRisk`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PositionInfo {
+    pub symbol: String,
+    #[serde(rename = "positionAmt")]
+    pub position_amt: String,
+    #[serde(rename = "entryPrice")]
+    pub entry_price: String,
+    #[serde(rename = "unRealizedProfit")]
+    pub unrealized_profit: String,
+}
+
+impl PositionInfo {
+    pub fn qty(&self) -> f64 {
+        self.position_amt.parse().unwrap_or(0.0)
+    }
+}
+
+/// True when the engine's view of the position agrees with the exchange's:
+/// both flat, or open in the same direction. Quantities are deliberately
+/// not compared — partial fills and quantity rounding make exact matches
+/// meaningless; a direction mismatch is the drift that matters.
+pub fn position_matches(engine_dir: Option<Direction>, exchange: Option<&PositionInfo>) -> bool {
+    match (engine_dir, exchange) {
+        (None, None) => true,
+        (Some(dir), Some(info)) => {
+            let qty = info.qty();
+            match dir {
+                Direction::Long => qty > 0.0,
+                Direction::Short => qty < 0.0,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Runner-side hard risk control: the protective levels captured when a
+/// position was entered, checked against every observed price. Unlike
+/// [`crate::engine::StrategyEngine::check_exit`] this does not recompute
+/// any model state, so it keeps working even if the models drift.
+#[derive(Debug, Clone, Copy)]
+pub struct LivePositionMonitor {
+    direction: Direction,
+    levels: RiskLevels,
+}
+
+impl LivePositionMonitor {
+    pub fn new(direction: Direction, levels: RiskLevels) -> Self {
+        Self { direction, levels }
+    }
+
+    /// The exit to take when `price` has crossed either stored level.
+    pub fn check(&self, price: f64) -> Option<ExitReason> {
+        if self.levels.is_stopped(price, self.direction) {
+            return Some(ExitReason::StopLoss);
+        }
+        if self.levels.is_profit_taken(price, self.direction) {
+            return Some(ExitReason::TakeProfit);
+        }
+        None
+    }
+}
+
+/// The one capability the shutdown path needs from an order client, split
+/// out so the handler can run against a fake in tests.
+pub trait PositionFlattener {
+    /// Close any open position on `symbol`.
+    fn close_all_positions(
+        &self,
+        symbol: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+}
+
+/// Place a market order, halving the quantity and retrying exactly once
+/// when the venue reports insufficient margin ([`MarginInsufficient`]).
+/// Returns the quantity that actually filled; any other error, or a
+/// second -2019, propagates.
+pub async fn market_order_with_margin_retry<E: OrderExecutor>(
+    exec: &E,
+    symbol: &str,
+    side: &str,
+    qty: f64,
+) -> Result<f64> {
+    match exec.market_order(symbol, side, qty).await {
+        Ok(()) => Ok(qty),
+        Err(e) if e.downcast_ref::<MarginInsufficient>().is_some() => {
+            let reduced = qty / 2.0;
+            warn!(symbol, side, qty, reduced, "margin insufficient, retrying at half size");
+            exec.market_order(symbol, side, reduced).await?;
+            Ok(reduced)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Flatten `symbol` and log final equity. The live runner calls this on
+/// Ctrl-C; errors are logged rather than propagated so shutdown always
+/// completes.
+pub async fn flatten_on_shutdown<C: PositionFlattener>(client: &C, symbol: &str, equity: f64) {
+    match client.close_all_positions(symbol).await {
+        Ok(()) => info!(symbol, equity, "flattened on shutdown"),
+        Err(e) => error!(error = %e, symbol, "failed to flatten on shutdown"),
+    }
+}
+
+/// Everything the live runner needs from a venue, so it can run against
+/// Binance, a paper simulator, or a test fake. Flattening comes from the
+/// [`PositionFlattener`] supertrait.
+pub trait OrderExecutor: PositionFlattener {
+    /// Place a MARKET order. `side` is "BUY" or "SELL".
+    fn market_order(
+        &self,
+        symbol: &str,
+        side: &str,
+        qty: f64,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Set initial leverage for `symbol`.
+    fn set_leverage(
+        &self,
+        symbol: &str,
+        leverage: u32,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Current position for `symbol`, `None` when flat.
+    fn get_position(
+        &self,
+        symbol: &str,
+    ) -> impl std::future::Future<Output = Result<Option<PositionInfo>>> + Send;
+
+    /// Latest traded price. Simulated venues fill orders at it; real
+    /// venues ignore it.
+    fn note_price(&self, _price: f64) {}
+}
+
+/// Order time-in-force. MARKET orders carry no TIF (the venue rejects
+/// one); limit-style order types default to [`TimeInForce::Gtc`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeInForce {
+    /// Good till canceled.
+    Gtc,
+    /// Immediate or cancel.
+    Ioc,
+    /// Fill or kill.
+    Fok,
+    /// Good till the given epoch-ms expiry (Binance `GTD`, which also
+    /// requires `goodTillDate`).
+    Gtd(i64),
+}
+
+impl TimeInForce {
+    /// Query-string fragment for this TIF.
+    fn query_fragment(&self) -> String {
+        match self {
+            TimeInForce::Gtc => "timeInForce=GTC".to_string(),
+            TimeInForce::Ioc => "timeInForce=IOC".to_string(),
+            TimeInForce::Fok => "timeInForce=FOK".to_string(),
+            TimeInForce::Gtd(expiry_ms) => {
+                format!("timeInForce=GTD&goodTillDate={expiry_ms}")
+            }
+        }
+    }
+}
+
+/// Build the `/fapi/v1/order` query string. `reduce_only` asks the venue
+/// to cap the fill at the open position, so a close can never flip into a
+/// fresh position.
+fn order_query(
+    symbol: &str,
+    side: &str,
+    order_type: &str,
+    qty: f64,
+    tif: Option<TimeInForce>,
+    reduce_only: bool,
+) -> String {
+    let mut query = format!("symbol={symbol}&side={side}&type={order_type}&quantity={qty}");
+    if let Some(tif) = tif {
+        query.push('&');
+        query.push_str(&tif.query_fragment());
+    }
+    if reduce_only {
+        query.push_str("&reduceOnly=true");
+    }
+    query
+}
+
+/// REST order client holding API credentials.
+pub struct LiveOrderClient {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    api_secret: String,
+}
+
+impl LiveOrderClient {
+    pub fn new(base_url: &str, api_key: &str, api_secret: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            api_secret: api_secret.to_string(),
+        }
+    }
+
+    fn sign(&self, query: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(self.api_secret.as_bytes())
+            .expect("HMAC accepts any key length");
+        mac.update(query.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn timestamp_ms() -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+
+    async fn signed_post_raw(
+        &self,
+        path: &str,
+        query: String,
+    ) -> Result<(reqwest::StatusCode, serde_json::Value)> {
+        let query = format!("{query}&timestamp={}", Self::timestamp_ms());
+        let sig = self.sign(&query);
+        let url = format!("{}{}?{}&signature={}", self.base_url, path, query, sig);
+        let resp = self
+            .client
+            .post(&url)
+            .header("X-MBX-APIKEY", &self.api_key)
+            .send()
+            .await
+            .context("order request failed")?;
+        let status = resp.status();
+        let body: serde_json::Value = resp.json().await.context("non-JSON order response")?;
+        Ok((status, body))
+    }
+
+    async fn signed_post(&self, path: &str, query: String) -> Result<serde_json::Value> {
+        let (status, body) = self.signed_post_raw(path, query).await?;
+        if !status.is_success() {
+            bail!("exchange rejected request ({status}): {body}");
+        }
+        Ok(body)
+    }
+
+    async fn signed_get(&self, path: &str, query: String) -> Result<serde_json::Value> {
+        let query = format!("{query}&timestamp={}", Self::timestamp_ms());
+        let sig = self.sign(&query);
+        let url = format!("{}{}?{}&signature={}", self.base_url, path, query, sig);
+        let resp = self
+            .client
+            .get(&url)
+            .header("X-MBX-APIKEY", &self.api_key)
+            .send()
+            .await
+            .context("signed GET failed")?;
+        let status = resp.status();
+        let body: serde_json::Value = resp.json().await.context("non-JSON response")?;
+        if !status.is_success() {
+            bail!("exchange rejected request ({status}): {body}");
+        }
+        Ok(body)
+    }
+
+    /// Place a MARKET order. `side` is "BUY" or "SELL". A -2019 rejection
+    /// surfaces as [`MarginInsufficient`].
+    pub async fn market_order(&self, symbol: &str, side: &str, qty: f64) -> Result<serde_json::Value> {
+        self.send_market_order(order_query(symbol, side, "MARKET", qty, None, false), symbol, side, qty)
+            .await
+    }
+
+    /// Place a reduce-only MARKET order for closing: the venue caps the
+    /// fill at the open position, so the close cannot flip it.
+    pub async fn close_market_order(
+        &self,
+        symbol: &str,
+        side: &str,
+        qty: f64,
+    ) -> Result<serde_json::Value> {
+        self.send_market_order(order_query(symbol, side, "MARKET", qty, None, true), symbol, side, qty)
+            .await
+    }
+
+    async fn send_market_order(
+        &self,
+        query: String,
+        symbol: &str,
+        side: &str,
+        qty: f64,
+    ) -> Result<serde_json::Value> {
+        let (status, body) = self.signed_post_raw("/fapi/v1/order", query).await?;
+        if !status.is_success() {
+            if body.get("code").and_then(|c| c.as_i64()) == Some(-2019) {
+                return Err(anyhow::Error::new(MarginInsufficient)
+                    .context(format!("MARKET {symbol} {side} {qty}")));
+            }
+            bail!("exchange rejected request ({status}): {body}");
+        }
+        info!(symbol, side, qty, "market order placed");
+        Ok(body)
+    }
+
+    /// Place a protective trigger order (`STOP_MARKET` or
+    /// `TAKE_PROFIT_MARKET`). With `qty = None` the order is sent with
+    /// `closePosition=true` and flattens whatever is open when it fires;
+    /// with `Some(qty)` it is a reduce-only order for that quantity.
+    /// A -2021 rejection surfaces as [`ImmediatelyTriggerable`].
+    async fn trigger_order(
+        &self,
+        order_type: &str,
+        symbol: &str,
+        side: &str,
+        stop_price: f64,
+        qty: Option<f64>,
+    ) -> Result<serde_json::Value> {
+        let mut query = format!(
+            "symbol={symbol}&side={side}&type={order_type}&stopPrice={stop_price}"
+        );
+        match qty {
+            Some(q) => query.push_str(&format!("&quantity={q}&reduceOnly=true")),
+            None => query.push_str("&closePosition=true"),
+        }
+        let (status, body) = self.signed_post_raw("/fapi/v1/order", query).await?;
+        if !status.is_success() {
+            if body.get("code").and_then(|c| c.as_i64()) == Some(-2021) {
+                return Err(anyhow::Error::new(ImmediatelyTriggerable)
+                    .context(format!("{order_type} {symbol} @ {stop_price}")));
+            }
+            bail!("exchange rejected {order_type} ({status}): {body}");
+        }
+        info!(symbol, side, order_type, stop_price, "trigger order placed");
+        Ok(body)
+    }
+
+    /// Place a `STOP_MARKET` protective stop.
+    pub async fn place_stop_market(
+        &self,
+        symbol: &str,
+        side: &str,
+        stop_price: f64,
+        qty: Option<f64>,
+    ) -> Result<serde_json::Value> {
+        self.trigger_order("STOP_MARKET", symbol, side, stop_price, qty)
+            .await
+    }
+
+    /// Place a `TAKE_PROFIT_MARKET` target order.
+    pub async fn place_take_profit_market(
+        &self,
+        symbol: &str,
+        side: &str,
+        stop_price: f64,
+        qty: Option<f64>,
+    ) -> Result<serde_json::Value> {
+        self.trigger_order("TAKE_PROFIT_MARKET", symbol, side, stop_price, qty)
+            .await
+    }
+
+    /// Market entry plus both protective orders from `levels`, so the
+    /// position is never naked even if this process dies. The protective
+    /// orders use `closePosition=true` and the side opposite the entry.
+    pub async fn place_bracket(
+        &self,
+        symbol: &str,
+        entry_side: &str,
+        qty: f64,
+        levels: &RiskLevels,
+    ) -> Result<()> {
+        self.market_order(symbol, entry_side, qty).await?;
+        let exit_side = if entry_side == "BUY" { "SELL" } else { "BUY" };
+        self.place_stop_market(symbol, exit_side, levels.stop_loss, None)
+            .await?;
+        self.place_take_profit_market(symbol, exit_side, levels.take_profit, None)
+            .await?;
+        info!(symbol, entry_side, qty, "bracket placed");
+        Ok(())
+    }
+
+    /// Set initial leverage for `symbol`.
+    pub async fn set_leverage(&self, symbol: &str, leverage: u32) -> Result<()> {
+        let query = format!("symbol={symbol}&leverage={leverage}");
+        self.signed_post("/fapi/v1/leverage", query).await?;
+        Ok(())
+    }
+
+    /// Fetch the current position for `symbol` (flat positions have qty 0).
+    pub async fn get_position(&self, symbol: &str) -> Result<Option<PositionInfo>> {
+        let query = format!("symbol={symbol}");
+        let body = self.signed_get("/fapi/v2/positionRisk", query).await?;
+        let positions: Vec<PositionInfo> =
+            serde_json::from_value(body).context("unexpected positionRisk schema")?;
+        Ok(positions.into_iter().find(|p| p.qty() != 0.0))
+    }
+
+    /// Close any open position on `symbol` with an opposing reduce-only
+    /// market order, so a stale quantity cannot open the other way.
+    pub async fn close_all_positions(&self, symbol: &str) -> Result<()> {
+        if let Some(pos) = self.get_position(symbol).await? {
+            let qty = pos.qty();
+            let side = if qty > 0.0 { "SELL" } else { "BUY" };
+            self.close_market_order(symbol, side, qty.abs()).await?;
+            info!(symbol, qty, "flattened position");
+        }
+        Ok(())
+    }
+}
+
+impl PositionFlattener for LiveOrderClient {
+    async fn close_all_positions(&self, symbol: &str) -> Result<()> {
+        LiveOrderClient::close_all_positions(self, symbol).await
+    }
+}
+
+impl OrderExecutor for LiveOrderClient {
+    async fn market_order(&self, symbol: &str, side: &str, qty: f64) -> Result<()> {
+        LiveOrderClient::market_order(self, symbol, side, qty)
+            .await
+            .map(|_| ())
+    }
+
+    async fn set_leverage(&self, symbol: &str, leverage: u32) -> Result<()> {
+        LiveOrderClient::set_leverage(self, symbol, leverage).await
+    }
+
+    async fn get_position(&self, symbol: &str) -> Result<Option<PositionInfo>> {
+        LiveOrderClient::get_position(self, symbol).await
+    }
+}
+
+/// In-memory venue that fills market orders at the latest noted price and
+/// charges the taker fee, so live mode can dry-run without exchange keys.
+pub struct PaperExecutor {
+    taker_fee: f64,
+    state: std::sync::Mutex<PaperState>,
+}
+
+#[derive(Debug, Default)]
+struct PaperState {
+    balance: f64,
+    /// Signed base-asset quantity; positive is long.
+    position_qty: f64,
+    entry_price: f64,
+    mark_price: f64,
+}
+
+impl PaperExecutor {
+    pub fn new(starting_balance: f64, taker_fee: f64) -> Self {
+        Self {
+            taker_fee,
+            state: std::sync::Mutex::new(PaperState {
+                balance: starting_balance,
+                ..PaperState::default()
+            }),
+        }
+    }
+
+    /// Virtual balance after fees and realized PnL.
+    pub fn balance(&self) -> f64 {
+        self.state.lock().unwrap().balance
+    }
+}
+
+impl PositionFlattener for PaperExecutor {
+    async fn close_all_positions(&self, symbol: &str) -> Result<()> {
+        let qty = self.state.lock().unwrap().position_qty;
+        if qty != 0.0 {
+            let side = if qty > 0.0 { "SELL" } else { "BUY" };
+            OrderExecutor::market_order(self, symbol, side, qty.abs()).await?;
+        }
+        Ok(())
+    }
+}
+
+impl OrderExecutor for PaperExecutor {
+    async fn market_order(&self, symbol: &str, side: &str, qty: f64) -> Result<()> {
+        let mut s = self.state.lock().unwrap();
+        if s.mark_price <= 0.0 {
+            bail!("paper executor has no mark price yet");
+        }
+        let signed = match side {
+            "BUY" => qty,
+            "SELL" => -qty,
+            other => bail!("unknown order side {other:?}"),
+        };
+        let price = s.mark_price;
+        s.balance -= qty * price * self.taker_fee;
+        // Realize PnL on whatever part of the order reduces the position.
+        if s.position_qty != 0.0 && s.position_qty.signum() != signed.signum() {
+            let closed = qty.min(s.position_qty.abs());
+            s.balance += (price - s.entry_price) * closed * s.position_qty.signum();
+        }
+        let new_qty = s.position_qty + signed;
+        if new_qty == 0.0 {
+            s.entry_price = 0.0;
+        } else if s.position_qty == 0.0 || s.position_qty.signum() != new_qty.signum() {
+            s.entry_price = price;
+        } else if signed.signum() == s.position_qty.signum() {
+            // Adding to the position: blend the entry price.
+            s.entry_price = (s.entry_price * s.position_qty.abs() + price * qty)
+                / (s.position_qty.abs() + qty);
+        }
+        s.position_qty = new_qty;
+        info!(symbol, side, qty, price, balance = s.balance, "paper fill");
+        Ok(())
+    }
+
+    async fn set_leverage(&self, symbol: &str, leverage: u32) -> Result<()> {
+        // Margin is not modeled; accept and log so the runner code path
+        // matches the real venue.
+        info!(symbol, leverage, "paper leverage set");
+        Ok(())
+    }
+
+    async fn get_position(&self, symbol: &str) -> Result<Option<PositionInfo>> {
+        let s = self.state.lock().unwrap();
+        if s.position_qty == 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(PositionInfo {
+            symbol: symbol.to_string(),
+            position_amt: s.position_qty.to_string(),
+            entry_price: s.entry_price.to_string(),
+            unrealized_profit: ((s.mark_price - s.entry_price) * s.position_qty).to_string(),
+        }))
+    }
+
+    fn note_price(&self, price: f64) {
+        self.state.lock().unwrap().mark_price = price;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct RecordingClient {
+        closes: AtomicUsize,
+    }
+
+    impl PositionFlattener for RecordingClient {
+        async fn close_all_positions(&self, _symbol: &str) -> Result<()> {
+            self.closes.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_handler_flattens_exactly_once() {
+        let client = RecordingClient {
+            closes: AtomicUsize::new(0),
+        };
+        flatten_on_shutdown(&client, "BTCUSDT", 10_000.0).await;
+        assert_eq!(client.closes.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn shutdown_handler_swallows_client_errors() {
+        struct Failing;
+        impl PositionFlattener for Failing {
+            async fn close_all_positions(&self, _symbol: &str) -> Result<()> {
+                bail!("transport down")
+            }
+        }
+        // Must not panic or propagate: shutdown has nowhere to bubble to.
+        flatten_on_shutdown(&Failing, "BTCUSDT", 10_000.0).await;
+    }
+
+    #[test]
+    fn reconcile_flags_direction_drift_only() {
+        let exchange_long = PositionInfo {
+            symbol: "BTCUSDT".to_string(),
+            position_amt: "0.5".to_string(),
+            entry_price: "100".to_string(),
+            unrealized_profit: "0".to_string(),
+        };
+        assert!(position_matches(None, None));
+        assert!(position_matches(Some(Direction::Long), Some(&exchange_long)));
+        assert!(!position_matches(Some(Direction::Short), Some(&exchange_long)));
+        assert!(!position_matches(None, Some(&exchange_long)));
+        assert!(!position_matches(Some(Direction::Long), None));
+    }
+
+    #[test]
+    fn reduce_only_appears_only_when_requested() {
+        let close = order_query("BTCUSDT", "SELL", "MARKET", 0.5, None, true);
+        assert_eq!(
+            close,
+            "symbol=BTCUSDT&side=SELL&type=MARKET&quantity=0.5&reduceOnly=true"
+        );
+        let entry = order_query("BTCUSDT", "BUY", "MARKET", 0.5, None, false);
+        assert!(!entry.contains("reduceOnly"));
+
+        // The signed form of the close query still carries the flag and
+        // signs deterministically for a given key.
+        let client = LiveOrderClient::new("https://example.invalid", "key", "secret");
+        assert!(close.contains("reduceOnly=true"));
+        let sig = client.sign(&close);
+        assert_eq!(sig.len(), 64);
+        assert_eq!(sig, client.sign(&close));
+    }
+
+    #[test]
+    fn time_in_force_variants_render_their_fragments() {
+        let q = order_query(
+            "BTCUSDT",
+            "BUY",
+            "LIMIT",
+            1.0,
+            Some(TimeInForce::Gtd(1_700_000_000_000)),
+            false,
+        );
+        assert!(q.contains("timeInForce=GTD&goodTillDate=1700000000000"), "{q}");
+        for (tif, expect) in [
+            (TimeInForce::Gtc, "timeInForce=GTC"),
+            (TimeInForce::Ioc, "timeInForce=IOC"),
+            (TimeInForce::Fok, "timeInForce=FOK"),
+        ] {
+            let q = order_query("BTCUSDT", "BUY", "LIMIT", 1.0, Some(tif), false);
+            assert!(q.ends_with(expect), "{q}");
+        }
+        // MARKET orders pass no TIF at all.
+        let market = order_query("BTCUSDT", "BUY", "MARKET", 1.0, None, false);
+        assert!(!market.contains("timeInForce"));
+    }
+
+    #[test]
+    fn monitor_fires_when_price_crosses_a_stored_level() {
+        let m = LivePositionMonitor::new(
+            Direction::Long,
+            RiskLevels {
+                stop_loss: 95.0,
+                take_profit: 110.0,
+            },
+        );
+        assert_eq!(m.check(100.0), None);
+        assert_eq!(m.check(94.5), Some(ExitReason::StopLoss));
+        assert_eq!(m.check(111.0), Some(ExitReason::TakeProfit));
+    }
+
+    #[tokio::test]
+    async fn paper_round_trip_pays_taker_fees_on_both_legs() {
+        let exec = PaperExecutor::new(10_000.0, 0.0005);
+        exec.note_price(100.0);
+        OrderExecutor::market_order(&exec, "BTCUSDT", "BUY", 1.0)
+            .await
+            .unwrap();
+        exec.note_price(110.0);
+        OrderExecutor::market_order(&exec, "BTCUSDT", "SELL", 1.0)
+            .await
+            .unwrap();
+        // +10 realized, minus 5 bps of each leg's notional (100 and 110).
+        let expected = 10_000.0 + 10.0 - 0.0005 * (100.0 + 110.0);
+        assert!((exec.balance() - expected).abs() < 1e-9);
+        assert!(OrderExecutor::get_position(&exec, "BTCUSDT")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn margin_insufficient_retries_exactly_once_at_half_size() {
+        struct FlakyMargin {
+            calls: AtomicUsize,
+        }
+        impl PositionFlattener for FlakyMargin {
+            async fn close_all_positions(&self, _symbol: &str) -> Result<()> {
+                Ok(())
+            }
+        }
+        impl OrderExecutor for FlakyMargin {
+            async fn market_order(&self, _symbol: &str, _side: &str, _qty: f64) -> Result<()> {
+                if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(anyhow::Error::new(MarginInsufficient))
+                } else {
+                    Ok(())
+                }
+            }
+            async fn set_leverage(&self, _symbol: &str, _leverage: u32) -> Result<()> {
+                Ok(())
+            }
+            async fn get_position(&self, _symbol: &str) -> Result<Option<PositionInfo>> {
+                Ok(None)
+            }
+        }
+
+        let exec = FlakyMargin {
+            calls: AtomicUsize::new(0),
+        };
+        let filled = market_order_with_margin_retry(&exec, "BTCUSDT", "BUY", 1.0)
+            .await
+            .unwrap();
+        assert_eq!(exec.calls.load(Ordering::SeqCst), 2);
+        assert!((filled - 0.5).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn paper_flatten_closes_a_short() {
+        let exec = PaperExecutor::new(1_000.0, 0.0);
+        exec.note_price(50.0);
+        OrderExecutor::market_order(&exec, "ETHUSDT", "SELL", 2.0)
+            .await
+            .unwrap();
+        exec.note_price(45.0);
+        PositionFlattener::close_all_positions(&exec, "ETHUSDT")
+            .await
+            .unwrap();
+        assert!((exec.balance() - 1_010.0).abs() < 1e-9);
+        assert!(OrderExecutor::get_position(&exec, "ETHUSDT")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
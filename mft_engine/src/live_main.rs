@@ -0,0 +1,188 @@
+//! Live trading runner: warm the models on history, then consume closed
+//! bars from the WebSocket stream and route signals to the exchange.
+//!
+//! With API keys set, orders go to the Binance Futures **testnet**; without
+//! keys the runner drops into paper mode and fills against a local
+//! [`PaperExecutor`], so the full loop can be dry-run with no credentials.
+
+use anyhow::Result;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use mft_engine::config::AppConfig;
+use mft_engine::data::{BinanceDataClient, BinanceWsClient, WsConfig};
+use mft_engine::engine::StrategyEngine;
+use mft_engine::live::{self, LiveOrderClient, LivePositionMonitor, OrderExecutor, PaperExecutor};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+    let cfg = AppConfig::from_env();
+    if cfg.api_key.is_empty() || cfg.api_secret.is_empty() {
+        info!("BINANCE_API_KEY / BINANCE_API_SECRET not set, running in paper mode");
+        let executor = PaperExecutor::new(cfg.initial_capital, cfg.taker_fee);
+        return run_live(cfg, executor).await;
+    }
+
+    let order_client = LiveOrderClient::new(
+        "https://testnet.binancefuture.com",
+        &cfg.api_key,
+        &cfg.api_secret,
+    );
+    run_live(cfg, order_client).await
+}
+
+/// The live loop, generic over the venue so it runs unchanged against the
+/// exchange client or the paper simulator.
+async fn run_live<E: OrderExecutor>(cfg: AppConfig, order_client: E) -> Result<()> {
+    let data_client = BinanceDataClient::default();
+    order_client
+        .set_leverage(&cfg.symbol, cfg.leverage as u32)
+        .await?;
+
+    let mut engine = StrategyEngine::new(cfg.clone());
+    let mut shutdown = shutdown_watch();
+
+    // Warm the models on recent history before going live; the engine knows
+    // how much each model needs. Ctrl-C during warmup just exits: nothing is
+    // open yet.
+    let warmup_bars = engine.required_warmup();
+    let end = chrono::Utc::now().timestamp_millis();
+    let start = end - (warmup_bars as i64) * 60_000;
+    let history = tokio::select! {
+        res = data_client.fetch_klines(&cfg.symbol, &cfg.kline_interval, start, end) => res?,
+        _ = shutdown.changed() => {
+            info!("shutdown requested during warmup");
+            return Ok(());
+        }
+    };
+    for kline in &history {
+        engine.on_bar(kline);
+    }
+    info!(bars = history.len(), "warmup complete, entering live loop");
+
+    let mut last_open_time = history.last().map(|k| k.open_time).unwrap_or(0);
+
+    // The stream task only delivers closed bars (`k.x == true`) and
+    // reconnects with backoff on its own.
+    let ws = BinanceWsClient::new(WsConfig::default());
+    let mut bars = ws.stream_klines(&cfg.symbol, &cfg.kline_interval);
+
+    // Hard risk control independent of the models: the levels from the
+    // entry signal, checked against each bar's extremes.
+    let mut monitor: Option<LivePositionMonitor> = None;
+    let mut bars_since_reconcile = 0usize;
+
+    loop {
+        let kline = tokio::select! {
+            maybe = bars.recv() => match maybe {
+                Some(k) => k,
+                None => break,
+            },
+            _ = shutdown.changed() => {
+                info!("shutdown requested, flattening before exit");
+                break;
+            }
+        };
+        // Guard against replays after a reconnect.
+        if kline.open_time <= last_open_time {
+            continue;
+        }
+        last_open_time = kline.open_time;
+        order_client.note_price(kline.close);
+
+        let hard_exit = monitor
+            .as_ref()
+            .and_then(|m| m.check(kline.low).or_else(|| m.check(kline.high)));
+        if let Some(reason) = hard_exit.or_else(|| engine.check_exit(kline.close)) {
+            let pos = engine.position().expect("exit implies a position");
+            let side = match pos.direction {
+                mft_engine::engine::Direction::Long => "SELL",
+                mft_engine::engine::Direction::Short => "BUY",
+            };
+            let qty = position_qty(&cfg, pos.size_frac, kline.close);
+            match order_client.market_order(&cfg.symbol, side, qty).await {
+                Ok(_) => {
+                    let pnl = engine.close_position(kline.close);
+                    monitor = None;
+                    info!(?reason, ?pnl, equity = engine.equity, "position closed");
+                }
+                Err(e) => error!(error = %e, "close order failed"),
+            }
+        }
+
+        if let Some(mut signal) = engine.on_bar(&kline) {
+            let side = match signal.direction {
+                mft_engine::engine::Direction::Long => "BUY",
+                mft_engine::engine::Direction::Short => "SELL",
+            };
+            let qty = position_qty(&cfg, signal.size_frac, signal.price);
+            info!(z = signal.z_score, ev = signal.ev, side, qty, "entry signal");
+            match live::market_order_with_margin_retry(&order_client, &cfg.symbol, side, qty).await
+            {
+                Ok(filled_qty) => {
+                    // A margin retry halves the order, so keep the engine's
+                    // notion of size in step with what actually filled.
+                    signal.size_frac *= filled_qty / qty;
+                    engine.open_position(&signal);
+                    monitor = Some(LivePositionMonitor::new(signal.direction, signal.risk));
+                }
+                Err(e) => error!(error = %e, "entry order failed"),
+            }
+        }
+
+        // Realized equity only moves on closed trades; the marked figure
+        // folds in the open position's PnL at this bar's close.
+        info!(
+            equity = engine.equity,
+            marked = engine.marked_equity(kline.close),
+            "heartbeat"
+        );
+
+        // Periodically check that the engine and the exchange agree on
+        // what is open; a mismatch means state has drifted (manual
+        // intervention, a missed fill, a protective order firing).
+        bars_since_reconcile += 1;
+        if bars_since_reconcile >= RECONCILE_EVERY_BARS {
+            bars_since_reconcile = 0;
+            match order_client.get_position(&cfg.symbol).await {
+                Ok(exchange_pos) => {
+                    let engine_dir = engine.position().map(|p| p.direction);
+                    if !live::position_matches(engine_dir, exchange_pos.as_ref()) {
+                        warn!(
+                            ?engine_dir,
+                            exchange_qty = exchange_pos.as_ref().map(|p| p.qty()),
+                            "engine and exchange disagree on the open position"
+                        );
+                    }
+                }
+                Err(e) => error!(error = %e, "position reconcile failed"),
+            }
+        }
+    }
+
+    live::flatten_on_shutdown(&order_client, &cfg.symbol, engine.equity).await;
+    info!(equity = engine.equity, "live runner stopped");
+    Ok(())
+}
+
+/// Bars between engine-vs-exchange position reconciliations.
+const RECONCILE_EVERY_BARS: usize = 15;
+
+/// Listen for Ctrl-C once and flip a shared shutdown flag that both the
+/// warmup fetch and the live loop select against.
+fn shutdown_watch() -> watch::Receiver<bool> {
+    let (tx, rx) = watch::channel(false);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = tx.send(true);
+        }
+    });
+    rx
+}
+
+/// Convert a fractional size into a base-asset quantity.
+fn position_qty(cfg: &AppConfig, size_frac: f64, price: f64) -> f64 {
+    let notional = cfg.initial_capital * size_frac * cfg.leverage;
+    notional / price
+}
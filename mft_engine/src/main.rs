@@ -0,0 +1,51 @@
+//! Quick historical sanity-run: fetch recent klines from Binance and replay
+//! them through the engine, printing a metrics summary. For serious work use
+//! the `rust_backtest` crate; this binary exists for fast iteration on the
+//! models themselves.
+
+use anyhow::Result;
+use tracing::info;
+
+use mft_engine::config::AppConfig;
+use mft_engine::data::BinanceDataClient;
+use mft_engine::engine::StrategyEngine;
+use mft_engine::metrics::compute_metrics;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+    let cfg = AppConfig::from_env();
+    info!(symbol = %cfg.symbol, interval = %cfg.kline_interval, "fetching history");
+
+    let client = BinanceDataClient::default();
+    let end = chrono::Utc::now().timestamp_millis();
+    let start = end - 3 * 24 * 3600 * 1000; // three days
+    let klines = client
+        .fetch_klines(&cfg.symbol, &cfg.kline_interval, start, end)
+        .await?;
+    info!(bars = klines.len(), "history loaded");
+
+    let bars_per_year = mft_engine::data::bars_per_year(&cfg.kline_interval).unwrap_or(525_600.0);
+
+    let mut engine = StrategyEngine::new(cfg.clone());
+    let mut equity_curve = vec![1.0];
+    let mut trade_pnls = Vec::new();
+
+    for kline in &klines {
+        if let Some(reason) = engine.check_exit(kline.close) {
+            if let Some(pnl) = engine.close_position(kline.close) {
+                info!(?reason, pnl, "closed position");
+                trade_pnls.push(pnl);
+            }
+        }
+        if let Some(signal) = engine.on_bar(kline) {
+            info!(z = signal.z_score, ev = signal.ev, "signal");
+            engine.open_position(&signal);
+        }
+        equity_curve.push(engine.equity);
+    }
+
+    let report = compute_metrics(&equity_curve, &trade_pnls, bars_per_year);
+    println!("{report}");
+    Ok(())
+}
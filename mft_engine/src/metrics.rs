@@ -0,0 +1,729 @@
+//! Performance metrics computed from an equity curve and closed trades.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{ContinuousCDF, Normal};
+
+use crate::engine::ExitReason;
+
+/// How per-period returns are computed from the equity series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReturnBasis {
+    /// Arithmetic returns `E_t / E_{t-1} − 1` (the default).
+    #[default]
+    Simple,
+    /// Log returns `ln(E_t / E_{t-1})`. These aggregate additively, so they
+    /// do not bias the Sharpe upward when per-period volatility is high;
+    /// total return is recovered as `exp(Σr) − 1`.
+    Log,
+}
+
+/// Annualisation and benchmark inputs for [`compute_metrics_with`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Bars per year for the equity sampling frequency (525 600 for 1m).
+    pub bars_per_year: f64,
+    /// Annual risk-free rate (fraction); subtracted per-period from returns
+    /// before Sharpe/Sortino.
+    pub risk_free_rate: f64,
+    /// Basis for the per-period return series. Drawdown always works on
+    /// equity levels regardless.
+    pub return_basis: ReturnBasis,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            bars_per_year: 525_600.0,
+            risk_free_rate: 0.0,
+            return_basis: ReturnBasis::Simple,
+        }
+    }
+}
+
+/// Summary statistics for a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerfReport {
+    pub n_trades: usize,
+    pub win_rate: f64,
+    /// Total return over the run (fraction).
+    pub total_return: f64,
+    pub sharpe: f64,
+    /// Lo (2002) standard error of the per-period Sharpe:
+    /// `sqrt((1 + SR²/2) / n)`.
+    pub sharpe_se: f64,
+    /// One-sided p-value of the Sharpe against SR = 0 (normal CDF).
+    pub sharpe_pvalue: f64,
+    /// Sharpe recomputed with the Newey-West long-run variance (Bartlett
+    /// kernel, automatic lag), which deflates the ratio when returns are
+    /// positively autocorrelated. Matches `sharpe` for white-noise returns.
+    pub sharpe_nw: f64,
+    pub sortino: f64,
+    /// Annual return over max drawdown; 0.0 (not `inf`) with no drawdown.
+    pub calmar: f64,
+    /// Gross wins over gross losses; 0.0 (not `inf`) with no losses.
+    pub profit_factor: f64,
+    /// Maximum peak-to-trough drawdown (positive fraction).
+    pub max_drawdown: f64,
+    /// Mean PnL per trade (fraction of notional).
+    pub avg_trade_pnl: f64,
+    /// Mean maximum adverse excursion per trade; `None` when the run did
+    /// not record excursions. Attach via [`PerfReport::with_excursions`].
+    pub avg_mae: Option<f64>,
+    /// Mean maximum favorable excursion per trade.
+    pub avg_mfe: Option<f64>,
+    /// Longest run of losing trades in chronological order.
+    pub max_consecutive_losses: usize,
+    /// Longest run of winning trades in chronological order.
+    pub max_consecutive_wins: usize,
+    /// Mean length of losing streaks; `None` when the run had no losses.
+    pub avg_loss_streak_len: Option<f64>,
+    /// Closed trades per exit reason; empty when the run did not record
+    /// reasons. Attach via [`PerfReport::with_exit_reasons`].
+    pub exit_reason_counts: HashMap<ExitReason, usize>,
+    /// Mean net PnL fraction per exit reason.
+    pub avg_pnl_by_reason: HashMap<ExitReason, f64>,
+}
+
+impl PerfReport {
+    /// Attach per-trade excursion aggregates once MAE/MFE are known.
+    pub fn with_excursions(mut self, mae_fracs: &[f64], mfe_fracs: &[f64]) -> Self {
+        let mean = |xs: &[f64]| {
+            if xs.is_empty() {
+                None
+            } else {
+                Some(xs.iter().sum::<f64>() / xs.len() as f64)
+            }
+        };
+        self.avg_mae = mean(mae_fracs).filter(|v| v.is_finite());
+        self.avg_mfe = mean(mfe_fracs).filter(|v| v.is_finite());
+        self
+    }
+
+    /// Attach the exit-reason breakdown from `(reason, pnl_frac)` pairs,
+    /// one per closed trade.
+    pub fn with_exit_reasons(mut self, trades: &[(ExitReason, f64)]) -> Self {
+        let mut counts: HashMap<ExitReason, usize> = HashMap::new();
+        let mut sums: HashMap<ExitReason, f64> = HashMap::new();
+        for &(reason, pnl) in trades {
+            *counts.entry(reason).or_insert(0) += 1;
+            *sums.entry(reason).or_insert(0.0) += pnl;
+        }
+        self.avg_pnl_by_reason = sums
+            .into_iter()
+            .map(|(reason, sum)| (reason, sum / counts[&reason] as f64))
+            .collect();
+        self.exit_reason_counts = counts;
+        self
+    }
+}
+
+impl std::fmt::Display for PerfReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Trades:        {}", self.n_trades)?;
+        writeln!(f, "Win rate:      {:.1}%", self.win_rate * 100.0)?;
+        writeln!(f, "Total return:  {:.2}%", self.total_return * 100.0)?;
+        writeln!(
+            f,
+            "Sharpe:        {:.4} (p={:.2})",
+            self.sharpe, self.sharpe_pvalue
+        )?;
+        writeln!(f, "Sharpe (NW):   {:.4}", self.sharpe_nw)?;
+        writeln!(f, "Sortino:       {:.4}", self.sortino)?;
+        writeln!(f, "Calmar:        {:.4}", self.calmar)?;
+        writeln!(f, "Profit factor: {:.4}", self.profit_factor)?;
+        writeln!(f, "Max drawdown:  {:.2}%", self.max_drawdown * 100.0)?;
+        if !self.exit_reason_counts.is_empty() {
+            // Most frequent reason first, so the dominant exit leads.
+            let mut rows: Vec<_> = self.exit_reason_counts.iter().collect();
+            rows.sort_by(|a, b| {
+                b.1.cmp(a.1)
+                    .then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0)))
+            });
+            writeln!(f, "Exit reasons:")?;
+            for (reason, n) in rows {
+                let avg = self.avg_pnl_by_reason.get(reason).copied().unwrap_or(0.0);
+                writeln!(f, "  {reason:?}: {n} trades, avg PnL {:.4}%", avg * 100.0)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Division that cannot poison a report: 0.0 instead of `inf`/`NaN` when
+/// the denominator vanishes or either operand is non-finite.
+pub fn safe_div(num: f64, den: f64) -> f64 {
+    let q = num / den;
+    if q.is_finite() {
+        q
+    } else {
+        0.0
+    }
+}
+
+/// Maximum peak-to-trough drawdown of `equity`, as a positive fraction.
+pub fn max_drawdown(equity: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut max_dd = 0.0;
+    for &e in equity {
+        if e > peak {
+            peak = e;
+        }
+        let dd = safe_div(peak - e, peak);
+        if dd > max_dd {
+            max_dd = dd;
+        }
+    }
+    max_dd
+}
+
+/// Per-period simple returns of an equity series. A zero equity point
+/// yields a −100% return for the period rather than an infinite one.
+pub fn returns(equity: &[f64]) -> Vec<f64> {
+    equity
+        .windows(2)
+        .map(|w| safe_div(w[1], w[0]) - 1.0)
+        .collect()
+}
+
+/// Per-period log returns of an equity series.
+pub fn log_returns(equity: &[f64]) -> Vec<f64> {
+    equity.windows(2).map(|w| (w[1] / w[0]).ln()).collect()
+}
+
+/// Rolling per-period Sharpe over trailing `window` returns. Entries before
+/// the window fills are NaN; output length matches `rets`.
+pub fn rolling_sharpe(rets: &[f64], window: usize) -> Vec<f64> {
+    let mut out = vec![f64::NAN; rets.len()];
+    if window < 2 {
+        return out;
+    }
+    for i in (window - 1)..rets.len() {
+        let w = &rets[i + 1 - window..=i];
+        let n = w.len() as f64;
+        let mean = w.iter().sum::<f64>() / n;
+        let var = w.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        let sd = var.sqrt();
+        out[i] = if sd > 0.0 { mean / sd } else { f64::NAN };
+    }
+    out
+}
+
+/// Rolling maximum drawdown over a trailing `window` of equity points.
+/// Entries before the window fills are NaN; output length matches `equity`.
+pub fn rolling_max_drawdown(equity: &[f64], window: usize) -> Vec<f64> {
+    let mut out = vec![f64::NAN; equity.len()];
+    if window == 0 {
+        return out;
+    }
+    for i in (window - 1)..equity.len() {
+        out[i] = max_drawdown(&equity[i + 1 - window..=i]);
+    }
+    out
+}
+
+/// Per-period Sharpe of `rets` using the Newey-West (HAC) long-run
+/// variance with Bartlett weights over `lag` autocovariances:
+///
+/// `lrv = γ₀ + 2 Σ_{j=1..lag} (1 − j/(lag+1)) γ_j`
+///
+/// Positive autocorrelation inflates `lrv` above the naive variance and
+/// deflates the ratio accordingly. Returns 0 for degenerate inputs.
+pub fn sharpe_newey_west(rets: &[f64], lag: usize) -> f64 {
+    let n = rets.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let nf = n as f64;
+    let mean = rets.iter().sum::<f64>() / nf;
+    let gamma = |j: usize| -> f64 {
+        rets[j..]
+            .iter()
+            .zip(rets)
+            .map(|(a, b)| (a - mean) * (b - mean))
+            .sum::<f64>()
+            / nf
+    };
+    let mut lrv = gamma(0);
+    for j in 1..=lag.min(n - 1) {
+        let w = 1.0 - j as f64 / (lag + 1) as f64;
+        lrv += 2.0 * w * gamma(j);
+    }
+    if lrv > 0.0 {
+        mean / lrv.sqrt()
+    } else {
+        0.0
+    }
+}
+
+/// Newey-West automatic lag, `⌊4 (n/100)^{2/9}⌋`.
+fn newey_west_lag(n: usize) -> usize {
+    (4.0 * (n as f64 / 100.0).powf(2.0 / 9.0)).floor() as usize
+}
+
+/// Compute the full report with `risk_free_rate = 0` (see
+/// [`compute_metrics_with`]).
+pub fn compute_metrics(equity: &[f64], trade_pnls: &[f64], bars_per_year: f64) -> PerfReport {
+    compute_metrics_with(
+        equity,
+        trade_pnls,
+        &MetricsConfig {
+            bars_per_year,
+            ..MetricsConfig::default()
+        },
+    )
+}
+
+/// Compute the full report.
+///
+/// `equity` is the per-bar equity series, `trade_pnls` the per-trade net PnL
+/// fractions. Sharpe and Sortino are computed on returns in excess of the
+/// per-period risk-free rate (`cfg.risk_free_rate / cfg.bars_per_year`).
+pub fn compute_metrics_with(
+    equity: &[f64],
+    trade_pnls: &[f64],
+    cfg: &MetricsConfig,
+) -> PerfReport {
+    let bars_per_year = cfg.bars_per_year;
+    let rf_period = cfg.risk_free_rate / bars_per_year;
+    let rets = match cfg.return_basis {
+        ReturnBasis::Simple => returns(equity),
+        ReturnBasis::Log => log_returns(equity),
+    };
+    let excess: Vec<f64> = rets.iter().map(|r| r - rf_period).collect();
+    let n = excess.len() as f64;
+    let mean = if n > 0.0 { excess.iter().sum::<f64>() / n } else { 0.0 };
+    let var = if n > 1.0 {
+        excess.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)
+    } else {
+        0.0
+    };
+    let sd = var.sqrt();
+    let downside_var = if n > 1.0 {
+        excess
+            .iter()
+            .filter(|r| **r < 0.0)
+            .map(|r| r * r)
+            .sum::<f64>()
+            / (n - 1.0)
+    } else {
+        0.0
+    };
+    let downside_sd = downside_var.sqrt();
+
+    let sharpe = if sd > 0.0 { mean / sd * bars_per_year.sqrt() } else { 0.0 };
+    let sharpe_nw =
+        sharpe_newey_west(&excess, newey_west_lag(excess.len())) * bars_per_year.sqrt();
+    let sortino = if downside_sd > 0.0 {
+        mean / downside_sd * bars_per_year.sqrt()
+    } else {
+        0.0
+    };
+
+    // Lo (2002): SE of the *per-period* Sharpe; the significance test is
+    // scale-free so annualisation does not enter.
+    let sr_period = if sd > 0.0 { mean / sd } else { 0.0 };
+    let (sharpe_se, sharpe_pvalue) = if n > 1.0 && sd > 0.0 {
+        let se = ((1.0 + 0.5 * sr_period * sr_period) / n).sqrt();
+        let normal = Normal::new(0.0, 1.0).expect("unit normal");
+        (se, 1.0 - normal.cdf(sr_period / se))
+    } else {
+        // A degenerate series carries no evidence against SR = 0.
+        (0.0, 1.0)
+    };
+
+    let total_return = if equity.is_empty() {
+        0.0
+    } else {
+        match cfg.return_basis {
+            ReturnBasis::Simple => equity[equity.len() - 1] / equity[0] - 1.0,
+            // Log returns aggregate additively; this equals the level ratio.
+            ReturnBasis::Log => rets.iter().sum::<f64>().exp_m1(),
+        }
+    };
+    let max_dd = max_drawdown(equity);
+    let years = n / bars_per_year;
+    let annual_return = if years > 0.0 {
+        (1.0 + total_return).powf(1.0 / years) - 1.0
+    } else {
+        0.0
+    };
+    let calmar = safe_div(annual_return, max_dd);
+
+    let n_trades = trade_pnls.len();
+    let wins = trade_pnls.iter().filter(|p| **p > 0.0).count();
+    let gross_win: f64 = trade_pnls.iter().filter(|p| **p > 0.0).sum();
+    let gross_loss: f64 = -trade_pnls.iter().filter(|p| **p < 0.0).sum::<f64>();
+    let profit_factor = safe_div(gross_win, gross_loss);
+    let win_rate = if n_trades > 0 {
+        wins as f64 / n_trades as f64
+    } else {
+        0.0
+    };
+    let avg_trade_pnl = if n_trades > 0 {
+        trade_pnls.iter().sum::<f64>() / n_trades as f64
+    } else {
+        0.0
+    };
+
+    // Streaks over the chronological trade sequence; a win is any trade with
+    // positive net PnL, consistent with `win_rate` above.
+    let mut max_consecutive_wins = 0usize;
+    let mut max_consecutive_losses = 0usize;
+    let mut loss_streaks: Vec<usize> = Vec::new();
+    let mut run = 0usize;
+    let mut run_is_win = false;
+    for &p in trade_pnls {
+        let is_win = p > 0.0;
+        if run > 0 && is_win == run_is_win {
+            run += 1;
+        } else {
+            if run > 0 && !run_is_win {
+                loss_streaks.push(run);
+            }
+            run = 1;
+            run_is_win = is_win;
+        }
+        if is_win {
+            max_consecutive_wins = max_consecutive_wins.max(run);
+        } else {
+            max_consecutive_losses = max_consecutive_losses.max(run);
+        }
+    }
+    if run > 0 && !run_is_win {
+        loss_streaks.push(run);
+    }
+    let avg_loss_streak_len = if loss_streaks.is_empty() {
+        None
+    } else {
+        Some(loss_streaks.iter().sum::<usize>() as f64 / loss_streaks.len() as f64)
+    };
+
+    let mut report = PerfReport {
+        n_trades,
+        win_rate,
+        total_return,
+        sharpe,
+        sharpe_se,
+        sharpe_pvalue,
+        sharpe_nw,
+        sortino,
+        calmar,
+        profit_factor,
+        max_drawdown: max_dd,
+        avg_trade_pnl,
+        avg_mae: None,
+        avg_mfe: None,
+        max_consecutive_losses,
+        max_consecutive_wins,
+        avg_loss_streak_len,
+        exit_reason_counts: HashMap::new(),
+        avg_pnl_by_reason: HashMap::new(),
+    };
+    sanitize_report(&mut report);
+    report
+}
+
+/// Last line of defense against pathological inputs: replace any non-finite
+/// headline statistic with 0.0 (the p-value with 1.0, its "no evidence"
+/// end) so serialized reports never carry `inf`/`NaN`, and log which fields
+/// were hit.
+fn sanitize_report(report: &mut PerfReport) {
+    let mut hit: Vec<&'static str> = Vec::new();
+    for (name, value) in [
+        ("win_rate", &mut report.win_rate),
+        ("total_return", &mut report.total_return),
+        ("sharpe", &mut report.sharpe),
+        ("sharpe_se", &mut report.sharpe_se),
+        ("sharpe_nw", &mut report.sharpe_nw),
+        ("sortino", &mut report.sortino),
+        ("calmar", &mut report.calmar),
+        ("profit_factor", &mut report.profit_factor),
+        ("max_drawdown", &mut report.max_drawdown),
+        ("avg_trade_pnl", &mut report.avg_trade_pnl),
+    ] {
+        if !value.is_finite() {
+            *value = 0.0;
+            hit.push(name);
+        }
+    }
+    if !report.sharpe_pvalue.is_finite() {
+        report.sharpe_pvalue = 1.0;
+        hit.push("sharpe_pvalue");
+    }
+    if !hit.is_empty() {
+        tracing::warn!(fields = ?hit, "non-finite metrics sanitized");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drawdown_of_monotone_series_is_zero() {
+        assert_eq!(max_drawdown(&[1.0, 1.1, 1.2]), 0.0);
+    }
+
+    #[test]
+    fn drawdown_catches_trough() {
+        let dd = max_drawdown(&[1.0, 1.2, 0.9, 1.1]);
+        assert!((dd - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn last_rolling_sharpe_matches_full_window_metrics() {
+        let mut equity = vec![1.0];
+        for i in 0..60 {
+            let r = if i % 3 == 0 { 0.002 } else { -0.0005 };
+            equity.push(equity.last().unwrap() * (1.0 + r));
+        }
+        let rets = returns(&equity);
+        let rolling = rolling_sharpe(&rets, 30);
+        assert!(rolling[28].is_nan());
+        assert!(rolling[29].is_finite());
+
+        // The last rolling value equals a full compute_metrics call over
+        // the trailing 30-return window (bars_per_year = 1 keeps the
+        // per-period scale).
+        let tail = &equity[equity.len() - 31..];
+        let full = compute_metrics(tail, &[], 1.0);
+        assert!((rolling.last().unwrap() - full.sharpe).abs() < 1e-12);
+    }
+
+    #[test]
+    fn last_rolling_drawdown_matches_windowed_max_drawdown() {
+        let equity: Vec<f64> = (0..50)
+            .map(|i| 1.0 + 0.1 * ((i as f64) * 0.7).sin())
+            .collect();
+        let rolling = rolling_max_drawdown(&equity, 20);
+        assert!(rolling[18].is_nan());
+        let expected = max_drawdown(&equity[equity.len() - 20..]);
+        assert!((rolling.last().unwrap() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sharpe_se_follows_lo_formula() {
+        // 100 alternating returns with a slight positive tilt.
+        let mut equity = vec![1.0];
+        for i in 0..100 {
+            let r = if i % 2 == 0 { 0.0012 } else { -0.001 };
+            equity.push(equity.last().unwrap() * (1.0 + r));
+        }
+        let report = compute_metrics(&equity, &[], 525_600.0);
+
+        let rets = returns(&equity);
+        let n = rets.len() as f64;
+        let mean = rets.iter().sum::<f64>() / n;
+        let sd = (rets.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        let sr = mean / sd;
+        let expected_se = ((1.0 + 0.5 * sr * sr) / n).sqrt();
+        assert!((report.sharpe_se - expected_se).abs() < 1e-12);
+    }
+
+    #[test]
+    fn near_zero_mean_is_not_significant() {
+        let mut equity = vec![1.0];
+        for i in 0..100 {
+            let r = if i % 2 == 0 { 0.001 } else { -0.001 };
+            equity.push(equity.last().unwrap() * (1.0 + r));
+        }
+        let report = compute_metrics(&equity, &[], 525_600.0);
+        assert!(report.sharpe_pvalue > 0.4, "p = {}", report.sharpe_pvalue);
+    }
+
+    #[test]
+    fn earning_exactly_the_risk_free_rate_has_zero_sharpe() {
+        let cfg = MetricsConfig {
+            bars_per_year: 365.0,
+            risk_free_rate: 0.05,
+            ..MetricsConfig::default()
+        };
+        // Returns oscillating symmetrically around the per-period risk-free
+        // rate: mean excess return is zero, so Sharpe must be ~0 (where with
+        // rf = 0 the same series scores clearly positive).
+        let rf_period = cfg.risk_free_rate / cfg.bars_per_year;
+        let mut equity = vec![1.0];
+        for i in 0..50 {
+            let e = if i % 2 == 0 { 1e-3 } else { -1e-3 };
+            equity.push(equity.last().unwrap() * (1.0 + rf_period + e));
+        }
+        let report = compute_metrics_with(&equity, &[], &cfg);
+        assert!(report.sharpe.abs() < 1e-6, "sharpe = {}", report.sharpe);
+
+        let zero_rf = compute_metrics(&equity, &[], 365.0);
+        assert!(zero_rf.sharpe > 0.01);
+    }
+
+    #[test]
+    fn excursion_aggregates_attach_to_the_report() {
+        let report = compute_metrics(&[1.0, 1.01], &[0.01], 525_600.0);
+        assert!(report.avg_mae.is_none());
+        let report = report.with_excursions(&[-0.02, -0.04], &[0.01, 0.03]);
+        assert_eq!(report.avg_mae, Some(-0.03));
+        assert_eq!(report.avg_mfe, Some(0.02));
+    }
+
+    #[test]
+    fn positive_autocorrelation_deflates_the_nw_sharpe() {
+        // A slow sine around a positive mean: strongly positively
+        // autocorrelated at small lags, deterministic.
+        let rets: Vec<f64> = (0..400)
+            .map(|t| 0.001 + 0.002 * ((t as f64) / 10.0).sin())
+            .collect();
+        let n = rets.len() as f64;
+        let mean = rets.iter().sum::<f64>() / n;
+        let sd = (rets.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+        let naive = mean / sd;
+
+        let nw = sharpe_newey_west(&rets, 10);
+        assert!(nw > 0.0);
+        assert!(nw < naive, "nw = {nw}, naive = {naive}");
+
+        // Alternating (negatively autocorrelated) returns go the other way.
+        let alt: Vec<f64> = (0..400)
+            .map(|t| if t % 2 == 0 { 0.002 } else { -0.001 })
+            .collect();
+        let mean = alt.iter().sum::<f64>() / n;
+        let sd = (alt.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+        assert!(sharpe_newey_west(&alt, 10) > mean / sd);
+
+        assert_eq!(sharpe_newey_west(&[], 5), 0.0);
+        assert_eq!(sharpe_newey_west(&[0.01], 5), 0.0);
+    }
+
+    #[test]
+    fn report_carries_the_nw_sharpe() {
+        let mut equity = vec![1.0];
+        for t in 0..200 {
+            let r = 0.0005 + 0.002 * ((t as f64) / 10.0).sin();
+            equity.push(equity.last().unwrap() * (1.0 + r));
+        }
+        let report = compute_metrics(&equity, &[], 525_600.0);
+        assert!(report.sharpe_nw.is_finite());
+        assert!(report.sharpe_nw < report.sharpe);
+    }
+
+    #[test]
+    fn streaks_track_the_longest_runs() {
+        // W L L L W W: max loss streak 3, max win streak 2, one loss streak.
+        let pnls = [0.01, -0.01, -0.02, -0.01, 0.02, 0.01];
+        let report = compute_metrics(&[1.0, 1.01], &pnls, 525_600.0);
+        assert_eq!(report.max_consecutive_losses, 3);
+        assert_eq!(report.max_consecutive_wins, 2);
+        assert_eq!(report.avg_loss_streak_len, Some(3.0));
+
+        // L W L: two loss streaks of length 1 each.
+        let report = compute_metrics(&[1.0, 1.01], &[-0.01, 0.01, -0.01], 525_600.0);
+        assert_eq!(report.max_consecutive_losses, 1);
+        assert_eq!(report.avg_loss_streak_len, Some(1.0));
+
+        let report = compute_metrics(&[1.0, 1.01], &[0.01, 0.02], 525_600.0);
+        assert_eq!(report.max_consecutive_losses, 0);
+        assert!(report.avg_loss_streak_len.is_none());
+    }
+
+    #[test]
+    fn exit_breakdown_counts_and_averages_per_reason() {
+        let trades = [
+            (ExitReason::TakeProfit, 0.01),
+            (ExitReason::TakeProfit, 0.02),
+            (ExitReason::StopLoss, -0.005),
+        ];
+        let report = compute_metrics(&[1.0, 1.01], &[0.01, 0.02, -0.005], 525_600.0)
+            .with_exit_reasons(&trades);
+        assert_eq!(report.exit_reason_counts[&ExitReason::TakeProfit], 2);
+        assert_eq!(report.exit_reason_counts[&ExitReason::StopLoss], 1);
+        assert!((report.avg_pnl_by_reason[&ExitReason::TakeProfit] - 0.015).abs() < 1e-12);
+
+        let printed = report.to_string();
+        assert!(printed.contains("Exit reasons:"));
+        assert!(printed.contains("TakeProfit: 2 trades"));
+        // Reports that never attached reasons stay quiet about them.
+        let bare = compute_metrics(&[1.0, 1.01], &[0.01], 525_600.0);
+        assert!(!bare.to_string().contains("Exit reasons:"));
+    }
+
+    #[test]
+    fn log_basis_is_flat_on_a_double_then_halve_path() {
+        let equity = [1.0, 2.0, 1.0];
+        let logs = log_returns(&equity);
+        assert!(logs.iter().sum::<f64>().abs() < 1e-12);
+
+        let cfg = MetricsConfig {
+            return_basis: ReturnBasis::Log,
+            ..MetricsConfig::default()
+        };
+        let log_report = compute_metrics_with(&equity, &[], &cfg);
+        assert!(log_report.total_return.abs() < 1e-12);
+        assert!(log_report.sharpe.abs() < 1e-12);
+        // Drawdown stays on equity levels: halving off the peak is −50%.
+        assert!((log_report.max_drawdown - 0.5).abs() < 1e-12);
+
+        // The simple basis averages +100% and −50% to a positive mean, so
+        // the same flat path scores a positive Sharpe.
+        let simple = compute_metrics(&equity, &[], 525_600.0);
+        assert!(simple.total_return.abs() < 1e-12);
+        assert!(simple.sharpe > 0.0);
+    }
+
+    /// Every f64 field of the report, for finiteness sweeps.
+    fn all_stats(r: &PerfReport) -> Vec<(&'static str, f64)> {
+        vec![
+            ("win_rate", r.win_rate),
+            ("total_return", r.total_return),
+            ("sharpe", r.sharpe),
+            ("sharpe_se", r.sharpe_se),
+            ("sharpe_pvalue", r.sharpe_pvalue),
+            ("sharpe_nw", r.sharpe_nw),
+            ("sortino", r.sortino),
+            ("calmar", r.calmar),
+            ("profit_factor", r.profit_factor),
+            ("max_drawdown", r.max_drawdown),
+            ("avg_trade_pnl", r.avg_trade_pnl),
+        ]
+    }
+
+    #[test]
+    fn zero_loss_run_has_finite_profit_factor_and_calmar() {
+        // Monotone equity and all-winning trades: both the profit-factor and
+        // calmar denominators vanish.
+        let report = compute_metrics(&[1.0, 1.01, 1.02], &[0.01, 0.02], 525_600.0);
+        assert_eq!(report.profit_factor, 0.0);
+        assert_eq!(report.calmar, 0.0);
+        for (name, v) in all_stats(&report) {
+            assert!(v.is_finite(), "{name} = {v}");
+        }
+    }
+
+    #[test]
+    fn zero_equity_points_leak_no_inf_or_nan() {
+        // A wiped-out account mid-series would divide by zero everywhere.
+        let report = compute_metrics(&[1.0, 0.0, 1.0], &[], 525_600.0);
+        for (name, v) in all_stats(&report) {
+            assert!(v.is_finite(), "{name} = {v}");
+        }
+        // The degenerate significance test reports "no evidence", not NaN.
+        let flat = compute_metrics(&[1.0, 1.0, 1.0], &[], 525_600.0);
+        assert_eq!(flat.sharpe_se, 0.0);
+        assert_eq!(flat.sharpe_pvalue, 1.0);
+    }
+
+    #[test]
+    fn safe_div_never_returns_non_finite() {
+        assert_eq!(safe_div(1.0, 2.0), 0.5);
+        assert_eq!(safe_div(1.0, 0.0), 0.0);
+        assert_eq!(safe_div(0.0, 0.0), 0.0);
+        assert_eq!(safe_div(f64::NAN, 1.0), 0.0);
+        assert_eq!(safe_div(f64::INFINITY, 2.0), 0.0);
+    }
+
+    #[test]
+    fn win_rate_counts_positive_trades() {
+        let report = compute_metrics(&[1.0, 1.01, 1.0], &[0.01, -0.005, 0.002], 525_600.0);
+        assert_eq!(report.n_trades, 3);
+        assert!((report.win_rate - 2.0 / 3.0).abs() < 1e-12);
+    }
+}
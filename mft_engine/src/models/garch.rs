@@ -0,0 +1,312 @@
+//! GARCH(1,1) conditional-volatility filter.
+//!
+//! σ²_t = ω + α·ε²_{t-1} + β·σ²_{t-1}, updated once per bar from log returns.
+//! The engine uses the one-step-ahead σ forecast to scale expected-value and
+//! stop distances with the volatility regime.
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Which conditional-volatility recursion to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GarchKind {
+    /// Symmetric GARCH(1,1) (the default).
+    Garch,
+    /// GJR-GARCH(1,1): negative shocks carry an extra `gamma` loading.
+    Gjr,
+}
+
+/// How σ²_0 is seeded before the recursion has consumed any returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GarchInit {
+    /// Seed from the data: the first squared return (the historical
+    /// default).
+    Sample,
+    /// Seed from the model's unconditional variance — ω/(1−α−β) for the
+    /// symmetric recursion, ω/(1−α−γ/2−β) for GJR. Non-stationary
+    /// parameters have no unconditional variance; construction falls
+    /// back to `Sample` with a warning.
+    Unconditional,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GarchModel {
+    pub omega: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    sigma2: f64,
+    n_obs: usize,
+    /// Whether σ² was pre-seeded at construction, so the first return
+    /// runs the recursion instead of seeding.
+    seeded: bool,
+}
+
+impl GarchModel {
+    pub fn new(omega: f64, alpha: f64, beta: f64) -> Self {
+        Self {
+            omega,
+            alpha,
+            beta,
+            sigma2: 0.0,
+            n_obs: 0,
+            seeded: false,
+        }
+    }
+
+    /// Like [`new`](Self::new), with an explicit σ²_0 policy.
+    pub fn with_init(omega: f64, alpha: f64, beta: f64, init: GarchInit) -> Self {
+        let mut model = Self::new(omega, alpha, beta);
+        if init == GarchInit::Unconditional {
+            if alpha + beta >= 1.0 {
+                warn!(
+                    alpha,
+                    beta,
+                    "non-stationary GARCH parameters have no unconditional \
+                     variance; seeding sigma^2 from the sample instead"
+                );
+            } else {
+                model.sigma2 = omega / (1.0 - alpha - beta);
+                model.seeded = true;
+            }
+        }
+        model
+    }
+
+    /// Feed one return; returns the updated conditional σ (per bar).
+    pub fn update(&mut self, ret: f64) -> f64 {
+        if self.n_obs == 0 && !self.seeded {
+            // Seed the recursion with the first squared return.
+            self.sigma2 = ret * ret;
+        } else {
+            self.sigma2 = self.omega + self.alpha * ret * ret + self.beta * self.sigma2;
+        }
+        self.n_obs += 1;
+        self.sigma()
+    }
+
+    /// Current conditional standard deviation (per bar).
+    pub fn sigma(&self) -> f64 {
+        self.sigma2.sqrt()
+    }
+
+    /// Number of returns consumed so far.
+    pub fn n_obs(&self) -> usize {
+        self.n_obs
+    }
+
+    /// Whether the recursion has had a minimal burn-in.
+    pub fn is_warm(&self) -> bool {
+        self.n_obs >= 20
+    }
+}
+
+/// GJR-GARCH(1,1): σ²_t = ω + (α + γ·I[ε<0])·ε²_{t-1} + β·σ²_{t-1}.
+///
+/// The `gamma` term captures the leverage effect — down-moves raise
+/// conditional volatility more than up-moves of the same size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GjrGarch {
+    pub omega: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub gamma: f64,
+    sigma2: f64,
+    n_obs: usize,
+    /// Whether σ² was pre-seeded at construction, so the first return
+    /// runs the recursion instead of seeding.
+    seeded: bool,
+}
+
+impl GjrGarch {
+    pub fn new(omega: f64, alpha: f64, beta: f64, gamma: f64) -> Self {
+        Self {
+            omega,
+            alpha,
+            beta,
+            gamma,
+            sigma2: 0.0,
+            n_obs: 0,
+            seeded: false,
+        }
+    }
+
+    /// Like [`new`](Self::new), with an explicit σ²_0 policy. The GJR
+    /// unconditional variance is ω/(1−α−γ/2−β), the γ term loading half
+    /// the time under symmetric shocks.
+    pub fn with_init(omega: f64, alpha: f64, beta: f64, gamma: f64, init: GarchInit) -> Self {
+        let mut model = Self::new(omega, alpha, beta, gamma);
+        if init == GarchInit::Unconditional {
+            let persistence = alpha + gamma / 2.0 + beta;
+            if persistence >= 1.0 {
+                warn!(
+                    alpha,
+                    beta,
+                    gamma,
+                    "non-stationary GJR-GARCH parameters have no unconditional \
+                     variance; seeding sigma^2 from the sample instead"
+                );
+            } else {
+                model.sigma2 = omega / (1.0 - persistence);
+                model.seeded = true;
+            }
+        }
+        model
+    }
+
+    /// Feed one return; returns the updated conditional σ (per bar).
+    pub fn update(&mut self, ret: f64) -> f64 {
+        if self.n_obs == 0 && !self.seeded {
+            // Seed the recursion with the first squared return.
+            self.sigma2 = ret * ret;
+        } else {
+            let arch = if ret < 0.0 {
+                self.alpha + self.gamma
+            } else {
+                self.alpha
+            };
+            self.sigma2 = self.omega + arch * ret * ret + self.beta * self.sigma2;
+        }
+        self.n_obs += 1;
+        self.sigma()
+    }
+
+    /// Current conditional standard deviation (per bar).
+    pub fn sigma(&self) -> f64 {
+        self.sigma2.sqrt()
+    }
+
+    /// Number of returns consumed so far.
+    pub fn n_obs(&self) -> usize {
+        self.n_obs
+    }
+
+    /// Whether the recursion has had a minimal burn-in.
+    pub fn is_warm(&self) -> bool {
+        self.n_obs >= 20
+    }
+}
+
+/// The config-selected volatility model, dispatching to one recursion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum VolModel {
+    Garch(GarchModel),
+    Gjr(GjrGarch),
+}
+
+impl VolModel {
+    pub fn new(kind: GarchKind, omega: f64, alpha: f64, beta: f64, gamma: f64) -> Self {
+        Self::with_init(kind, omega, alpha, beta, gamma, GarchInit::Sample)
+    }
+
+    /// Like [`new`](Self::new), with an explicit σ²_0 policy.
+    pub fn with_init(
+        kind: GarchKind,
+        omega: f64,
+        alpha: f64,
+        beta: f64,
+        gamma: f64,
+        init: GarchInit,
+    ) -> Self {
+        match kind {
+            GarchKind::Garch => VolModel::Garch(GarchModel::with_init(omega, alpha, beta, init)),
+            GarchKind::Gjr => VolModel::Gjr(GjrGarch::with_init(omega, alpha, beta, gamma, init)),
+        }
+    }
+
+    /// Feed one return; returns the updated conditional σ (per bar).
+    pub fn update(&mut self, ret: f64) -> f64 {
+        match self {
+            VolModel::Garch(g) => g.update(ret),
+            VolModel::Gjr(g) => g.update(ret),
+        }
+    }
+
+    /// Current conditional standard deviation (per bar).
+    pub fn sigma(&self) -> f64 {
+        match self {
+            VolModel::Garch(g) => g.sigma(),
+            VolModel::Gjr(g) => g.sigma(),
+        }
+    }
+
+    /// Number of returns consumed so far.
+    pub fn n_obs(&self) -> usize {
+        match self {
+            VolModel::Garch(g) => g.n_obs(),
+            VolModel::Gjr(g) => g.n_obs(),
+        }
+    }
+
+    /// Whether the recursion has had a minimal burn-in.
+    pub fn is_warm(&self) -> bool {
+        match self {
+            VolModel::Garch(g) => g.is_warm(),
+            VolModel::Gjr(g) => g.is_warm(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vol_clusters_after_shock() {
+        let mut g = GarchModel::new(1e-6, 0.1, 0.85);
+        for _ in 0..50 {
+            g.update(0.0001);
+        }
+        let calm = g.sigma();
+        g.update(0.05); // large shock
+        let shocked = g.sigma();
+        assert!(shocked > calm * 2.0, "calm={calm} shocked={shocked}");
+    }
+
+    #[test]
+    fn gjr_amplifies_negative_shocks() {
+        let mut down = GjrGarch::new(1e-6, 0.08, 0.85, 0.1);
+        let mut up = GjrGarch::new(1e-6, 0.08, 0.85, 0.1);
+        for _ in 0..50 {
+            down.update(0.0001);
+            up.update(0.0001);
+        }
+        let sigma_down = down.update(-0.02);
+        let sigma_up = up.update(0.02);
+        assert!(
+            sigma_down > sigma_up,
+            "down={sigma_down} up={sigma_up}"
+        );
+    }
+
+    #[test]
+    fn unconditional_init_matches_the_stationary_variance() {
+        let g = GarchModel::with_init(1e-6, 0.1, 0.85, GarchInit::Unconditional);
+        let expected = (1e-6_f64 / (1.0 - 0.1 - 0.85)).sqrt();
+        assert!((g.sigma() - expected).abs() < 1e-15);
+
+        let gjr = GjrGarch::with_init(1e-6, 0.08, 0.85, 0.1, GarchInit::Unconditional);
+        let expected = (1e-6_f64 / (1.0 - 0.08 - 0.05 - 0.85)).sqrt();
+        assert!((gjr.sigma() - expected).abs() < 1e-15);
+
+        // Non-stationary parameters fall back to the sample seed: σ stays
+        // zero until the first return arrives.
+        let mut ns = GarchModel::with_init(1e-6, 0.3, 0.75, GarchInit::Unconditional);
+        assert_eq!(ns.sigma(), 0.0);
+        ns.update(0.01);
+        assert!((ns.sigma() - 0.01).abs() < 1e-15);
+    }
+
+    #[test]
+    fn gjr_with_zero_gamma_matches_plain_garch() {
+        let mut plain = VolModel::new(GarchKind::Garch, 1e-6, 0.08, 0.85, 0.0);
+        let mut gjr = VolModel::new(GarchKind::Gjr, 1e-6, 0.08, 0.85, 0.0);
+        for i in 0..100 {
+            let ret = if i % 2 == 0 { 0.001 } else { -0.0015 };
+            plain.update(ret);
+            gjr.update(ret);
+        }
+        assert!((plain.sigma() - gjr.sigma()).abs() < 1e-15);
+    }
+}
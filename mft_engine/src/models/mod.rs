@@ -0,0 +1,6 @@
+//! Statistical models feeding the strategy engine.
+
+pub mod garch;
+pub mod ofi;
+pub mod ou;
+pub mod vpin;
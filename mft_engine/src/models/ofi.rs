@@ -0,0 +1,252 @@
+//! Order-flow imbalance (OFI) over a rolling tick window.
+//!
+//! OFI here is the signed-volume imbalance `Σ signed_qty / Σ qty ∈ [-1, 1]`
+//! over the last `window` trades. A fast/slow OFI pair gives flow velocity,
+//! `ΔOFI = OFI_fast − OFI_slow`, which front-runs momentum bursts.
+//!
+//! [`FlowAnalyser`] bundles the OFI and VPIN engines behind one `push_tick`
+//! so callers see a single [`FlowSignal`] snapshot per update.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::TradeTick;
+use crate::models::vpin::VpinEngine;
+
+/// Best bid/ask snapshot, the unit consumed by [`OfiEngine::push_book_delta`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookLevel {
+    pub bid_px: f64,
+    pub bid_sz: f64,
+    pub ask_px: f64,
+    pub ask_sz: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfiEngine {
+    window: usize,
+    /// (signed_qty, qty) per tick.
+    ticks: VecDeque<(f64, f64)>,
+    signed_sum: f64,
+    abs_sum: f64,
+}
+
+impl OfiEngine {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            ticks: VecDeque::with_capacity(window + 1),
+            signed_sum: 0.0,
+            abs_sum: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, tick: &TradeTick) {
+        let signed = if tick.is_buy { tick.qty } else { -tick.qty };
+        self.ticks.push_back((signed, tick.qty));
+        self.signed_sum += signed;
+        self.abs_sum += tick.qty;
+        if self.ticks.len() > self.window {
+            let (s, q) = self.ticks.pop_front().unwrap();
+            self.signed_sum -= s;
+            self.abs_sum -= q;
+        }
+    }
+
+    /// Feed one best-level book transition, using the Cont–Kukanov–Stoikov
+    /// order-flow imbalance:
+    ///
+    /// `e = 1[b≥b'] · bid_sz − 1[b≤b'] · bid_sz' − 1[a≤a'] · ask_sz + 1[a≥a'] · ask_sz'`
+    ///
+    /// where primes denote `prev`. The signed contribution enters the same
+    /// rolling window as trade ticks, so `ofi()` blends whatever feed the
+    /// caller has — true book deltas when snapshots are available, the
+    /// bar-level tick approximation otherwise.
+    pub fn push_book_delta(&mut self, prev: &BookLevel, curr: &BookLevel) {
+        let mut e = 0.0;
+        if curr.bid_px >= prev.bid_px {
+            e += curr.bid_sz;
+        }
+        if curr.bid_px <= prev.bid_px {
+            e -= prev.bid_sz;
+        }
+        if curr.ask_px <= prev.ask_px {
+            e -= curr.ask_sz;
+        }
+        if curr.ask_px >= prev.ask_px {
+            e += prev.ask_sz;
+        }
+        self.ticks.push_back((e, e.abs()));
+        self.signed_sum += e;
+        self.abs_sum += e.abs();
+        if self.ticks.len() > self.window {
+            let (s, q) = self.ticks.pop_front().unwrap();
+            self.signed_sum -= s;
+            self.abs_sum -= q;
+        }
+    }
+
+    /// Normalised imbalance in `[-1, 1]`; `None` before any volume arrives.
+    pub fn ofi(&self) -> Option<f64> {
+        if self.abs_sum <= 0.0 {
+            None
+        } else {
+            Some(self.signed_sum / self.abs_sum)
+        }
+    }
+
+    pub fn n_ticks(&self) -> usize {
+        self.ticks.len()
+    }
+}
+
+/// Combined flow state handed to the strategy each update.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlowSignal {
+    pub ofi: Option<f64>,
+    pub vpin: Option<f64>,
+    /// Flow velocity `ΔOFI = OFI_fast − OFI_slow`; `None` until both
+    /// windows have volume, or when no fast window is configured.
+    pub ofi_velocity: Option<f64>,
+}
+
+/// Owns the flow engines and fans ticks out to them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowAnalyser {
+    ofi: OfiEngine,
+    /// Short-window OFI backing `ΔOFI`; `None` when velocity is disabled.
+    ofi_fast: Option<OfiEngine>,
+    vpin: VpinEngine,
+}
+
+impl FlowAnalyser {
+    pub fn new(ofi_window: usize, vpin_bucket_volume: f64, vpin_n_buckets: usize) -> Self {
+        Self {
+            ofi: OfiEngine::new(ofi_window),
+            ofi_fast: None,
+            vpin: VpinEngine::new(vpin_bucket_volume, vpin_n_buckets),
+        }
+    }
+
+    /// Track a second, fast OFI window so `ofi_velocity` is available.
+    /// A `window` of 0 leaves velocity disabled.
+    pub fn with_fast_window(mut self, window: usize) -> Self {
+        self.ofi_fast = (window > 0).then(|| OfiEngine::new(window));
+        self
+    }
+
+    pub fn push_tick(&mut self, tick: &TradeTick) -> FlowSignal {
+        self.ofi.push(tick);
+        if let Some(fast) = &mut self.ofi_fast {
+            fast.push(tick);
+        }
+        self.vpin.push(tick);
+        self.signal()
+    }
+
+    pub fn signal(&self) -> FlowSignal {
+        FlowSignal {
+            ofi: self.ofi.ofi(),
+            vpin: self.vpin.vpin(),
+            ofi_velocity: self.ofi_velocity(),
+        }
+    }
+
+    /// `ΔOFI = OFI_fast − OFI_slow`: positive when recent flow is more
+    /// buy-heavy than the longer window, i.e. buying is accelerating.
+    pub fn ofi_velocity(&self) -> Option<f64> {
+        let fast = self.ofi_fast.as_ref()?.ofi()?;
+        Some(fast - self.ofi.ofi()?)
+    }
+
+    pub fn vpin_engine(&self) -> &VpinEngine {
+        &self.vpin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(qty: f64, is_buy: bool) -> TradeTick {
+        TradeTick {
+            ts: 0,
+            price: 100.0,
+            qty,
+            is_buy,
+        }
+    }
+
+    #[test]
+    fn pure_buy_flow_is_plus_one() {
+        let mut o = OfiEngine::new(10);
+        for _ in 0..5 {
+            o.push(&tick(2.0, true));
+        }
+        assert!((o.ofi().unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rising_bid_falling_ask_gives_positive_ofi() {
+        let mut o = OfiEngine::new(10);
+        let prev = BookLevel {
+            bid_px: 100.0,
+            bid_sz: 10.0,
+            ask_px: 100.1,
+            ask_sz: 10.0,
+        };
+        // Unchanged prices, bid size up, ask size down: buy pressure.
+        let curr = BookLevel {
+            bid_sz: 12.0,
+            ask_sz: 8.0,
+            ..prev
+        };
+        o.push_book_delta(&prev, &curr);
+        assert!(o.ofi().unwrap() > 0.0);
+
+        // The mirrored transition flips the sign.
+        let mut o2 = OfiEngine::new(10);
+        let curr2 = BookLevel {
+            bid_sz: 8.0,
+            ask_sz: 12.0,
+            ..prev
+        };
+        o2.push_book_delta(&prev, &curr2);
+        assert!(o2.ofi().unwrap() < 0.0);
+    }
+
+    #[test]
+    fn buy_burst_spikes_velocity_which_decays_as_slow_catches_up() {
+        // Huge VPIN buckets so only the OFI engines see the tape.
+        let mut f = FlowAnalyser::new(40, 1e9, 10).with_fast_window(5);
+        // Balanced tape: both windows near zero imbalance.
+        for i in 0..40 {
+            f.push_tick(&tick(1.0, i % 2 == 0));
+        }
+        // A sudden burst of buys fills the fast window long before the slow.
+        let mut sig = FlowSignal::default();
+        for _ in 0..5 {
+            sig = f.push_tick(&tick(1.0, true));
+        }
+        let burst = sig.ofi_velocity.unwrap();
+        assert!(burst > 0.5, "burst velocity {burst}");
+        // Sustained buying lets the slow window catch up and ΔOFI decay.
+        for _ in 0..40 {
+            sig = f.push_tick(&tick(1.0, true));
+        }
+        assert!(sig.ofi_velocity.unwrap() < 1e-12);
+    }
+
+    #[test]
+    fn window_evicts_old_flow() {
+        let mut o = OfiEngine::new(4);
+        for _ in 0..4 {
+            o.push(&tick(1.0, true));
+        }
+        for _ in 0..4 {
+            o.push(&tick(1.0, false));
+        }
+        assert!((o.ofi().unwrap() + 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,608 @@
+//! Ornstein-Uhlenbeck mean-reversion model.
+//!
+//! Prices are assumed to follow `dX = θ(μ − X)dt + σ dW`. We estimate the
+//! discretised AR(1) form `x_{t+1} = a + b·x_t + ε` by OLS over a rolling
+//! window and recover `θ = −ln(b)/Δt`, `μ = a/(1−b)` and the equilibrium
+//! standard deviation `σ_eq = σ_ε / sqrt(1 − b²)`. The z-score of the latest
+//! price against `(μ, σ_eq)` is the strategy's primary entry signal.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Which estimator tracks the OU equilibrium.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OuEstimatorKind {
+    /// Windowed OLS on the AR(1) discretisation (the default).
+    Ols,
+    /// Recursive Kalman update of `mu`; adapts faster to regime shifts.
+    Kalman,
+    /// Profile MLE of the stationary OU likelihood
+    /// ([`OuParams::estimate_mle`]); less upward θ bias on short windows.
+    Mle,
+}
+
+/// How the equilibrium level the z-score is measured against is obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OuMuMode {
+    /// The regression intercept `a/(1−b)` from the windowed fit (the
+    /// default). Assumes `mu` is constant over the window.
+    Regression,
+    /// An EWMA of price, `mu_t = λ·mu_{t−1} + (1−λ)·price_t`, which trails
+    /// a drifting equilibrium much more closely than the windowed
+    /// intercept when price trends.
+    Ewma,
+}
+
+/// EWMA decay for [`OuMuMode::Ewma`] (~20-bar memory).
+const EWMA_MU_LAMBDA: f64 = 0.95;
+
+/// Scalar Kalman filter tracking the OU equilibrium `mu` as a random walk.
+///
+/// State: `mu_t = mu_{t-1} + w`, observation: `price_t = mu_t + v`. The
+/// observation variance is tracked as an EW variance of the innovations and
+/// the process variance is a fixed fraction of it, so the filter self-tunes
+/// to the price scale without extra config knobs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KalmanOuEstimator {
+    mu: f64,
+    p: f64,
+    obs_var: f64,
+    /// Process-to-observation variance ratio; larger adapts faster.
+    q_ratio: f64,
+    n_obs: usize,
+}
+
+impl KalmanOuEstimator {
+    pub fn new(q_ratio: f64) -> Self {
+        Self {
+            mu: 0.0,
+            p: 0.0,
+            obs_var: 0.0,
+            q_ratio,
+            n_obs: 0,
+        }
+    }
+
+    /// Feed one price; returns the updated equilibrium estimate.
+    pub fn update(&mut self, price: f64) -> f64 {
+        if self.n_obs == 0 {
+            self.mu = price;
+            self.obs_var = (price * 1e-4).powi(2);
+            self.p = self.obs_var;
+        } else {
+            let innovation = price - self.mu;
+            // EW update of the observation variance (λ = 0.99).
+            self.obs_var = 0.99 * self.obs_var + 0.01 * innovation * innovation;
+            self.p += self.obs_var * self.q_ratio;
+            let gain = self.p / (self.p + self.obs_var);
+            self.mu += gain * innovation;
+            self.p *= 1.0 - gain;
+        }
+        self.n_obs += 1;
+        self.mu
+    }
+
+    pub fn mu(&self) -> f64 {
+        self.mu
+    }
+}
+
+/// Fitted OU parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OuParams {
+    /// Long-run equilibrium level.
+    pub mu: f64,
+    /// Mean-reversion speed (per bar when `dt = 1`).
+    pub theta: f64,
+    /// Equilibrium (stationary) standard deviation.
+    pub sigma_eq: f64,
+    /// Half-life of a deviation, in bars: `ln(2)/θ`.
+    pub half_life: f64,
+}
+
+impl OuParams {
+    /// OLS fit of the AR(1) discretisation over `prices`.
+    ///
+    /// Returns `None` when the window is too short or the slope estimate is
+    /// outside `(0, 1)` (no mean reversion detectable).
+    pub fn estimate(prices: &[f64], dt: f64) -> Option<OuParams> {
+        let n = prices.len();
+        if n < 3 {
+            return None;
+        }
+        let x = &prices[..n - 1];
+        let y = &prices[1..];
+        let m = x.len() as f64;
+        let sum_x: f64 = x.iter().sum();
+        let sum_y: f64 = y.iter().sum();
+        let sum_xx: f64 = x.iter().map(|v| v * v).sum();
+        let sum_xy: f64 = x.iter().zip(y).map(|(a, b)| a * b).sum();
+        let denom = m * sum_xx - sum_x * sum_x;
+        if denom.abs() < f64::EPSILON {
+            return None;
+        }
+        let b = (m * sum_xy - sum_x * sum_y) / denom;
+        let a = (sum_y - b * sum_x) / m;
+        if b <= 0.0 || b >= 1.0 {
+            return None;
+        }
+        let resid_var: f64 = x
+            .iter()
+            .zip(y)
+            .map(|(xi, yi)| {
+                let e = yi - (a + b * xi);
+                e * e
+            })
+            .sum::<f64>()
+            / m;
+        let theta = -b.ln() / dt;
+        let mu = a / (1.0 - b);
+        let sigma_eq = (resid_var / (1.0 - b * b)).sqrt();
+        Some(OuParams {
+            mu,
+            theta,
+            sigma_eq,
+            half_life: std::f64::consts::LN_2 / theta,
+        })
+    }
+
+    /// Profile MLE of the *stationary* OU likelihood over `prices`.
+    ///
+    /// The conditional (transition-only) likelihood is maximised exactly by
+    /// the AR(1) OLS of [`estimate`](Self::estimate); this variant also
+    /// includes the density of the first observation under the stationary
+    /// law. For fixed `b = e^{−θΔt}` the remaining parameters have closed
+    /// forms, leaving a 1-D golden-section search over `b`. The extra term
+    /// shrinks the classic upward small-sample bias of θ̂ on short windows;
+    /// both estimators agree in the long-window limit.
+    pub fn estimate_mle(prices: &[f64], dt: f64) -> Option<OuParams> {
+        let n = prices.len();
+        if n < 3 {
+            return None;
+        }
+        let x0 = prices[0];
+        let x = &prices[..n - 1];
+        let y = &prices[1..];
+        let m = x.len() as f64;
+        // Profiled `(μ̂, σ̂², NLL)` for a fixed slope (constants dropped).
+        let profile = |b: f64| -> (f64, f64, f64) {
+            let c = 1.0 - b;
+            let w = 1.0 - b * b;
+            let sum_d: f64 = x.iter().zip(y).map(|(xi, yi)| yi - b * xi).sum();
+            let mu = (c * sum_d + w * x0) / (m * c * c + w);
+            let sse = x
+                .iter()
+                .zip(y)
+                .map(|(xi, yi)| {
+                    let e = (yi - mu) - b * (xi - mu);
+                    e * e
+                })
+                .sum::<f64>()
+                + w * (x0 - mu) * (x0 - mu);
+            let var = sse / (m + 1.0);
+            let nll = if var > 0.0 {
+                0.5 * (m + 1.0) * var.ln() - 0.5 * w.ln()
+            } else {
+                f64::INFINITY
+            };
+            (mu, var, nll)
+        };
+        // Golden-section search keeps `b` inside (0, 1), the same
+        // admissible range the OLS path enforces.
+        let gr = (5.0_f64.sqrt() - 1.0) / 2.0;
+        let (mut lo, mut hi) = (1e-6, 1.0 - 1e-6);
+        for _ in 0..100 {
+            let c = hi - gr * (hi - lo);
+            let d = lo + gr * (hi - lo);
+            if profile(c).2 < profile(d).2 {
+                hi = d;
+            } else {
+                lo = c;
+            }
+        }
+        let b = 0.5 * (lo + hi);
+        let (mu, var, nll) = profile(b);
+        if !nll.is_finite() {
+            return None;
+        }
+        let theta = -b.ln() / dt;
+        let sigma_eq = (var / (1.0 - b * b)).sqrt();
+        Some(OuParams {
+            mu,
+            theta,
+            sigma_eq,
+            half_life: std::f64::consts::LN_2 / theta,
+        })
+    }
+
+    /// Z-score of `price` against the fitted equilibrium.
+    pub fn z_score(&self, price: f64) -> f64 {
+        (price - self.mu) / self.sigma_eq
+    }
+}
+
+/// Running sums over the consecutive-price pairs `(x_i, x_{i+1})` in the
+/// window, enough to solve the AR(1) OLS (including the residual variance,
+/// via `SSE = Σy² + a²m + b²Σx² − 2aΣy − 2bΣxy + 2abΣx`) in O(1).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct PairSums {
+    sum_x: f64,
+    sum_y: f64,
+    sum_xx: f64,
+    sum_xy: f64,
+    sum_yy: f64,
+    /// Evictions since the last rebuild; used to cap float drift.
+    evictions: usize,
+}
+
+impl PairSums {
+    fn add(&mut self, x: f64, y: f64) {
+        self.sum_x += x;
+        self.sum_y += y;
+        self.sum_xx += x * x;
+        self.sum_xy += x * y;
+        self.sum_yy += y * y;
+    }
+
+    fn remove(&mut self, x: f64, y: f64) {
+        self.sum_x -= x;
+        self.sum_y -= y;
+        self.sum_xx -= x * x;
+        self.sum_xy -= x * y;
+        self.sum_yy -= y * y;
+        self.evictions += 1;
+    }
+}
+
+/// Rolling-window OU estimator fed one close at a time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OuSignalEngine {
+    window: usize,
+    price_buf: VecDeque<f64>,
+    params: Option<OuParams>,
+    last_z: Option<f64>,
+    estimator: OuEstimatorKind,
+    kalman: KalmanOuEstimator,
+    mu_mode: OuMuMode,
+    /// EWMA equilibrium; seeded with the first price.
+    ewma_mu: Option<f64>,
+    /// When set, the OLS fit is maintained incrementally from [`PairSums`]
+    /// instead of re-summing the whole window each bar.
+    incremental: bool,
+    sums: PairSums,
+}
+
+/// Rebuild the incremental sums from scratch after this many evictions so
+/// accumulated add/subtract rounding cannot drift away from the batch fit.
+const SUMS_REBUILD_EVERY: usize = 4096;
+
+impl OuSignalEngine {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            price_buf: VecDeque::with_capacity(window + 1),
+            params: None,
+            last_z: None,
+            estimator: OuEstimatorKind::Ols,
+            kalman: KalmanOuEstimator::new(0.01),
+            mu_mode: OuMuMode::Regression,
+            ewma_mu: None,
+            incremental: false,
+            sums: PairSums::default(),
+        }
+    }
+
+    /// Like [`new`](Self::new), but maintains the OLS sums incrementally so
+    /// each bar costs O(1) instead of O(window). Results match the batch
+    /// path to within float rounding (see the regression test).
+    pub fn new_incremental(window: usize) -> Self {
+        Self {
+            incremental: true,
+            ..Self::new(window)
+        }
+    }
+
+    /// Select the equilibrium estimator (builder-style).
+    pub fn with_estimator(mut self, estimator: OuEstimatorKind) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Select how the equilibrium `mu` is tracked (builder-style).
+    pub fn with_mu_mode(mut self, mu_mode: OuMuMode) -> Self {
+        self.mu_mode = mu_mode;
+        self
+    }
+
+    /// Push a close price; returns the current z-score once the window is full.
+    pub fn push(&mut self, price: f64) -> Option<f64> {
+        self.kalman.update(price);
+        self.ewma_mu = Some(match self.ewma_mu {
+            Some(mu) => EWMA_MU_LAMBDA * mu + (1.0 - EWMA_MU_LAMBDA) * price,
+            None => price,
+        });
+        if self.incremental {
+            if let Some(&prev) = self.price_buf.back() {
+                self.sums.add(prev, price);
+            }
+        }
+        self.price_buf.push_back(price);
+        if self.price_buf.len() > self.window {
+            if self.incremental {
+                self.sums.remove(self.price_buf[0], self.price_buf[1]);
+            }
+            self.price_buf.pop_front();
+            if self.incremental && self.sums.evictions >= SUMS_REBUILD_EVERY {
+                self.rebuild_sums();
+            }
+        }
+        if self.price_buf.len() < self.window {
+            self.last_z = None;
+            return None;
+        }
+        let mut params = if self.estimator == OuEstimatorKind::Mle {
+            // The stationary likelihood needs the raw window; the
+            // incremental sums only support the OLS path.
+            OuParams::estimate_mle(self.price_buf.make_contiguous(), 1.0)
+        } else if self.incremental {
+            self.estimate_from_sums(1.0)
+        } else {
+            OuParams::estimate(self.price_buf.make_contiguous(), 1.0)
+        };
+        if self.estimator == OuEstimatorKind::Kalman {
+            // The Kalman mean replaces the regression intercept; theta and
+            // sigma_eq keep their windowed estimates so the z-score scale
+            // (and its interpretation) is unchanged.
+            if let Some(p) = &mut params {
+                p.mu = self.kalman.mu();
+            }
+        }
+        if self.mu_mode == OuMuMode::Ewma {
+            // The EWMA level wins over both the intercept and the Kalman
+            // mean; like above, only mu is replaced.
+            if let (Some(p), Some(mu)) = (&mut params, self.ewma_mu) {
+                p.mu = mu;
+            }
+        }
+        self.params = params;
+        self.last_z = self.params.map(|p| p.z_score(price));
+        self.last_z
+    }
+
+    /// Z-score of an arbitrary price against the current fit.
+    pub fn z_score(&self, price: f64) -> Option<f64> {
+        self.params.map(|p| p.z_score(price))
+    }
+
+    /// Most recent z-score produced by [`push`](Self::push).
+    pub fn last_z(&self) -> Option<f64> {
+        self.last_z
+    }
+
+    /// Current fitted parameters, if the window has filled.
+    pub fn params(&self) -> Option<&OuParams> {
+        self.params.as_ref()
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// True once enough prices have been seen to fit.
+    pub fn is_warm(&self) -> bool {
+        self.params.is_some()
+    }
+
+    /// Recompute the pair sums from the buffer contents.
+    fn rebuild_sums(&mut self) {
+        self.sums = PairSums::default();
+        for i in 1..self.price_buf.len() {
+            self.sums.add(self.price_buf[i - 1], self.price_buf[i]);
+        }
+    }
+
+    /// Solve the AR(1) OLS from the running sums; mirrors
+    /// [`OuParams::estimate`] including its degeneracy checks.
+    fn estimate_from_sums(&self, dt: f64) -> Option<OuParams> {
+        let n = self.price_buf.len();
+        if n < 3 {
+            return None;
+        }
+        let m = (n - 1) as f64;
+        let PairSums {
+            sum_x,
+            sum_y,
+            sum_xx,
+            sum_xy,
+            sum_yy,
+            ..
+        } = self.sums;
+        let denom = m * sum_xx - sum_x * sum_x;
+        if denom.abs() < f64::EPSILON {
+            return None;
+        }
+        let b = (m * sum_xy - sum_x * sum_y) / denom;
+        let a = (sum_y - b * sum_x) / m;
+        if b <= 0.0 || b >= 1.0 {
+            return None;
+        }
+        let sse =
+            sum_yy + a * a * m + b * b * sum_xx - 2.0 * a * sum_y - 2.0 * b * sum_xy
+                + 2.0 * a * b * sum_x;
+        let resid_var = (sse / m).max(0.0);
+        let theta = -b.ln() / dt;
+        let mu = a / (1.0 - b);
+        let sigma_eq = (resid_var / (1.0 - b * b)).sqrt();
+        Some(OuParams {
+            mu,
+            theta,
+            sigma_eq,
+            half_life: std::f64::consts::LN_2 / theta,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic OU path via a simple LCG so the test needs no RNG dep.
+    fn synth_ou(n: usize, mu: f64, theta: f64, sigma: f64, seed: u64) -> Vec<f64> {
+        let mut state = seed;
+        let mut gauss = || {
+            // sum of 12 uniforms, variance 1
+            let mut acc = 0.0;
+            for _ in 0..12 {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                acc += (state >> 11) as f64 / (1u64 << 53) as f64;
+            }
+            acc - 6.0
+        };
+        let mut x = mu;
+        let mut out = Vec::with_capacity(n);
+        for _ in 0..n {
+            x += theta * (mu - x) + sigma * gauss();
+            out.push(x);
+        }
+        out
+    }
+
+    #[test]
+    fn estimate_recovers_mu_on_long_path() {
+        let prices = synth_ou(5_000, 100.0, 0.1, 0.5, 7);
+        let p = OuParams::estimate(&prices, 1.0).expect("fit");
+        assert!((p.mu - 100.0).abs() < 1.0, "mu = {}", p.mu);
+        assert!(p.theta > 0.0);
+        assert!(p.half_life > 0.0);
+    }
+
+    #[test]
+    fn kalman_tracks_step_change_faster_than_ols() {
+        // 300 bars around 100, then a regime shift to 110.
+        let mut prices = synth_ou(300, 100.0, 0.2, 0.3, 11);
+        prices.extend(synth_ou(60, 110.0, 0.2, 0.3, 12));
+
+        let mut ols = OuSignalEngine::new(200);
+        let mut kalman = OuSignalEngine::new(200).with_estimator(OuEstimatorKind::Kalman);
+        for p in &prices {
+            ols.push(*p);
+            kalman.push(*p);
+        }
+        let ols_err = (ols.params().unwrap().mu - 110.0).abs();
+        let kalman_err = (kalman.params().unwrap().mu - 110.0).abs();
+        assert!(
+            kalman_err < ols_err,
+            "kalman_err={kalman_err} should beat ols_err={ols_err}"
+        );
+    }
+
+    #[test]
+    fn ewma_mu_shrinks_persistent_z_on_a_trending_series() {
+        // An OU process whose equilibrium drifts gently upward: the windowed
+        // intercept averages the drift away and lags, leaving a persistent
+        // positive z bias; the EWMA level trails the drift closely.
+        let mut state = 17u64;
+        let mut gauss = || {
+            let mut acc = 0.0;
+            for _ in 0..12 {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                acc += (state >> 11) as f64 / (1u64 << 53) as f64;
+            }
+            acc - 6.0
+        };
+        let mut x = 100.0;
+        let prices: Vec<f64> = (0..800)
+            .map(|t| {
+                let mu_t = 100.0 + 0.02 * t as f64;
+                x += 0.2 * (mu_t - x) + 0.3 * gauss();
+                x
+            })
+            .collect();
+
+        let mut reg = OuSignalEngine::new(120);
+        let mut ewma = OuSignalEngine::new(120).with_mu_mode(OuMuMode::Ewma);
+        let (mut reg_zs, mut ewma_zs) = (Vec::new(), Vec::new());
+        for (i, p) in prices.iter().enumerate() {
+            let zr = reg.push(*p);
+            let ze = ewma.push(*p);
+            if i >= 400 {
+                reg_zs.extend(zr);
+                ewma_zs.extend(ze);
+            }
+        }
+        let mean_abs = |zs: &[f64]| zs.iter().map(|z| z.abs()).sum::<f64>() / zs.len() as f64;
+        assert!(!reg_zs.is_empty() && !ewma_zs.is_empty());
+        assert!(
+            mean_abs(&ewma_zs) < mean_abs(&reg_zs),
+            "ewma {} should be below regression {}",
+            mean_abs(&ewma_zs),
+            mean_abs(&reg_zs)
+        );
+    }
+
+    #[test]
+    fn mle_matches_ols_long_run_with_less_short_window_bias() {
+        // In the long-window limit the stationary term is negligible and
+        // the two estimators coincide.
+        let prices = synth_ou(20_000, 100.0, 0.1, 0.5, 7);
+        let ols = OuParams::estimate(&prices, 1.0).expect("ols fit");
+        let mle = OuParams::estimate_mle(&prices, 1.0).expect("mle fit");
+        assert!((mle.mu - 100.0).abs() < 1.0, "mu = {}", mle.mu);
+        assert!((mle.theta - 0.1).abs() < 0.02, "theta = {}", mle.theta);
+        assert!((mle.theta - ols.theta).abs() < 1e-3);
+
+        // Average θ̂ over disjoint 40-bar windows: both overshoot the true
+        // 0.1 (the AR(1) small-sample bias); the stationary term shrinks
+        // the overshoot.
+        let long = synth_ou(40_000, 100.0, 0.1, 0.5, 9);
+        let (mut ols_sum, mut mle_sum, mut n) = (0.0, 0.0, 0usize);
+        for w in long.chunks_exact(40) {
+            if let (Some(o), Some(m)) =
+                (OuParams::estimate(w, 1.0), OuParams::estimate_mle(w, 1.0))
+            {
+                ols_sum += o.theta;
+                mle_sum += m.theta;
+                n += 1;
+            }
+        }
+        let ols_bias = ols_sum / n as f64 - 0.1;
+        let mle_bias = mle_sum / n as f64 - 0.1;
+        assert!(mle_bias > 0.0);
+        assert!(mle_bias < ols_bias, "mle {mle_bias} vs ols {ols_bias}");
+    }
+
+    #[test]
+    fn engine_emits_z_only_after_window_fills() {
+        let prices = synth_ou(300, 50.0, 0.2, 0.2, 3);
+        let mut eng = OuSignalEngine::new(200);
+        let mut first_some = None;
+        for (i, p) in prices.iter().enumerate() {
+            if eng.push(*p).is_some() && first_some.is_none() {
+                first_some = Some(i);
+            }
+        }
+        assert_eq!(first_some, Some(199));
+    }
+
+    #[test]
+    fn incremental_matches_batch_over_long_path() {
+        let prices = synth_ou(10_000, 100.0, 0.1, 0.5, 21);
+        let mut batch = OuSignalEngine::new(120);
+        let mut inc = OuSignalEngine::new_incremental(120);
+        for p in &prices {
+            let zb = batch.push(*p);
+            let zi = inc.push(*p);
+            assert_eq!(zb.is_some(), zi.is_some());
+            if let (Some(zb), Some(zi)) = (zb, zi) {
+                assert!((zb - zi).abs() < 1e-9, "zb={zb} zi={zi}");
+            }
+        }
+        let pb = batch.params().unwrap();
+        let pi = inc.params().unwrap();
+        assert!((pb.mu - pi.mu).abs() < 1e-9);
+        assert!((pb.sigma_eq - pi.sigma_eq).abs() < 1e-9);
+    }
+}
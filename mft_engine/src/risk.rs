@@ -0,0 +1,131 @@
+//! Expected-value gating, Kelly sizing and protective levels.
+//!
+//! Invariants the engine relies on:
+//! - `kelly_fraction` scales the raw Kelly output; never size above full Kelly.
+//! - stop_frac must cover at least round-trip fees → enforced by
+//!   [`AppConfig::validate`] at config load and by `StrategyEngine::new`,
+//!   which warns and widens an inside-cost stop.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::engine::Direction;
+
+/// Protective price levels attached to an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RiskLevels {
+    pub stop_loss: f64,
+    pub take_profit: f64,
+}
+
+impl RiskLevels {
+    /// Levels for an entry at `price` in `direction`, from config fractions.
+    pub fn from_entry(price: f64, direction: Direction, cfg: &AppConfig) -> Self {
+        match direction {
+            Direction::Long => Self {
+                stop_loss: price * (1.0 - cfg.stop_loss_frac),
+                take_profit: price * (1.0 + cfg.take_profit_frac),
+            },
+            Direction::Short => Self {
+                stop_loss: price * (1.0 + cfg.stop_loss_frac),
+                take_profit: price * (1.0 - cfg.take_profit_frac),
+            },
+        }
+    }
+
+    /// True when `price` has crossed the stop for `direction`.
+    pub fn is_stopped(&self, price: f64, direction: Direction) -> bool {
+        match direction {
+            Direction::Long => price <= self.stop_loss,
+            Direction::Short => price >= self.stop_loss,
+        }
+    }
+
+    /// True when `price` has crossed the target for `direction`.
+    pub fn is_profit_taken(&self, price: f64, direction: Direction) -> bool {
+        match direction {
+            Direction::Long => price >= self.take_profit,
+            Direction::Short => price <= self.take_profit,
+        }
+    }
+}
+
+/// Expected value (fraction of notional) of fading a z-score deviation.
+///
+/// The expected reversion move is `|z| · σ_eq / price`; costs are a full
+/// round trip of taker fees plus slippage. Positive EV is required to trade.
+pub fn evaluate_ev(z: f64, sigma_eq: f64, price: f64, cfg: &AppConfig) -> f64 {
+    let expected_move = z.abs() * sigma_eq / price;
+    let round_trip_cost = 2.0 * cfg.one_way_cost();
+    expected_move - round_trip_cost
+}
+
+/// Fractional Kelly for a binary-outcome approximation.
+///
+/// `p` is the estimated win probability, `b` the win/loss payoff ratio.
+/// The result is already scaled by `cfg.kelly_fraction` and floored at zero.
+pub fn kelly_size(p: f64, b: f64, cfg: &AppConfig) -> f64 {
+    if b <= 0.0 {
+        return 0.0;
+    }
+    let raw = (p * b - (1.0 - p)) / b;
+    (raw * cfg.kelly_fraction).max(0.0)
+}
+
+/// Size multiplier from flow toxicity: shrinks toward a 0.25 floor as VPIN
+/// approaches 1. Returns 1.0 when `vpin_size_scaling` is off or VPIN is
+/// not yet available.
+pub fn vpin_size_multiplier(vpin: Option<f64>, cfg: &AppConfig) -> f64 {
+    if !cfg.vpin_size_scaling {
+        return 1.0;
+    }
+    match vpin {
+        Some(v) => (1.0 - v).clamp(0.25, 1.0),
+        None => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ev_is_negative_when_move_under_costs() {
+        let cfg = AppConfig::default();
+        // Tiny deviation: expected move far below round-trip cost.
+        let ev = evaluate_ev(0.1, 1.0, 50_000.0, &cfg);
+        assert!(ev < 0.0);
+    }
+
+    #[test]
+    fn kelly_never_negative() {
+        let cfg = AppConfig::default();
+        assert_eq!(kelly_size(0.1, 1.0, &cfg), 0.0);
+        assert!(kelly_size(0.6, 1.5, &cfg) > 0.0);
+    }
+
+    #[test]
+    fn higher_vpin_means_smaller_size() {
+        let cfg = AppConfig {
+            vpin_size_scaling: true,
+            ..AppConfig::default()
+        };
+        let calm = vpin_size_multiplier(Some(0.1), &cfg);
+        let toxic = vpin_size_multiplier(Some(0.6), &cfg);
+        assert!((calm - 0.9).abs() < 1e-12);
+        assert!((toxic - 0.4).abs() < 1e-12);
+        // Floor at 0.25, full size when cold or disabled.
+        assert_eq!(vpin_size_multiplier(Some(0.95), &cfg), 0.25);
+        assert_eq!(vpin_size_multiplier(None, &cfg), 1.0);
+        assert_eq!(vpin_size_multiplier(Some(0.6), &AppConfig::default()), 1.0);
+    }
+
+    #[test]
+    fn long_stop_below_entry() {
+        let cfg = AppConfig::default();
+        let levels = RiskLevels::from_entry(100.0, Direction::Long, &cfg);
+        assert!(levels.stop_loss < 100.0);
+        assert!(levels.take_profit > 100.0);
+        assert!(levels.is_stopped(levels.stop_loss, Direction::Long));
+    }
+}
@@ -0,0 +1,424 @@
+//! Nautilus-based backtest: build an engine, synthesize tick-level events
+//! from parquet klines, and drive [`crate::vortex_strategy::VortexStrategy`].
+
+use anyhow::{Context, Result};
+use nautilus_backtest::config::BacktestEngineConfig;
+use nautilus_backtest::engine::BacktestEngine;
+use nautilus_core::UnixNanos;
+use nautilus_model::data::{Bar, BarSpecification, BarType, Data, HasTsInit, QuoteTick, TradeTick};
+use nautilus_model::enums::{
+    AccountType, AggregationSource, AggressorSide, BarAggregation, BookType, CurrencyType,
+    OmsType, PriceType,
+};
+use nautilus_model::identifiers::{InstrumentId, Symbol, TradeId, Venue};
+use nautilus_model::instruments::{CryptoPerpetual, InstrumentAny};
+use nautilus_model::types::{Currency, Money, Price, Quantity};
+use polars::prelude::*;
+use tracing::info;
+
+use mft_engine::config::AppConfig;
+use mft_engine::data::Kline;
+
+use crate::instruments::{find_spec, InstrumentSpec};
+
+/// Bars per loader chunk in the Nautilus run (~9 events per bar, so a
+/// batch tops out around 90k events regardless of dataset length).
+const BACKTEST_CHUNK_ROWS: usize = 10_000;
+
+/// Load a kline parquet written by `fetch_data` into memory.
+pub fn load_klines(path: &str) -> Result<Vec<Kline>> {
+    let df = LazyFrame::scan_parquet(path, Default::default())?
+        .collect()
+        .with_context(|| format!("reading {path}"))?;
+    df_to_klines(&df)
+}
+
+/// Stream a kline parquet in fixed-size row chunks instead of collecting
+/// the whole file, so multi-month datasets never hold more than
+/// `chunk_rows` bars (and the events derived from them) in memory at once.
+pub fn load_klines_chunked(path: &str, chunk_rows: usize) -> Result<KlineChunks> {
+    anyhow::ensure!(chunk_rows > 0, "chunk_rows must be positive");
+    // Keep the lazy scan; each chunk collects only its own slice.
+    let lf = LazyFrame::scan_parquet(path, Default::default())
+        .with_context(|| format!("scanning {path}"))?;
+    Ok(KlineChunks {
+        lf,
+        offset: 0,
+        chunk_rows,
+        done: false,
+    })
+}
+
+/// Iterator returned by [`load_klines_chunked`]; yields at most
+/// `chunk_rows` bars per step, in file order.
+pub struct KlineChunks {
+    lf: LazyFrame,
+    offset: i64,
+    chunk_rows: usize,
+    done: bool,
+}
+
+impl Iterator for KlineChunks {
+    type Item = Result<Vec<Kline>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let df = match self
+            .lf
+            .clone()
+            .slice(self.offset, self.chunk_rows as u32)
+            .collect()
+        {
+            Ok(df) => df,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e.into()));
+            }
+        };
+        if df.height() == 0 {
+            self.done = true;
+            return None;
+        }
+        if df.height() < self.chunk_rows {
+            self.done = true;
+        }
+        self.offset += df.height() as i64;
+        Some(df_to_klines(&df))
+    }
+}
+
+fn df_to_klines(df: &DataFrame) -> Result<Vec<Kline>> {
+    let open_time = df.column("open_time")?.i64()?;
+    let open = df.column("open")?.f64()?;
+    let high = df.column("high")?.f64()?;
+    let low = df.column("low")?.f64()?;
+    let close = df.column("close")?.f64()?;
+    let volume = df.column("volume")?.f64()?;
+    let close_time = df.column("close_time")?.i64()?;
+    let quote_volume = df.column("quote_asset_volume")?.f64()?;
+    let n_trades = df.column("n_trades")?.i64()?;
+    let taker_buy = df.column("taker_buy_volume")?.f64()?;
+    let mut out = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        out.push(Kline {
+            open_time: open_time.get(i).unwrap(),
+            open: open.get(i).unwrap(),
+            high: high.get(i).unwrap(),
+            low: low.get(i).unwrap(),
+            close: close.get(i).unwrap(),
+            volume: volume.get(i).unwrap(),
+            close_time: close_time.get(i).unwrap(),
+            quote_volume: quote_volume.get(i).unwrap(),
+            n_trades: n_trades.get(i).unwrap() as u64,
+            taker_buy_volume: taker_buy.get(i).unwrap(),
+        });
+    }
+    Ok(out)
+}
+
+/// Look up `code` in Nautilus's currency map, registering it as a crypto
+/// currency on first use. The map ships with the majors; long-tail perp
+/// bases would otherwise fail the lookup.
+fn ensure_currency(code: &str) -> Result<Currency> {
+    if let Some(currency) = Currency::try_from_str(code) {
+        return Ok(currency);
+    }
+    let currency = Currency::new_checked(code, 8, 0, code, CurrencyType::Crypto)?;
+    Currency::register(currency, false)?;
+    Ok(currency)
+}
+
+/// Create the venue instrument from the spec table, quoted and settled in
+/// `quote` (e.g. "USDT", "USDC"). A symbol quoted in another stablecoin
+/// falls back to its USDT market's filters when it has no spec of its own
+/// — the contract increments match across Binance's stablecoin markets.
+pub fn make_instrument(symbol: &str, quote: &str, venue: &Venue) -> Result<CryptoPerpetual> {
+    let base = symbol.trim_end_matches(quote);
+    let spec = find_spec(symbol)
+        .or_else(|| find_spec(&format!("{base}USDT")))
+        .with_context(|| format!("no instrument spec for {symbol}"))?;
+    let instrument_id = InstrumentId::from(format!("{symbol}-PERP.{venue}").as_str());
+    let quote_currency = ensure_currency(quote)?;
+    // Format the increments at the spec's own precision: `Price`/`Quantity`
+    // take theirs from the string, and `{}` drops trailing zeros.
+    let price_increment = Price::from(
+        format!("{:.prec$}", spec.price_incr, prec = spec.price_prec as usize).as_str(),
+    );
+    let size_increment = Quantity::from(
+        format!("{:.prec$}", spec.size_incr, prec = spec.size_prec as usize).as_str(),
+    );
+    CryptoPerpetual::new_checked(
+        instrument_id,
+        Symbol::new(symbol),
+        ensure_currency(base)?,
+        quote_currency,
+        quote_currency,
+        false,
+        spec.price_prec,
+        spec.size_prec,
+        price_increment,
+        size_increment,
+        None, // multiplier
+        None, // lot_size
+        None, // max_quantity
+        None, // min_quantity
+        None, // max_notional
+        None, // min_notional
+        None, // max_price
+        None, // min_price
+        None, // margin_init
+        None, // margin_maint
+        None, // maker_fee
+        None, // taker_fee
+        None, // info
+        UnixNanos::default(),
+        UnixNanos::default(),
+    )
+}
+
+/// Expand one candle into synthetic intrabar events.
+///
+/// Per candle: four quotes (O→H→L→C path), four trades along the same path
+/// with volume split evenly, and the bar itself — ~9 events per candle.
+///
+/// When `spec` is given, generated prices are snapped to the instrument's
+/// tick size and quantities to its step size, so synthetic events (and the
+/// fills they produce) are valid on the real exchange.
+pub fn kline_to_events(
+    kline: &Kline,
+    instrument_id: InstrumentId,
+    spec: Option<&InstrumentSpec>,
+) -> Vec<Data> {
+    let snap_px = |px: f64| spec.map_or(px, |s| s.snap_price(px));
+    let snap_qty = |q: f64| spec.map_or(q, |s| s.snap_qty(q));
+    // Format at the instrument's own precision so the parsed `Price` and
+    // `Quantity` carry it; eight decimals on a two-decimal symbol would make
+    // the matching engine re-snap every synthetic event.
+    let price_prec = spec.map_or(8, |s| s.price_prec) as usize;
+    let size_prec = spec.map_or(8, |s| s.size_prec) as usize;
+    let fmt_px = |px: f64| format!("{:.price_prec$}", snap_px(px));
+    let fmt_qty = |q: f64| format!("{:.size_prec$}", q);
+    let mut events = Vec::with_capacity(9);
+    let path = [kline.open, kline.high, kline.low, kline.close];
+    let qv = snap_qty(kline.volume / 4.0);
+    let step_ns = 60_000 / 4 * 1_000_000u64;
+    let base_ns = (kline.open_time as u64) * 1_000_000;
+    for (i, px) in path.iter().enumerate() {
+        let ts = base_ns + step_ns * i as u64;
+        let spread = px * 0.0001;
+        events.push(Data::Quote(QuoteTick::new(
+            instrument_id,
+            Price::from(fmt_px(px - spread / 2.0).as_str()),
+            Price::from(fmt_px(px + spread / 2.0).as_str()),
+            Quantity::from(fmt_qty(qv).as_str()),
+            Quantity::from(fmt_qty(qv).as_str()),
+            ts.into(),
+            ts.into(),
+        )));
+        let is_buy = i + 1 < path.len() && path[i + 1] >= *px;
+        events.push(Data::Trade(TradeTick::new(
+            instrument_id,
+            Price::from(fmt_px(*px).as_str()),
+            Quantity::from(fmt_qty(qv).as_str()),
+            if is_buy { AggressorSide::Buyer } else { AggressorSide::Seller },
+            TradeId::new(format!("{}-{}", kline.open_time, i)),
+            ts.into(),
+            ts.into(),
+        )));
+    }
+    let bar_type = BarType::new(
+        instrument_id,
+        BarSpecification::new(1, BarAggregation::Minute, PriceType::Last),
+        AggregationSource::External,
+    );
+    let ts = (kline.close_time as u64) * 1_000_000;
+    events.push(Data::Bar(Bar::new(
+        bar_type,
+        Price::from(fmt_px(kline.open).as_str()),
+        Price::from(fmt_px(kline.high).as_str()),
+        Price::from(fmt_px(kline.low).as_str()),
+        Price::from(fmt_px(kline.close).as_str()),
+        Quantity::from(fmt_qty(kline.volume).as_str()),
+        ts.into(),
+        ts.into(),
+    )));
+    events
+}
+
+/// Build the engine, load every symbol's parquet, interleave events by
+/// timestamp and run the strategy.
+pub fn run_nautilus_backtest(
+    cfg: &AppConfig,
+    symbols: &[String],
+    data_dir: &str,
+) -> Result<crate::vortex_strategy::VortexStrategy> {
+    let venue = Venue::from("BINANCE");
+    let mut engine = BacktestEngine::new(BacktestEngineConfig::default())?;
+    engine.add_venue(
+        venue,
+        OmsType::Netting,
+        AccountType::Margin,
+        BookType::L1_MBP,
+        vec![Money::new(
+            cfg.initial_capital,
+            ensure_currency(&cfg.quote_currency)?,
+        )],
+        None,               // base_currency: multi-currency account
+        None,               // default_leverage: engine default for margin
+        Default::default(), // leverages
+        Vec::new(),         // modules
+        Default::default(), // fill_model
+        Default::default(), // fee_model
+        None,               // latency_model
+        None,               // routing
+        None,               // reject_stop_orders
+        None,               // support_gtd_orders
+        None,               // support_contingent_orders
+        None,               // use_position_ids
+        None,               // use_random_ids
+        None,               // use_reduce_only
+        None,               // use_message_queue
+        None,               // use_market_order_acks
+        Some(true),         // bar_execution
+        None,               // bar_adaptive_high_low_ordering
+        Some(true),         // trade_execution
+        None,               // liquidity_consumption
+        None,               // allow_cash_borrowing
+        None,               // frozen_account
+        None,               // price_protection_points
+    )?;
+
+    let mut strategy = crate::vortex_strategy::VortexStrategy::new(cfg.clone(), cfg.initial_capital);
+    let mut streams = Vec::new();
+    for symbol in symbols {
+        let instrument = make_instrument(symbol, &cfg.quote_currency, &venue)?;
+        let instrument_id = instrument.id;
+        engine.add_instrument(InstrumentAny::CryptoPerpetual(instrument))?;
+        strategy.add_symbol(symbol, instrument_id)?;
+
+        let path = format!("{data_dir}/{symbol}_1m.parquet");
+        let spec = cfg.snap_to_filters.then(|| find_spec(symbol)).flatten();
+        streams.push((
+            instrument_id,
+            spec,
+            load_klines_chunked(&path, BACKTEST_CHUNK_ROWS)?,
+        ));
+    }
+
+    // Feed the engine one chunk-sized batch at a time instead of
+    // materialising the whole event stream. The per-symbol parquets are
+    // written over the same date range by fetch_data, so equal row slices
+    // cover the same time window and sorting within a batch preserves the
+    // global interleave.
+    loop {
+        let mut batch: Vec<Data> = Vec::new();
+        for (instrument_id, spec, chunks) in &mut streams {
+            if let Some(klines) = chunks.next().transpose()? {
+                for kline in &klines {
+                    batch.extend(kline_to_events(kline, *instrument_id, *spec));
+                }
+            }
+        }
+        if batch.is_empty() {
+            break;
+        }
+        batch.sort_by_key(HasTsInit::ts_init);
+        info!(events = batch.len(), "feeding event batch");
+        // Already sorted above, so skip the engine's own sort pass.
+        engine.add_data(batch, None, true, false);
+    }
+    engine.run(None, None, None, false)?;
+    Ok(strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_currency_propagates_into_the_instrument() {
+        let venue = Venue::from("BINANCE");
+        // No SOLUSDC spec exists; the USDT market's filters are borrowed,
+        // but the currencies follow the configured quote.
+        let instrument = make_instrument("SOLUSDC", "USDC", &venue).unwrap();
+        assert_eq!(instrument.base_currency.code.as_str(), "SOL");
+        assert_eq!(instrument.quote_currency.code.as_str(), "USDC");
+        assert_eq!(instrument.settlement_currency.code.as_str(), "USDC");
+        assert_eq!(instrument.price_precision, 2);
+
+        let unknown = make_instrument("NOPEUSDC", "USDC", &venue);
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn sol_events_format_prices_to_two_decimals() {
+        let spec = crate::instruments::find_spec("SOLUSDT").unwrap();
+        let kline = Kline {
+            open_time: 0,
+            open: 150.123456,
+            high: 151.0,
+            low: 149.5,
+            close: 150.5,
+            volume: 12.7,
+            close_time: 59_999,
+            quote_volume: 0.0,
+            n_trades: 10,
+            taker_buy_volume: 6.0,
+        };
+        let id = InstrumentId::from("SOLUSDT-PERP.BINANCE");
+
+        match &kline_to_events(&kline, id, Some(spec))[0] {
+            Data::Quote(q) => {
+                assert_eq!(q.bid_price.precision, 2);
+                assert_eq!(q.bid_size.precision, 0);
+            }
+            other => panic!("expected a quote first, got {other:?}"),
+        }
+        // Without a spec the legacy eight-decimal formatting is kept.
+        match &kline_to_events(&kline, id, None)[0] {
+            Data::Quote(q) => assert_eq!(q.bid_price.precision, 8),
+            other => panic!("expected a quote first, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn chunked_loader_bounds_resident_rows() {
+        let dir = std::env::temp_dir().join("mft_chunk_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("chunks.parquet").to_string_lossy().to_string();
+
+        let n = 100i64;
+        let mut df = df![
+            "open_time" => (0..n).map(|i| i * 60_000).collect::<Vec<_>>(),
+            "open" => vec![100.0; n as usize],
+            "high" => vec![100.5; n as usize],
+            "low" => vec![99.5; n as usize],
+            "close" => vec![100.2; n as usize],
+            "volume" => vec![10.0; n as usize],
+            "close_time" => (0..n).map(|i| i * 60_000 + 59_999).collect::<Vec<_>>(),
+            "quote_asset_volume" => vec![1_000.0; n as usize],
+            "n_trades" => vec![50i64; n as usize],
+            "taker_buy_volume" => vec![5.0; n as usize],
+        ]
+        .unwrap();
+        let file = std::fs::File::create(&path).unwrap();
+        ParquetWriter::new(file).finish(&mut df).unwrap();
+
+        let mut total = 0usize;
+        let mut peak = 0usize;
+        let mut last_time = i64::MIN;
+        for chunk in load_klines_chunked(&path, 30).unwrap() {
+            let chunk = chunk.unwrap();
+            peak = peak.max(chunk.len());
+            total += chunk.len();
+            for k in &chunk {
+                assert!(k.open_time > last_time, "order preserved across chunks");
+                last_time = k.open_time;
+            }
+        }
+        assert_eq!(total, 100);
+        assert!(peak <= 30, "peak chunk size {peak}");
+    }
+}
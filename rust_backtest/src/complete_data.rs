@@ -0,0 +1,170 @@
+//! "Complete dataset" collector: everything the models could want for one
+//! symbol — 1m and 15m klines, funding rates, aggTrades and an orderbook
+//! snapshot — saved as a directory of parquet files.
+
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use serde::Deserialize;
+use tracing::info;
+
+use mft_engine::data::{BinanceDataClient, Kline, TradeTick};
+
+/// One funding-rate observation.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FundingRate {
+    #[serde(rename = "fundingTime")]
+    pub funding_time: i64,
+    #[serde(rename = "fundingRate")]
+    pub funding_rate: String,
+}
+
+/// The full bundle for one symbol and date range.
+pub struct CompleteDataset {
+    pub symbol: String,
+    pub klines_1m: Vec<Kline>,
+    pub klines_15m: Vec<Kline>,
+    pub funding: Vec<(i64, f64)>,
+    pub agg_trades: Vec<TradeTick>,
+}
+
+/// Downloads the complete dataset from the Binance Futures REST API.
+pub struct CompleteDataCollector {
+    client: BinanceDataClient,
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl Default for CompleteDataCollector {
+    fn default() -> Self {
+        Self {
+            client: BinanceDataClient::default(),
+            http: reqwest::Client::new(),
+            base_url: "https://fapi.binance.com".to_string(),
+        }
+    }
+}
+
+impl CompleteDataCollector {
+    /// Download klines (1m + 15m), funding history and aggTrades.
+    pub async fn download_complete_dataset(
+        &self,
+        symbol: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> Result<CompleteDataset> {
+        info!(symbol, start_ms, end_ms, "downloading complete dataset");
+        let klines_1m = self.client.fetch_klines(symbol, "1m", start_ms, end_ms).await?;
+        let klines_15m = self.client.fetch_klines(symbol, "15m", start_ms, end_ms).await?;
+        let funding = self.fetch_funding(symbol, start_ms, end_ms).await?;
+        let agg_trades = self.fetch_agg_trades(symbol, start_ms, end_ms).await?;
+        Ok(CompleteDataset {
+            symbol: symbol.to_string(),
+            klines_1m,
+            klines_15m,
+            funding,
+            agg_trades,
+        })
+    }
+
+    async fn fetch_funding(&self, symbol: &str, start_ms: i64, end_ms: i64) -> Result<Vec<(i64, f64)>> {
+        let url = format!(
+            "{}/fapi/v1/fundingRate?symbol={}&startTime={}&endTime={}&limit=1000",
+            self.base_url, symbol, start_ms, end_ms
+        );
+        let rows: Vec<FundingRate> = self
+            .http
+            .get(&url)
+            .send()
+            .await?
+            .json()
+            .await
+            .context("funding response was not JSON")?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|r| r.funding_rate.parse::<f64>().ok().map(|v| (r.funding_time, v)))
+            .collect())
+    }
+
+    async fn fetch_agg_trades(&self, symbol: &str, start_ms: i64, end_ms: i64) -> Result<Vec<TradeTick>> {
+        let mut all = Vec::new();
+        let mut cursor = start_ms;
+        loop {
+            let url = format!(
+                "{}/fapi/v1/aggTrades?symbol={}&startTime={}&endTime={}&limit=1000",
+                self.base_url, symbol, cursor, end_ms
+            );
+            let rows: Vec<serde_json::Value> = self.http.get(&url).send().await?.json().await?;
+            if rows.is_empty() {
+                break;
+            }
+            let page: Vec<TradeTick> = rows
+                .iter()
+                .filter_map(|v| {
+                    Some(TradeTick {
+                        ts: v.get("T")?.as_i64()?,
+                        price: v.get("p")?.as_str()?.parse().ok()?,
+                        qty: v.get("q")?.as_str()?.parse().ok()?,
+                        // "m" = buyer is the maker, i.e. the aggressor sold.
+                        is_buy: !v.get("m")?.as_bool()?,
+                    })
+                })
+                .collect();
+            let last_ts = page.last().map(|t| t.ts).unwrap_or(end_ms);
+            let page_len = page.len();
+            all.extend(page);
+            if page_len < 1000 || last_ts >= end_ms {
+                break;
+            }
+            cursor = last_ts + 1;
+        }
+        Ok(all)
+    }
+}
+
+/// Persist the bundle to `<out_dir>/` as one parquet per component.
+pub fn save_complete_dataset(ds: &CompleteDataset, out_dir: &str) -> Result<()> {
+    std::fs::create_dir_all(out_dir)?;
+    crate::fetch_data::save_klines_parquet(&ds.klines_1m, out_dir, &ds.symbol, "1m")?;
+    crate::fetch_data::save_klines_parquet(&ds.klines_15m, out_dir, &ds.symbol, "15m")?;
+
+    let mut funding_df = df![
+        "funding_time" => ds.funding.iter().map(|(t, _)| *t).collect::<Vec<_>>(),
+        "funding_rate" => ds.funding.iter().map(|(_, r)| *r).collect::<Vec<_>>(),
+    ]?;
+    let path = format!("{out_dir}/{}_funding.parquet", ds.symbol);
+    ParquetWriter::new(std::fs::File::create(&path)?).finish(&mut funding_df)?;
+
+    let mut trades_df = df![
+        "ts" => ds.agg_trades.iter().map(|t| t.ts).collect::<Vec<_>>(),
+        "price" => ds.agg_trades.iter().map(|t| t.price).collect::<Vec<_>>(),
+        "qty" => ds.agg_trades.iter().map(|t| t.qty).collect::<Vec<_>>(),
+        "is_buy" => ds.agg_trades.iter().map(|t| t.is_buy).collect::<Vec<_>>(),
+    ]?;
+    let path = format!("{out_dir}/{}_trades.parquet", ds.symbol);
+    ParquetWriter::new(std::fs::File::create(&path)?).finish(&mut trades_df)?;
+
+    info!(out_dir, "complete dataset saved");
+    Ok(())
+}
+
+/// Load a `<symbol>_trades.parquet` written by [`save_complete_dataset`]
+/// back into ticks, in file order.
+pub fn load_trades_parquet(path: &str) -> Result<Vec<TradeTick>> {
+    let df = LazyFrame::scan_parquet(path, Default::default())?
+        .collect()
+        .with_context(|| format!("reading {path}"))?;
+    let ts = df.column("ts")?.i64()?;
+    let price = df.column("price")?.f64()?;
+    let qty = df.column("qty")?.f64()?;
+    let is_buy = df.column("is_buy")?.bool()?;
+    let mut out = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        out.push(TradeTick {
+            ts: ts.get(i).unwrap(),
+            price: price.get(i).unwrap(),
+            qty: qty.get(i).unwrap(),
+            is_buy: is_buy.get(i).unwrap(),
+        });
+    }
+    Ok(out)
+}
@@ -0,0 +1,91 @@
+//! Conversions from our [`Kline`] records into Nautilus data objects.
+
+use nautilus_model::data::{Bar, BarSpecification, BarType, QuoteTick, TradeTick};
+use nautilus_model::enums::{AggregationSource, AggressorSide, BarAggregation, PriceType};
+use nautilus_model::identifiers::{InstrumentId, TradeId};
+use nautilus_model::types::{Price, Quantity};
+
+use mft_engine::data::Kline;
+
+/// Nanoseconds per millisecond, for Binance ms → Nautilus ns timestamps.
+pub const NS_PER_MS: u64 = 1_000_000;
+
+/// Convert a kline into a Nautilus `Bar` (1-minute, last-price aggregation).
+pub fn kline_to_bar(kline: &Kline, instrument_id: InstrumentId) -> Bar {
+    let bar_type = BarType::new(
+        instrument_id,
+        BarSpecification::new(1, BarAggregation::Minute, PriceType::Last),
+        AggregationSource::External,
+    );
+    let ts = (kline.close_time as u64) * NS_PER_MS;
+    Bar::new(
+        bar_type,
+        Price::from(format!("{:.8}", kline.open).as_str()),
+        Price::from(format!("{:.8}", kline.high).as_str()),
+        Price::from(format!("{:.8}", kline.low).as_str()),
+        Price::from(format!("{:.8}", kline.close).as_str()),
+        Quantity::from(format!("{:.8}", kline.volume).as_str()),
+        ts.into(),
+        ts.into(),
+    )
+}
+
+/// Synthesize a top-of-book quote from the bar close with a nominal spread.
+pub fn kline_to_quote(kline: &Kline, instrument_id: InstrumentId) -> QuoteTick {
+    let spread = kline.close * 0.0001;
+    let ts = (kline.close_time as u64) * NS_PER_MS;
+    QuoteTick::new(
+        instrument_id,
+        Price::from(format!("{:.8}", kline.close - spread / 2.0).as_str()),
+        Price::from(format!("{:.8}", kline.close + spread / 2.0).as_str()),
+        Quantity::from(format!("{:.8}", kline.volume / 2.0).as_str()),
+        Quantity::from(format!("{:.8}", kline.volume / 2.0).as_str()),
+        ts.into(),
+        ts.into(),
+    )
+}
+
+/// Collapse the bar into one trade print at the close.
+pub fn kline_to_trade(kline: &Kline, instrument_id: InstrumentId, trade_id: u64) -> TradeTick {
+    let tick = kline.to_tick();
+    let ts = (tick.ts as u64) * NS_PER_MS;
+    TradeTick::new(
+        instrument_id,
+        Price::from(format!("{:.8}", tick.price).as_str()),
+        Quantity::from(format!("{:.8}", tick.qty).as_str()),
+        if tick.is_buy {
+            AggressorSide::Buyer
+        } else {
+            AggressorSide::Seller
+        },
+        TradeId::new(trade_id.to_string()),
+        ts.into(),
+        ts.into(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kline_converts_to_a_bar_with_ns_timestamps() {
+        let kline = Kline {
+            open_time: 0,
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.5,
+            volume: 12.5,
+            close_time: 59_999,
+            quote_volume: 1_256.25,
+            n_trades: 10,
+            taker_buy_volume: 7.0,
+        };
+        let bar = kline_to_bar(&kline, InstrumentId::from("BTCUSDT-PERP.BINANCE"));
+        assert_eq!(bar.open.as_f64(), 100.0);
+        assert_eq!(bar.close.as_f64(), 100.5);
+        assert_eq!(bar.volume.as_f64(), 12.5);
+        assert_eq!(bar.ts_event.as_u64(), 59_999 * NS_PER_MS);
+    }
+}
@@ -0,0 +1,83 @@
+//! Download klines from Binance and persist them as parquet for backtests.
+
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use tracing::info;
+
+use mft_engine::data::{BinanceDataClient, Kline};
+
+/// Write `klines` to `<out_dir>/<symbol>_<interval>.parquet`.
+///
+/// Columns: `open_time` (i64 ms), `open/high/low/close/volume` (f64),
+/// `close_time` (i64 ms), `quote_asset_volume` (f64), `n_trades` (i64),
+/// `taker_buy_volume` (f64).
+pub fn save_klines_parquet(klines: &[Kline], out_dir: &str, symbol: &str, interval: &str) -> Result<String> {
+    std::fs::create_dir_all(out_dir)?;
+    let mut df = df![
+        "open_time" => klines.iter().map(|k| k.open_time).collect::<Vec<_>>(),
+        "open" => klines.iter().map(|k| k.open).collect::<Vec<_>>(),
+        "high" => klines.iter().map(|k| k.high).collect::<Vec<_>>(),
+        "low" => klines.iter().map(|k| k.low).collect::<Vec<_>>(),
+        "close" => klines.iter().map(|k| k.close).collect::<Vec<_>>(),
+        "volume" => klines.iter().map(|k| k.volume).collect::<Vec<_>>(),
+        "close_time" => klines.iter().map(|k| k.close_time).collect::<Vec<_>>(),
+        "quote_asset_volume" => klines.iter().map(|k| k.quote_volume).collect::<Vec<_>>(),
+        "n_trades" => klines.iter().map(|k| k.n_trades as i64).collect::<Vec<_>>(),
+        "taker_buy_volume" => klines.iter().map(|k| k.taker_buy_volume).collect::<Vec<_>>(),
+    ]?;
+    let path = format!("{out_dir}/{symbol}_{interval}.parquet");
+    let file = std::fs::File::create(&path).with_context(|| format!("creating {path}"))?;
+    ParquetWriter::new(file).finish(&mut df)?;
+    info!(path, rows = klines.len(), "saved klines");
+    Ok(path)
+}
+
+/// Load a klines parquet written by [`save_klines_parquet`] back into
+/// memory, in file order. Expects the canonical column set above; for
+/// third-party files with alias column names use the simple backtest CLI,
+/// which normalizes schemas on load.
+pub fn load_klines_parquet(path: &str) -> Result<Vec<Kline>> {
+    let df = LazyFrame::scan_parquet(path, Default::default())?
+        .collect()
+        .with_context(|| format!("reading {path}"))?;
+    let open_time = df.column("open_time")?.i64()?;
+    let open = df.column("open")?.f64()?;
+    let high = df.column("high")?.f64()?;
+    let low = df.column("low")?.f64()?;
+    let close = df.column("close")?.f64()?;
+    let volume = df.column("volume")?.f64()?;
+    let close_time = df.column("close_time")?.i64()?;
+    let quote_volume = df.column("quote_asset_volume")?.f64()?;
+    let n_trades = df.column("n_trades")?.i64()?;
+    let taker_buy = df.column("taker_buy_volume")?.f64()?;
+    let mut out = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        out.push(Kline {
+            open_time: open_time.get(i).context("null open_time")?,
+            open: open.get(i).context("null open")?,
+            high: high.get(i).context("null high")?,
+            low: low.get(i).context("null low")?,
+            close: close.get(i).context("null close")?,
+            volume: volume.get(i).context("null volume")?,
+            close_time: close_time.get(i).context("null close_time")?,
+            quote_volume: quote_volume.get(i).unwrap_or(0.0),
+            n_trades: n_trades.get(i).unwrap_or(0) as u64,
+            taker_buy_volume: taker_buy.get(i).unwrap_or(0.0),
+        });
+    }
+    Ok(out)
+}
+
+/// Fetch and persist a date range of klines in one call.
+pub async fn download_klines(
+    client: &BinanceDataClient,
+    symbol: &str,
+    interval: &str,
+    start_ms: i64,
+    end_ms: i64,
+    out_dir: &str,
+) -> Result<String> {
+    let klines = client.fetch_klines(symbol, interval, start_ms, end_ms).await?;
+    anyhow::ensure!(!klines.is_empty(), "no klines returned for {symbol}");
+    save_klines_parquet(&klines, out_dir, symbol, interval)
+}
@@ -0,0 +1,152 @@
+//! Per-symbol exchange filters (Binance USDⓈ-M Futures), shared by the
+//! Nautilus adapter and the simple engine so order prices and sizes are
+//! snapped to the same increments everywhere.
+//!
+//! The built-in table covers the majors; extra symbols can be loaded from
+//! a TOML file at startup via [`from_toml`] + [`register_specs`] without
+//! recompiling.
+
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Price/size precision and increments for one symbol.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstrumentSpec {
+    pub symbol: String,
+    pub price_prec: u8,
+    pub size_prec: u8,
+    pub price_incr: f64,
+    pub size_incr: f64,
+}
+
+impl InstrumentSpec {
+    /// Round a price to the nearest valid tick (`price_incr`).
+    pub fn snap_price(&self, px: f64) -> f64 {
+        (px / self.price_incr).round() * self.price_incr
+    }
+
+    /// Round a quantity *down* to the step size (`size_incr`), matching how
+    /// the exchange truncates order quantities.
+    pub fn snap_qty(&self, qty: f64) -> f64 {
+        (qty / self.size_incr).floor() * self.size_incr
+    }
+}
+
+fn builtin_specs() -> Vec<InstrumentSpec> {
+    let spec = |symbol: &str, price_prec, size_prec, price_incr, size_incr| InstrumentSpec {
+        symbol: symbol.to_string(),
+        price_prec,
+        size_prec,
+        price_incr,
+        size_incr,
+    };
+    vec![
+        spec("BTCUSDT", 1, 3, 0.1, 0.001),
+        spec("ETHUSDT", 2, 3, 0.01, 0.001),
+        spec("SOLUSDT", 2, 0, 0.01, 1.0),
+        spec("XRPUSDT", 4, 1, 0.0001, 0.1),
+        spec("DOGEUSDT", 5, 0, 0.00001, 1.0),
+    ]
+}
+
+/// Process-wide spec table: the builtins, plus whatever [`register_specs`]
+/// added before the first lookup.
+static SPECS: OnceLock<Vec<InstrumentSpec>> = OnceLock::new();
+
+/// Parse user specs from TOML of the form:
+///
+/// ```toml
+/// [[instrument]]
+/// symbol = "AVAXUSDT"
+/// price_prec = 3
+/// size_prec = 0
+/// price_incr = 0.001
+/// size_incr = 1.0
+/// ```
+pub fn from_toml(content: &str) -> Result<Vec<InstrumentSpec>> {
+    #[derive(Deserialize)]
+    struct SpecFile {
+        instrument: Vec<InstrumentSpec>,
+    }
+    let file: SpecFile = toml::from_str(content).context("parsing instrument spec TOML")?;
+    Ok(file.instrument)
+}
+
+/// Install `extra` specs on top of the builtins. A user spec with the same
+/// symbol as a builtin overrides it. Must run before the first
+/// [`find_spec`] call, which freezes the table.
+pub fn register_specs(extra: Vec<InstrumentSpec>) -> Result<()> {
+    let mut specs = builtin_specs();
+    for spec in extra {
+        specs.retain(|s| s.symbol != spec.symbol);
+        specs.push(spec);
+    }
+    SPECS
+        .set(specs)
+        .map_err(|_| anyhow::anyhow!("instrument specs already frozen by an earlier lookup"))
+}
+
+pub fn find_spec(symbol: &str) -> Option<&'static InstrumentSpec> {
+    SPECS
+        .get_or_init(builtin_specs)
+        .iter()
+        .find(|s| s.symbol == symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_symbol_has_the_right_precision() {
+        let spec = find_spec("BTCUSDT").unwrap();
+        assert_eq!(spec.price_prec, 1);
+        assert_eq!(spec.size_prec, 3);
+        assert!((spec.size_incr - 0.001).abs() < 1e-12);
+    }
+
+    #[test]
+    fn price_snaps_to_tick_size() {
+        let spec = find_spec("BTCUSDT").unwrap();
+        // A sub-tick price lands on an exact multiple of the tick.
+        let snapped = spec.snap_price(50_000.123_456);
+        assert!((snapped - 50_000.1).abs() < 1e-9);
+        let ticks = snapped / spec.price_incr;
+        assert!((ticks - ticks.round()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn quantity_snaps_down_to_step_size() {
+        let spec = find_spec("BTCUSDT").unwrap();
+        assert!((spec.snap_qty(0.001_49) - 0.001).abs() < 1e-12);
+        let sol = find_spec("SOLUSDT").unwrap();
+        assert_eq!(sol.snap_qty(2.7), 2.0);
+    }
+
+    #[test]
+    fn unknown_symbol_has_no_spec() {
+        assert!(find_spec("NOPEUSDT").is_none());
+    }
+
+    #[test]
+    fn toml_specs_parse_into_the_spec_struct() {
+        let parsed = from_toml(
+            r#"
+            [[instrument]]
+            symbol = "AVAXUSDT"
+            price_prec = 3
+            size_prec = 0
+            price_incr = 0.001
+            size_incr = 1.0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].symbol, "AVAXUSDT");
+        assert!((parsed[0].price_incr - 0.001).abs() < 1e-12);
+
+        assert!(from_toml("instrument = 3").is_err());
+    }
+}
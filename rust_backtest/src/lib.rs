@@ -0,0 +1,20 @@
+//! Backtest harness for the MFT engine.
+//!
+//! Two execution paths:
+//! - [`backtest`] — full Nautilus Trader engine with synthesized tick events
+//!   per candle and the [`vortex_strategy::VortexStrategy`] adapter.
+//! - [`simple_engine`] — a fast bar-replay engine
+//!   ([`simple_engine::SimpleBacktestEngine`]) for parameter iteration.
+
+pub mod backtest;
+pub mod batch;
+pub mod complete_data;
+pub mod data_adapter;
+pub mod fetch_data;
+pub mod instruments;
+pub mod montecarlo;
+pub mod reporting;
+pub mod sensitivity;
+pub mod simple_engine;
+pub mod vortex_strategy;
+pub mod walk_forward;
@@ -0,0 +1,430 @@
+//! Unified backtest CLI: run the Nautilus backtest, validate configuration,
+//! or analyze a saved report.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Parser, Subcommand};
+use tracing::info;
+
+use mft_engine::config::AppConfig;
+use mft_engine::metrics::compute_metrics;
+use rust_backtest::reporting::{BacktestReport, ReportConfig, ReportGenerator};
+
+#[derive(Parser)]
+#[command(name = "rust_backtest", about = "MFT engine backtest harness")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a backtest over a date range.
+    Run {
+        /// Symbols to trade, comma-separated.
+        #[arg(long, default_value = "BTCUSDT")]
+        symbols: String,
+        /// Start date (YYYY-MM-DD).
+        #[arg(long)]
+        start_date: String,
+        /// End date (YYYY-MM-DD).
+        #[arg(long)]
+        end_date: String,
+        /// Directory holding the parquet data.
+        #[arg(long, default_value = "data")]
+        data_dir: String,
+        /// TOML config path.
+        #[arg(long)]
+        config: Option<String>,
+        /// Initial balance in quote currency.
+        #[arg(long, default_value_t = 5_000.0)]
+        balance: f64,
+    },
+    /// Validate the configuration and data paths without running.
+    Validate {
+        #[arg(long)]
+        config: Option<String>,
+    },
+    /// Re-analyze a previously saved JSON report.
+    Analyze {
+        /// Path to a `*_report.json` file.
+        file: String,
+    },
+    /// Download a complete dataset (1m/15m klines, funding, aggTrades).
+    Download {
+        /// Symbol to download.
+        #[arg(long, default_value = "BTCUSDT")]
+        symbol: String,
+        /// Start date (YYYY-MM-DD).
+        #[arg(long)]
+        start_date: String,
+        /// End date (YYYY-MM-DD).
+        #[arg(long)]
+        end_date: String,
+        /// Directory the parquet files are written to.
+        #[arg(long, default_value = "data")]
+        out_dir: String,
+    },
+}
+
+/// Bootstrap settings for the report's robustness bands. The seed is fixed
+/// so re-running a backtest reproduces the same bands.
+const BOOTSTRAP_SIMS: usize = 1_000;
+const BOOTSTRAP_BLOCK: usize = 10;
+const BOOTSTRAP_SEED: u64 = 42;
+
+struct UnifiedBacktestApp {
+    config: AppConfig,
+    report_config: ReportConfig,
+    /// Top-level keys read from the TOML config, for `Validate` output.
+    loaded_keys: Vec<String>,
+}
+
+impl UnifiedBacktestApp {
+    fn new(config_path: Option<&str>) -> Result<Self> {
+        let (config, loaded_keys) = Self::load_config(config_path)?;
+        Ok(Self {
+            config,
+            report_config: ReportConfig::default(),
+            loaded_keys,
+        })
+    }
+
+    /// Load the app config from a TOML file, or fall back to the
+    /// environment/defaults when no path is given. Also returns the keys
+    /// the file set, so `Validate` can report them.
+    fn load_config(path: Option<&str>) -> Result<(AppConfig, Vec<String>)> {
+        let Some(path) = path else {
+            return Ok((AppConfig::from_env(), Vec::new()));
+        };
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("reading config {path}"))?;
+        let table: toml::Table =
+            toml::from_str(&content).with_context(|| format!("{path} is not valid TOML"))?;
+        let mut keys: Vec<String> = table.keys().cloned().collect();
+        keys.sort();
+        let config =
+            AppConfig::from_toml_str(&content).with_context(|| format!("loading config {path}"))?;
+        Ok((config, keys))
+    }
+
+    fn run_backtest(
+        &mut self,
+        symbols: &[String],
+        start_date: &str,
+        end_date: &str,
+        data_dir: &str,
+        balance: f64,
+    ) -> Result<()> {
+        let (start_time, end_time) = resolve_date_range(start_date, end_date)?;
+
+        self.config.initial_capital = balance;
+        let strategy =
+            rust_backtest::backtest::run_nautilus_backtest(&self.config, symbols, data_dir)?;
+        strategy.print_summary();
+
+        let trades = strategy.all_trades();
+        let pnls: Vec<f64> = trades.iter().map(|t| t.pnl_frac).collect();
+        let mut equity = vec![balance];
+        for p in &pnls {
+            equity.push(equity.last().unwrap() * (1.0 + p));
+        }
+        let equity_curve: Vec<(i64, f64)> = trades
+            .iter()
+            .zip(equity.iter().skip(1))
+            .map(|(t, e)| ((t.exit_ts / 1_000_000) as i64, *e))
+            .collect();
+        let generator = ReportGenerator::new(self.report_config.clone());
+        let report = BacktestReport {
+            symbol: symbols.join("+"),
+            quote_currency: self.config.quote_currency.clone(),
+            start_time: start_time.to_rfc3339(),
+            end_time: end_time.to_rfc3339(),
+            perf: compute_metrics(&equity, &pnls, 525_600.0)
+                .with_excursions(
+                    &trades.iter().map(|t| t.mae_frac).collect::<Vec<_>>(),
+                    &trades.iter().map(|t| t.mfe_frac).collect::<Vec<_>>(),
+                )
+                .with_exit_reasons(
+                    &trades
+                        .iter()
+                        .map(|t| (t.exit_reason, t.pnl_frac))
+                        .collect::<Vec<_>>(),
+                ),
+            mft_analytics: generator.generate_mft_analytics(
+                &trades,
+                Some(strategy.vpin_threshold_hits()),
+                &strategy.vol_calibration_pairs(),
+            ),
+            // The Nautilus path's equity curve is trade-indexed, so no
+            // bar benchmark aligns with it; beta/alpha stay unreported.
+            risk_metrics: generator.calculate_risk_metrics(&equity_curve, &[]),
+            equity_curve,
+            trades: Vec::new(),
+            turnover: 0.0,
+            turnover_annualized: 0.0,
+            capacity_usd: {
+                // Average net edge per trade vs the impact of its own size.
+                let avg_edge = if pnls.is_empty() {
+                    0.0
+                } else {
+                    pnls.iter().sum::<f64>() / pnls.len() as f64
+                };
+                rust_backtest::reporting::estimate_capacity_usd(
+                    avg_edge,
+                    strategy.avg_bar_quote_volume(),
+                )
+            },
+            holding_histogram: Vec::new(),
+            regime_analysis: {
+                let (low, high) = strategy.regime_bar_counts();
+                rust_backtest::reporting::RegimeAnalysis::from_trades(&trades, low, high)
+            },
+            bootstrap: rust_backtest::montecarlo::bootstrap_returns(
+                &pnls,
+                BOOTSTRAP_SIMS,
+                BOOTSTRAP_BLOCK,
+                BOOTSTRAP_SEED,
+            ),
+        };
+        print_backtest_summary(&report);
+
+        let json_path = generator.save_json(&report)?;
+        let html_path = generator.save_html(&report)?;
+        let returns_path = format!(
+            "{}/{}_returns.csv",
+            self.report_config.output_dir, report.symbol
+        );
+        rust_backtest::reporting::export_quantstats_csv(&report.equity_curve, &returns_path)?;
+        info!(json_path, html_path, returns_path, "reports saved");
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        println!("symbol:   {}", self.config.symbol);
+        println!("interval: {}", self.config.kline_interval);
+        println!("ou_window: {}", self.config.ou_window);
+        if self.loaded_keys.is_empty() {
+            println!("config source: defaults + environment");
+        } else {
+            println!("loaded keys: {}", self.loaded_keys.join(", "));
+        }
+        println!("config OK");
+        Ok(())
+    }
+
+    fn analyze_results(&self, file: &str) -> Result<()> {
+        let content =
+            std::fs::read_to_string(file).with_context(|| format!("reading {file}"))?;
+        let mut report: BacktestReport = serde_json::from_str(&content)
+            .with_context(|| format!("{file} does not contain a valid BacktestReport"))?;
+
+        // Re-derive everything the stored equity curve and blotter can
+        // reproduce, so an old report picks up later metric fixes.
+        let generator = ReportGenerator::new(self.report_config.clone());
+        // A saved report carries no kline series to rebuild a benchmark.
+        report.risk_metrics = generator.calculate_risk_metrics(&report.equity_curve, &[]);
+        report.turnover =
+            rust_backtest::reporting::compute_turnover(&report.trades, &report.equity_curve);
+        report.turnover_annualized = rust_backtest::reporting::compute_turnover_annualized(
+            &report.trades,
+            &report.equity_curve,
+        );
+        report.holding_histogram =
+            rust_backtest::reporting::holding_period_histogram(&report.trades);
+
+        print_backtest_summary(&report);
+        let html_path = generator.save_html(&report)?;
+        info!(html_path, "analysis regenerated");
+        Ok(())
+    }
+}
+
+/// Download the complete dataset for `symbol` over a date range and save
+/// it under `out_dir`, printing row counts per component.
+fn download_dataset(symbol: &str, start_date: &str, end_date: &str, out_dir: &str) -> Result<()> {
+    let (start_time, end_time) = resolve_date_range(start_date, end_date)?;
+    std::fs::create_dir_all(out_dir).with_context(|| format!("creating {out_dir}"))?;
+
+    let collector = rust_backtest::complete_data::CompleteDataCollector::default();
+    let runtime = tokio::runtime::Runtime::new()?;
+    let dataset = runtime.block_on(collector.download_complete_dataset(
+        symbol,
+        start_time.timestamp_millis(),
+        end_time.timestamp_millis(),
+    ))?;
+    rust_backtest::complete_data::save_complete_dataset(&dataset, out_dir)?;
+
+    println!("Dataset saved to {out_dir}/");
+    println!("  {symbol}_1m klines:   {}", dataset.klines_1m.len());
+    println!("  {symbol}_15m klines:  {}", dataset.klines_15m.len());
+    println!("  {symbol}_funding:     {}", dataset.funding.len());
+    println!("  {symbol}_trades:      {}", dataset.agg_trades.len());
+    Ok(())
+}
+
+/// Parse and sanity-check the backtest date range. Future end dates are
+/// clamped to now *before* the ordering check, so a start date that is
+/// itself in the future fails with a clear error instead of producing an
+/// empty range.
+fn resolve_date_range(start_date: &str, end_date: &str) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let start_time = parse_date(start_date)?;
+    let mut end_time = parse_date(end_date)?;
+    if end_time > Utc::now() {
+        info!("end date is in the future, clamping to now");
+        end_time = Utc::now();
+    }
+    if start_time >= end_time {
+        bail!("start date {start_date} must be before end date {end_date} (after clamping to now)");
+    }
+    Ok((start_time, end_time))
+}
+
+fn parse_date(s: &str) -> Result<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .with_context(|| format!("invalid date {s}, expected YYYY-MM-DD"))?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+/// Width of the separator rules in the console summary.
+const SUMMARY_WIDTH: usize = 60;
+
+fn print_backtest_summary(report: &BacktestReport) {
+    let rule = "=".repeat(SUMMARY_WIDTH);
+    println!("\n{rule}");
+    println!("  BACKTEST SUMMARY — {}", report.symbol);
+    println!("{rule}");
+    println!("  Period: {} → {}", report.start_time, report.end_time);
+    print!("{}", report.perf);
+    println!("{rule}");
+}
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Run {
+            symbols,
+            start_date,
+            end_date,
+            data_dir,
+            config,
+            balance,
+        } => {
+            let symbols: Vec<String> = symbols.split(',').map(|s| s.trim().to_string()).collect();
+            let mut app = UnifiedBacktestApp::new(config.as_deref())?;
+            app.run_backtest(&symbols, &start_date, &end_date, &data_dir, balance)
+        }
+        Command::Validate { config } => UnifiedBacktestApp::new(config.as_deref())?.validate(),
+        Command::Analyze { file } => UnifiedBacktestApp::new(None)?.analyze_results(&file),
+        Command::Download {
+            symbol,
+            start_date,
+            end_date,
+            out_dir,
+        } => download_dataset(&symbol, &start_date, &end_date, &out_dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_printer_runs_on_a_minimal_report() {
+        use rust_backtest::reporting::{ModelPerformance, RegimeAnalysis, RiskMetrics};
+
+        let equity: Vec<(i64, f64)> =
+            (0..10).map(|i| (i * 60_000, 1000.0 + i as f64)).collect();
+        let levels: Vec<f64> = equity.iter().map(|(_, e)| *e).collect();
+        let report = BacktestReport {
+            symbol: "BTCUSDT".to_string(),
+            quote_currency: "USDT".to_string(),
+            start_time: "2024-01-01T00:00:00Z".to_string(),
+            end_time: "2024-01-02T00:00:00Z".to_string(),
+            perf: compute_metrics(&levels, &[0.01, -0.005], 525_600.0),
+            equity_curve: equity,
+            trades: Vec::new(),
+            turnover: 0.0,
+            turnover_annualized: 0.0,
+            capacity_usd: None,
+            holding_histogram: Vec::new(),
+            mft_analytics: ModelPerformance {
+                garch_calibration_r2: None,
+                vol_reliability: Vec::new(),
+                ou_mean_reversion_success: None,
+                vpin_threshold_hits: None,
+            },
+            risk_metrics: RiskMetrics {
+                var_95: None,
+                cvar_95: None,
+                annualized_volatility: None,
+                beta: None,
+                alpha: None,
+                information_ratio: None,
+            },
+            regime_analysis: RegimeAnalysis {
+                high_vol_periods: 0,
+                low_vol_periods: 0,
+                win_rate_by_regime: Vec::new(),
+            },
+            bootstrap: None,
+        };
+        // Smoke test: the whole function formats and prints without panicking.
+        print_backtest_summary(&report);
+    }
+
+    #[test]
+    fn valid_past_range_is_accepted() {
+        let (start, end) = resolve_date_range("2024-01-01", "2024-02-01").unwrap();
+        assert!(start < end);
+    }
+
+    #[test]
+    fn future_end_is_clamped_to_now() {
+        let (_, end) = resolve_date_range("2024-01-01", "2999-01-01").unwrap();
+        assert!(end <= Utc::now());
+    }
+
+    #[test]
+    fn download_subcommand_parses_with_required_args() {
+        let cli = Cli::try_parse_from([
+            "rust_backtest",
+            "download",
+            "--symbol",
+            "ETHUSDT",
+            "--start-date",
+            "2024-01-01",
+            "--end-date",
+            "2024-02-01",
+            "--out-dir",
+            "tmp_data",
+        ])
+        .unwrap();
+        match cli.command {
+            Command::Download {
+                symbol,
+                start_date,
+                end_date,
+                out_dir,
+            } => {
+                assert_eq!(symbol, "ETHUSDT");
+                assert_eq!(start_date, "2024-01-01");
+                assert_eq!(end_date, "2024-02-01");
+                assert_eq!(out_dir, "tmp_data");
+            }
+            _ => panic!("expected the download subcommand"),
+        }
+
+        // The dates are required.
+        assert!(Cli::try_parse_from(["rust_backtest", "download"]).is_err());
+    }
+
+    #[test]
+    fn future_start_fails_after_clamp() {
+        // Both dates are in the future; the end clamps to now, which makes
+        // the range inverted and must error out clearly.
+        let err = resolve_date_range("2998-01-01", "2999-01-01").unwrap_err();
+        assert!(err.to_string().contains("before end date"), "{err}");
+    }
+}
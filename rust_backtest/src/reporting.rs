@@ -0,0 +1,1027 @@
+//! Report generation: JSON/HTML output for a finished backtest.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use mft_engine::metrics::{safe_div, PerfReport};
+
+use mft_engine::data::Kline;
+use mft_engine::engine::VolRegime;
+
+use crate::simple_engine::Trade;
+use crate::vortex_strategy::TradeRecord;
+
+/// Model-diagnostic analytics for the MFT stack. Fields are `None` when the
+/// run did not record the data needed to compute them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPerformance {
+    /// R² of a regression of realized per-bar |return| on the GARCH
+    /// one-step-ahead σ forecast. 1.0 would be a perfectly calibrated
+    /// forecast; `None` with too few forecast/realized pairs.
+    pub garch_calibration_r2: Option<f64>,
+    /// Reliability table: forecast deciles vs mean realized vol. Empty
+    /// when there are too few pairs to form deciles.
+    pub vol_reliability: Vec<VolReliabilityRow>,
+    /// Fraction of trades that exited via take-profit, i.e. reverted to the
+    /// OU mean as predicted.
+    pub ou_mean_reversion_success: Option<f64>,
+    /// Number of bars where VPIN exceeded the configured threshold.
+    pub vpin_threshold_hits: Option<usize>,
+}
+
+/// One forecast-decile row of the volatility reliability table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolReliabilityRow {
+    /// Forecast decile, 0 (lowest σ forecasts) through 9.
+    pub decile: usize,
+    pub mean_forecast: f64,
+    pub mean_realized: f64,
+    pub n: usize,
+}
+
+/// R² of the OLS regression of realized |return| on forecast σ — for a
+/// single regressor this is the squared Pearson correlation. `None` with
+/// fewer than [`MIN_RISK_SAMPLES`] pairs or a degenerate forecast series.
+pub fn garch_calibration_r2(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < MIN_RISK_SAMPLES {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let mut cov = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x <= 0.0 || var_y <= 0.0 {
+        return None;
+    }
+    Some(cov * cov / (var_x * var_y))
+}
+
+/// Group the forecast/realized pairs into forecast deciles and report the
+/// mean forecast and mean realized vol per decile. Empty with fewer than
+/// ten pairs.
+pub fn vol_reliability_table(pairs: &[(f64, f64)]) -> Vec<VolReliabilityRow> {
+    if pairs.len() < 10 {
+        return Vec::new();
+    }
+    let mut sorted = pairs.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let n = sorted.len();
+    (0..10)
+        .map(|decile| {
+            let lo = decile * n / 10;
+            let hi = (decile + 1) * n / 10;
+            let bucket = &sorted[lo..hi];
+            let m = bucket.len() as f64;
+            VolReliabilityRow {
+                decile,
+                mean_forecast: bucket.iter().map(|(x, _)| x).sum::<f64>() / m,
+                mean_realized: bucket.iter().map(|(_, y)| y).sum::<f64>() / m,
+                n: bucket.len(),
+            }
+        })
+        .collect()
+}
+
+/// Portfolio-level risk metrics. Fields are `None` when the run did not
+/// record the data needed to compute them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskMetrics {
+    /// 95% one-period Value-at-Risk (negative fraction).
+    pub var_95: Option<f64>,
+    /// 95% conditional VaR / expected shortfall.
+    pub cvar_95: Option<f64>,
+    /// Annualized volatility of the equity-curve returns.
+    pub annualized_volatility: Option<f64>,
+    /// OLS beta of strategy returns on the benchmark returns.
+    pub beta: Option<f64>,
+    /// Annualized excess return over what beta exposure explains.
+    pub alpha: Option<f64>,
+    /// Annualized mean active return over its own volatility.
+    pub information_ratio: Option<f64>,
+}
+
+/// Minimum return observations before quantile-based risk metrics are
+/// reported; below this the empirical tail is too sparse to be meaningful.
+const MIN_RISK_SAMPLES: usize = 20;
+
+/// Empirical one-period Value-at-Risk at `confidence` (e.g. 0.95): the
+/// return at the `1 - confidence` quantile, linearly interpolated between
+/// order statistics. Negative when the tail loses money. `None` with fewer
+/// than [`MIN_RISK_SAMPLES`] observations.
+pub fn empirical_var(returns: &[f64], confidence: f64) -> Option<f64> {
+    if returns.len() < MIN_RISK_SAMPLES {
+        return None;
+    }
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let pos = (1.0 - confidence) * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    let frac = pos - lo as f64;
+    Some(sorted[lo] * (1.0 - frac) + sorted[hi] * frac)
+}
+
+/// Per-period simple returns of buying and holding the bar series — the
+/// natural benchmark for a single-symbol run.
+pub fn buy_and_hold_returns(klines: &[Kline]) -> Vec<f64> {
+    klines
+        .windows(2)
+        .map(|w| safe_div(w[1].close - w[0].close, w[0].close))
+        .collect()
+}
+
+/// Beta (OLS of strategy returns on benchmark returns), annualized alpha,
+/// and annualized information ratio over the overlapping sample. All
+/// `None` with fewer than [`MIN_RISK_SAMPLES`] overlapping periods or a
+/// flat benchmark.
+fn relative_metrics(
+    returns: &[f64],
+    benchmark: &[f64],
+    periods_per_year: f64,
+) -> (Option<f64>, Option<f64>, Option<f64>) {
+    let n = returns.len().min(benchmark.len());
+    if n < MIN_RISK_SAMPLES {
+        return (None, None, None);
+    }
+    let (rs, rb) = (&returns[..n], &benchmark[..n]);
+    let mean = |xs: &[f64]| xs.iter().sum::<f64>() / n as f64;
+    let (ms, mb) = (mean(rs), mean(rb));
+    let cov = rs.iter().zip(rb).map(|(s, b)| (s - ms) * (b - mb)).sum::<f64>() / n as f64;
+    let var_b = rb.iter().map(|b| (b - mb).powi(2)).sum::<f64>() / n as f64;
+    if var_b <= 0.0 {
+        return (None, None, None);
+    }
+    let beta = cov / var_b;
+    let alpha = (ms - beta * mb) * periods_per_year;
+    let active: Vec<f64> = rs.iter().zip(rb).map(|(s, b)| s - b).collect();
+    let ma = mean(&active);
+    let sd = (active.iter().map(|a| (a - ma).powi(2)).sum::<f64>() / n as f64).sqrt();
+    // Tracking error at float-noise level means the series are identical;
+    // the ratio of two epsilons would report an arbitrary IR.
+    let information_ratio = if sd < 1e-12 {
+        0.0
+    } else {
+        safe_div(ma, sd) * periods_per_year.sqrt()
+    };
+    (Some(beta), Some(alpha), Some(information_ratio))
+}
+
+/// Empirical conditional VaR (expected shortfall): the mean of returns at
+/// or below the VaR quantile.
+pub fn empirical_cvar(returns: &[f64], confidence: f64) -> Option<f64> {
+    let var = empirical_var(returns, confidence)?;
+    let tail: Vec<f64> = returns.iter().copied().filter(|r| *r <= var).collect();
+    if tail.is_empty() {
+        return Some(var);
+    }
+    Some(tail.iter().sum::<f64>() / tail.len() as f64)
+}
+
+/// Volatility-regime summary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeAnalysis {
+    pub high_vol_periods: usize,
+    pub low_vol_periods: usize,
+    /// Win rate of trades grouped by the regime at entry:
+    /// `(regime, n_trades, win_rate)`. Regimes with no trades are omitted.
+    pub win_rate_by_regime: Vec<(VolRegime, usize, f64)>,
+}
+
+impl RegimeAnalysis {
+    /// Build from closed trades plus the engine's per-regime bar counts.
+    pub fn from_trades(
+        trades: &[&TradeRecord],
+        low_vol_periods: usize,
+        high_vol_periods: usize,
+    ) -> Self {
+        let mut win_rate_by_regime = Vec::new();
+        for regime in [VolRegime::Low, VolRegime::Normal, VolRegime::High] {
+            let n = trades.iter().filter(|t| t.regime_at_entry == regime).count();
+            if n == 0 {
+                continue;
+            }
+            let wins = trades
+                .iter()
+                .filter(|t| t.regime_at_entry == regime && t.pnl_frac > 0.0)
+                .count();
+            win_rate_by_regime.push((regime, n, wins as f64 / n as f64));
+        }
+        Self {
+            high_vol_periods,
+            low_vol_periods,
+            win_rate_by_regime,
+        }
+    }
+}
+
+/// One bucket of the holding-period distribution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingBucket {
+    /// Human-readable range, e.g. "5-15m".
+    pub label: String,
+    /// Upper bound in minutes (exclusive); `u64::MAX` for the tail bucket.
+    pub upper_min: u64,
+    pub count: usize,
+}
+
+/// Bucket edges (minutes) for the holding-period histogram.
+const HOLDING_BUCKET_EDGES_MIN: &[u64] = &[1, 5, 15, 30, 60, 240];
+
+/// Histogram of trade holding periods over fixed minute buckets.
+pub fn holding_period_histogram(trades: &[Trade]) -> Vec<HoldingBucket> {
+    let mut buckets: Vec<HoldingBucket> = Vec::new();
+    let mut lower = 0u64;
+    for &upper in HOLDING_BUCKET_EDGES_MIN {
+        buckets.push(HoldingBucket {
+            label: format!("{lower}-{upper}m"),
+            upper_min: upper,
+            count: 0,
+        });
+        lower = upper;
+    }
+    buckets.push(HoldingBucket {
+        label: format!(">{lower}m"),
+        upper_min: u64::MAX,
+        count: 0,
+    });
+    for trade in trades {
+        let held_min = ((trade.exit_time - trade.entry_time).max(0) as u64) / 60_000;
+        let bucket = buckets
+            .iter_mut()
+            .find(|b| held_min < b.upper_min)
+            .expect("tail bucket is unbounded");
+        bucket.count += 1;
+    }
+    buckets
+}
+
+/// Portfolio turnover: total traded notional (both legs of every trade)
+/// divided by the average equity over the run.
+pub fn compute_turnover(trades: &[Trade], equity_curve: &[(i64, f64)]) -> f64 {
+    if equity_curve.is_empty() {
+        return 0.0;
+    }
+    let notional: f64 = trades
+        .iter()
+        .map(|t| t.quantity * (t.entry_price + t.exit_price))
+        .sum();
+    let avg_equity =
+        equity_curve.iter().map(|(_, e)| e).sum::<f64>() / equity_curve.len() as f64;
+    if avg_equity <= 0.0 {
+        return 0.0;
+    }
+    notional / avg_equity
+}
+
+/// Milliseconds in the 365-day year the other annualized metrics assume.
+const MS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60_000.0;
+
+/// [`compute_turnover`] scaled to a per-year rate using the equity curve's
+/// time span. Zero when the curve spans no time.
+pub fn compute_turnover_annualized(trades: &[Trade], equity_curve: &[(i64, f64)]) -> f64 {
+    let (Some(&(t0, _)), Some(&(t1, _))) = (equity_curve.first(), equity_curve.last()) else {
+        return 0.0;
+    };
+    let years = (t1 - t0) as f64 / MS_PER_YEAR;
+    if years <= 0.0 {
+        return 0.0;
+    }
+    compute_turnover(trades, equity_curve) / years
+}
+
+/// Milliseconds in a UTC day, for the daily-returns resample.
+const MS_PER_DAY: i64 = 24 * 60 * 60_000;
+
+/// One bucket of a resampled equity curve: the OHLC of equity over the
+/// period starting at `start_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EquityBucket {
+    pub start_ms: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// Resample an equity curve into fixed `freq_ms` buckets aligned to the
+/// epoch. Periods with no points produce no bucket; the closes give
+/// last-value-per-period semantics, matching how
+/// [`export_quantstats_csv`] resamples to days.
+pub fn resample_equity(points: &[(i64, f64)], freq_ms: i64) -> Vec<EquityBucket> {
+    let mut buckets: Vec<EquityBucket> = Vec::new();
+    for &(ts, equity) in points {
+        let start_ms = ts.div_euclid(freq_ms) * freq_ms;
+        match buckets.last_mut() {
+            Some(bucket) if bucket.start_ms == start_ms => {
+                bucket.high = bucket.high.max(equity);
+                bucket.low = bucket.low.min(equity);
+                bucket.close = equity;
+            }
+            _ => buckets.push(EquityBucket {
+                start_ms,
+                open: equity,
+                high: equity,
+                low: equity,
+                close: equity,
+            }),
+        }
+    }
+    buckets
+}
+
+/// Export daily returns in the two-column CSV layout `quantstats` and
+/// `pyfolio` read (`date,returns`, ISO dates), so a run can be analyzed
+/// with the Python tooling. The minute equity curve is resampled to one
+/// observation per UTC day (the day's last equity); the first day seeds
+/// the baseline and emits no row, and days without bars simply do not
+/// appear.
+pub fn export_quantstats_csv(equity_curve: &[(i64, f64)], path: &str) -> Result<()> {
+    let mut daily: Vec<(i64, f64)> = Vec::new();
+    for &(ts, equity) in equity_curve {
+        let day = ts.div_euclid(MS_PER_DAY);
+        match daily.last_mut() {
+            Some((d, e)) if *d == day => *e = equity,
+            _ => daily.push((day, equity)),
+        }
+    }
+    let mut buf = String::from("date,returns\n");
+    for pair in daily.windows(2) {
+        let (_, prev) = pair[0];
+        let (day, equity) = pair[1];
+        let date = chrono::DateTime::from_timestamp_millis(day * MS_PER_DAY)
+            .context("equity timestamp out of range")?
+            .date_naive();
+        buf.push_str(&format!("{date},{:.8}\n", safe_div(equity - prev, prev)));
+    }
+    std::fs::write(path, buf).with_context(|| format!("writing {path}"))?;
+    Ok(())
+}
+
+/// Assumed square-root market impact: a taker order consuming an entire
+/// bar's quote volume moves price by this fraction.
+const IMPACT_AT_FULL_BAR: f64 = 0.005;
+
+/// Crude per-trade capacity: the largest notional (in quote currency) for
+/// which square-root impact `IMPACT_AT_FULL_BAR * sqrt(notional / volume)`
+/// stays under the strategy's average net edge per trade. `None` when the
+/// edge is non-positive (there is no capacity to speak of) or no volume
+/// data is available.
+pub fn estimate_capacity_usd(avg_edge_frac: f64, avg_bar_quote_volume: f64) -> Option<f64> {
+    if avg_edge_frac <= 0.0 || avg_bar_quote_volume <= 0.0 {
+        return None;
+    }
+    Some(avg_bar_quote_volume * (avg_edge_frac / IMPACT_AT_FULL_BAR).powi(2))
+}
+
+/// The complete artifact a backtest run serializes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub symbol: String,
+    /// Quote/settlement currency the run's capital figures are in.
+    pub quote_currency: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub perf: PerfReport,
+    /// (open_time ms, equity) samples.
+    pub equity_curve: Vec<(i64, f64)>,
+    pub trades: Vec<Trade>,
+    /// Total traded notional / average equity over the run.
+    pub turnover: f64,
+    /// The same turnover scaled to a per-year rate.
+    pub turnover_annualized: f64,
+    /// Crude capacity: per-trade notional at which market impact would eat
+    /// the average edge. `None` without a positive edge and volume data.
+    pub capacity_usd: Option<f64>,
+    /// Distribution of holding periods across fixed buckets.
+    pub holding_histogram: Vec<HoldingBucket>,
+    pub mft_analytics: ModelPerformance,
+    pub risk_metrics: RiskMetrics,
+    pub regime_analysis: RegimeAnalysis,
+    /// Block-bootstrap robustness bands; `None` when there were no trades
+    /// to resample.
+    pub bootstrap: Option<crate::montecarlo::BootstrapBands>,
+}
+
+/// Output options for report rendering.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportConfig {
+    pub output_dir: String,
+    pub include_charts: bool,
+    /// Sampling periods per year used to annualize equity-curve volatility.
+    /// Defaults to daily sampling of a market that never closes.
+    pub periods_per_year: f64,
+    /// Resample the equity curve to this period (ms) before computing
+    /// risk metrics, so `periods_per_year` and the sampling frequency
+    /// agree — a minute-level curve must not be annualized as if each
+    /// point were a day. The default of one day matches the default
+    /// `periods_per_year`; 0 uses the curve as-is.
+    pub risk_resample_ms: i64,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: "reports".to_string(),
+            include_charts: true,
+            periods_per_year: 365.0,
+            risk_resample_ms: MS_PER_DAY,
+        }
+    }
+}
+
+/// Renders a [`BacktestReport`] to disk.
+pub struct ReportGenerator {
+    pub config: ReportConfig,
+}
+
+impl ReportGenerator {
+    pub fn new(config: ReportConfig) -> Self {
+        Self { config }
+    }
+
+    /// Diagnostics for the MFT model stack, computed from the closed trades
+    /// and the strategy's flow engine.
+    pub fn generate_mft_analytics(
+        &self,
+        trades: &[&TradeRecord],
+        vpin_threshold_hits: Option<usize>,
+        vol_calibration: &[(f64, f64)],
+    ) -> ModelPerformance {
+        use mft_engine::engine::ExitReason;
+        let ou_mean_reversion_success = if trades.is_empty() {
+            None
+        } else {
+            let reverted = trades
+                .iter()
+                .filter(|t| t.exit_reason == ExitReason::TakeProfit)
+                .count();
+            Some(reverted as f64 / trades.len() as f64)
+        };
+        ModelPerformance {
+            garch_calibration_r2: garch_calibration_r2(vol_calibration),
+            vol_reliability: vol_reliability_table(vol_calibration),
+            ou_mean_reversion_success,
+            vpin_threshold_hits,
+        }
+    }
+
+    /// Portfolio risk metrics from the equity curve, resampled to
+    /// `risk_resample_ms` first so `periods_per_year` annualizes at the
+    /// matching frequency. `benchmark_returns` is the per-period series
+    /// beta/alpha/information ratio are measured against (e.g.
+    /// [`buy_and_hold_returns`] of the traded symbol), sampled at the
+    /// same frequency as the resampled curve; empty leaves them
+    /// unreported.
+    pub fn calculate_risk_metrics(
+        &self,
+        equity_curve: &[(i64, f64)],
+        benchmark_returns: &[f64],
+    ) -> RiskMetrics {
+        let periods_per_year = self.config.periods_per_year;
+        let equity: Vec<f64> = if self.config.risk_resample_ms > 0 {
+            resample_equity(equity_curve, self.config.risk_resample_ms)
+                .iter()
+                .map(|b| b.close)
+                .collect()
+        } else {
+            equity_curve.iter().map(|(_, e)| *e).collect()
+        };
+        let returns = mft_engine::metrics::returns(&equity);
+        let annualized_volatility = if returns.len() >= MIN_RISK_SAMPLES {
+            let n = returns.len() as f64;
+            let mean = returns.iter().sum::<f64>() / n;
+            let var = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            Some(var.sqrt() * periods_per_year.sqrt())
+        } else {
+            None
+        };
+        let (beta, alpha, information_ratio) =
+            relative_metrics(&returns, benchmark_returns, periods_per_year);
+        RiskMetrics {
+            var_95: empirical_var(&returns, 0.95),
+            cvar_95: empirical_cvar(&returns, 0.95),
+            annualized_volatility,
+            beta,
+            alpha,
+            information_ratio,
+        }
+    }
+
+    /// Write the JSON report; returns the path.
+    pub fn save_json(&self, report: &BacktestReport) -> Result<String> {
+        std::fs::create_dir_all(&self.config.output_dir)?;
+        let path = format!(
+            "{}/{}_report.json",
+            self.config.output_dir, report.symbol
+        );
+        std::fs::write(&path, serde_json::to_string_pretty(report)?)?;
+        Ok(path)
+    }
+
+    /// Write the HTML report; returns the path.
+    pub fn save_html(&self, report: &BacktestReport) -> Result<String> {
+        std::fs::create_dir_all(&self.config.output_dir)?;
+        let path = format!(
+            "{}/{}_report.html",
+            self.config.output_dir, report.symbol
+        );
+        std::fs::write(&path, self.generate_html_content(report))?;
+        Ok(path)
+    }
+
+    /// Build the HTML body. Charts are included when `include_charts` is set.
+    pub fn generate_html_content(&self, report: &BacktestReport) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html><html><head><meta charset=\"utf-8\">");
+        html.push_str(&format!("<title>{} backtest</title>", report.symbol));
+        html.push_str("<style>body{font-family:monospace;margin:2em}table{border-collapse:collapse}td,th{border:1px solid #999;padding:4px 8px}</style>");
+        html.push_str("</head><body>");
+        html.push_str(&format!(
+            "<h1>{} — {} → {}</h1>",
+            report.symbol, report.start_time, report.end_time
+        ));
+        html.push_str("<h2>Performance</h2><table>");
+        html.push_str(&format!(
+            "<tr><th>Trades</th><td>{}</td></tr>",
+            report.perf.n_trades
+        ));
+        html.push_str(&format!(
+            "<tr><th>Win rate</th><td>{:.1}%</td></tr>",
+            report.perf.win_rate * 100.0
+        ));
+        html.push_str(&format!(
+            "<tr><th>Total return</th><td>{:.2}%</td></tr>",
+            report.perf.total_return * 100.0
+        ));
+        html.push_str(&format!(
+            "<tr><th>Sharpe</th><td>{:.4}</td></tr>",
+            report.perf.sharpe
+        ));
+        html.push_str(&format!(
+            "<tr><th>Max drawdown</th><td>{:.2}%</td></tr>",
+            report.perf.max_drawdown * 100.0
+        ));
+        html.push_str(&format!(
+            "<tr><th>Turnover</th><td>{:.2}x</td></tr>",
+            report.turnover
+        ));
+        html.push_str(&format!(
+            "<tr><th>Turnover (annualized)</th><td>{:.2}x</td></tr>",
+            report.turnover_annualized
+        ));
+        if let Some(cap) = report.capacity_usd {
+            html.push_str(&format!(
+                "<tr><th>Capacity</th><td>{cap:.0} {}</td></tr>",
+                report.quote_currency
+            ));
+        }
+        html.push_str("</table>");
+        if self.config.include_charts && report.equity_curve.len() >= 2 {
+            let equity: Vec<f64> = report.equity_curve.iter().map(|(_, e)| *e).collect();
+            let mut peak = f64::MIN;
+            let drawdown: Vec<f64> = equity
+                .iter()
+                .map(|e| {
+                    if *e > peak {
+                        peak = *e;
+                    }
+                    safe_div(peak - e, peak)
+                })
+                .collect();
+            html.push_str("<h2>Equity</h2>");
+            html.push_str(&svg_sparkline(&equity, 600, 120, "#2a7"));
+            html.push_str("<h2>Drawdown</h2>");
+            html.push_str(&svg_sparkline(&drawdown, 600, 60, "#c33"));
+        }
+        if !report.holding_histogram.is_empty() {
+            html.push_str("<h2>Holding periods</h2><table>");
+            html.push_str("<tr><th>Range</th><th>Trades</th><th></th></tr>");
+            let max_count = report
+                .holding_histogram
+                .iter()
+                .map(|b| b.count)
+                .max()
+                .unwrap_or(0)
+                .max(1);
+            for bucket in &report.holding_histogram {
+                let bar_width = bucket.count * 40 / max_count;
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    bucket.label,
+                    bucket.count,
+                    "█".repeat(bar_width)
+                ));
+            }
+            html.push_str("</table>");
+        }
+        html.push_str("</body></html>");
+        html
+    }
+}
+
+/// Dependency-free inline SVG sparkline: a single `<path>` through
+/// `values`, scaled to the data range. Fewer than two points render
+/// nothing; a flat series draws along the bottom edge.
+fn svg_sparkline(values: &[f64], width: u32, height: u32, stroke: &str) -> String {
+    if values.len() < 2 {
+        return String::new();
+    }
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let span = if max > min { max - min } else { 1.0 };
+    let (w, h) = (width as f64, height as f64);
+    let mut d = String::new();
+    for (i, v) in values.iter().enumerate() {
+        let x = w * i as f64 / (values.len() - 1) as f64;
+        let y = h - h * (v - min) / span;
+        let cmd = if i == 0 { 'M' } else { 'L' };
+        d.push_str(&format!("{cmd}{x:.1},{y:.1} "));
+    }
+    format!(
+        "<svg width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" \
+         xmlns=\"http://www.w3.org/2000/svg\"><path d=\"{}\" fill=\"none\" \
+         stroke=\"{stroke}\" stroke-width=\"1.5\"/></svg>",
+        d.trim_end()
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::*;
+    use mft_engine::metrics::compute_metrics;
+
+    /// A minimal but well-formed report for rendering tests.
+    pub fn minimal_report() -> BacktestReport {
+        let equity: Vec<(i64, f64)> = (0..10).map(|i| (i * 60_000, 1000.0 + i as f64)).collect();
+        let levels: Vec<f64> = equity.iter().map(|(_, e)| *e).collect();
+        BacktestReport {
+            symbol: "BTCUSDT".to_string(),
+            quote_currency: "USDT".to_string(),
+            start_time: "2024-01-01T00:00:00Z".to_string(),
+            end_time: "2024-01-02T00:00:00Z".to_string(),
+            perf: compute_metrics(&levels, &[0.01, -0.005], 525_600.0),
+            equity_curve: equity,
+            trades: Vec::new(),
+            turnover: 0.0,
+            turnover_annualized: 0.0,
+            capacity_usd: None,
+            holding_histogram: Vec::new(),
+            mft_analytics: ModelPerformance {
+                garch_calibration_r2: None,
+                vol_reliability: Vec::new(),
+                ou_mean_reversion_success: None,
+                vpin_threshold_hits: None,
+            },
+            risk_metrics: RiskMetrics {
+                var_95: None,
+                cvar_95: None,
+                annualized_volatility: None,
+                beta: None,
+                alpha: None,
+                information_ratio: None,
+            },
+            regime_analysis: RegimeAnalysis {
+                high_vol_periods: 0,
+                low_vol_periods: 0,
+                win_rate_by_regime: Vec::new(),
+            },
+            bootstrap: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mft_engine::engine::{Direction, ExitReason, SignalReason};
+
+    fn trade(entry_ms: i64, held_min: i64, qty: f64, px: f64) -> Trade {
+        Trade {
+            entry_time: entry_ms,
+            exit_time: entry_ms + held_min * 60_000,
+            direction: Direction::Long,
+            entry_price: px,
+            exit_price: px,
+            quantity: qty,
+            entry_fill_kind: crate::simple_engine::FillKind::Taker,
+            exit_fill_kind: crate::simple_engine::FillKind::Taker,
+            exit_reason: ExitReason::TakeProfit,
+            entry_reason: SignalReason::OuReversion,
+            pnl: 0.0,
+            commission: 0.0,
+            return_pct: 0.0,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn holding_histogram_buckets_known_durations() {
+        let trades = vec![
+            trade(0, 0, 1.0, 100.0),   // < 1m
+            trade(0, 3, 1.0, 100.0),   // 1-5m
+            trade(0, 3, 1.0, 100.0),   // 1-5m
+            trade(0, 45, 1.0, 100.0),  // 30-60m
+            trade(0, 500, 1.0, 100.0), // tail
+        ];
+        let hist = holding_period_histogram(&trades);
+        let count_for = |label: &str| {
+            hist.iter().find(|b| b.label == label).map(|b| b.count).unwrap()
+        };
+        assert_eq!(count_for("0-1m"), 1);
+        assert_eq!(count_for("1-5m"), 2);
+        assert_eq!(count_for("30-60m"), 1);
+        assert_eq!(count_for(">240m"), 1);
+        assert_eq!(hist.iter().map(|b| b.count).sum::<usize>(), trades.len());
+    }
+
+    #[test]
+    fn turnover_is_notional_over_average_equity() {
+        // Two trades, each 1 unit at 100 in and out → notional 400.
+        let trades = vec![trade(0, 5, 1.0, 100.0), trade(0, 5, 1.0, 100.0)];
+        let equity = vec![(0, 900.0), (1, 1000.0), (2, 1100.0)]; // avg 1000
+        let turnover = compute_turnover(&trades, &equity);
+        assert!((turnover - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn annualized_turnover_scales_by_the_curves_time_span() {
+        // Same notional-400 trades over a curve spanning half a year at a
+        // flat 1000 equity: per-run turnover 0.4 → 0.8/year.
+        let trades = vec![trade(0, 5, 1.0, 100.0), trade(0, 5, 1.0, 100.0)];
+        let half_year_ms = (365 * 24 * 60 * 60_000i64) / 2;
+        let equity = vec![(0, 1000.0), (half_year_ms, 1000.0)];
+        let annualized = compute_turnover_annualized(&trades, &equity);
+        assert!((annualized - 0.8).abs() < 1e-12, "annualized = {annualized}");
+
+        // A zero-span or empty curve cannot be annualized.
+        assert_eq!(compute_turnover_annualized(&trades, &[(0, 1000.0)]), 0.0);
+        assert_eq!(compute_turnover_annualized(&trades, &[]), 0.0);
+    }
+
+    #[test]
+    fn capacity_grows_with_the_square_of_the_edge() {
+        // An edge equal to the impact coefficient caps a trade at exactly
+        // one bar's volume; doubling the edge quadruples the capacity.
+        let cap = estimate_capacity_usd(0.005, 1_000_000.0).unwrap();
+        assert!((cap - 1_000_000.0).abs() < 1e-6, "cap = {cap}");
+        let cap2 = estimate_capacity_usd(0.010, 1_000_000.0).unwrap();
+        assert!((cap2 - 4_000_000.0).abs() < 1e-6, "cap2 = {cap2}");
+
+        // No positive edge or no volume data → no estimate.
+        assert!(estimate_capacity_usd(0.0, 1_000_000.0).is_none());
+        assert!(estimate_capacity_usd(-0.001, 1_000_000.0).is_none());
+        assert!(estimate_capacity_usd(0.005, 0.0).is_none());
+    }
+
+    #[test]
+    fn quantstats_csv_has_one_return_row_per_later_day() {
+        // Four UTC days of hourly samples, equity stepping 1% per day; the
+        // first day is the baseline, so three return rows follow the header.
+        let mut curve = Vec::new();
+        for day in 0..4i64 {
+            for hour in 0..24i64 {
+                let ts = day * MS_PER_DAY + hour * 3_600_000;
+                curve.push((ts, 1000.0 * 1.01f64.powi(day as i32)));
+            }
+        }
+        let path = std::env::temp_dir().join("mft_quantstats_test.csv");
+        let path = path.to_string_lossy().into_owned();
+        export_quantstats_csv(&curve, &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(lines.next(), Some("date,returns"));
+        let rows: Vec<&str> = lines.collect();
+        assert_eq!(rows.len(), 3);
+        // ISO date index and the 1% daily step survive the resample.
+        assert!(rows[0].starts_with("1970-01-02,"));
+        for row in rows {
+            let ret: f64 = row.split(',').nth(1).unwrap().parse().unwrap();
+            assert!((ret - 0.01).abs() < 1e-6, "row = {row}");
+        }
+    }
+
+    #[test]
+    fn html_renders_holding_histogram() {
+        let gen = ReportGenerator::new(ReportConfig::default());
+        let mut report = test_util::minimal_report();
+        report.trades = vec![trade(0, 3, 1.0, 100.0)];
+        report.holding_histogram = holding_period_histogram(&report.trades);
+        report.turnover = compute_turnover(&report.trades, &report.equity_curve);
+        let html = gen.generate_html_content(&report);
+        assert!(html.contains("Holding periods"));
+        assert!(html.contains("1-5m"));
+    }
+
+    #[test]
+    fn charts_render_one_svg_path_command_per_sample() {
+        let path = svg_sparkline(&[1.0, 2.0, 3.0, 2.0], 100, 40, "#000");
+        assert!(path.starts_with("<svg"));
+        assert!(path.contains("<path"));
+        // One M plus one L per remaining point.
+        assert_eq!(path.matches('L').count(), 3);
+        assert!(svg_sparkline(&[1.0], 100, 40, "#000").is_empty());
+
+        let report = test_util::minimal_report();
+        let on = ReportGenerator::new(ReportConfig::default());
+        assert!(on.generate_html_content(&report).contains("<svg"));
+        let off = ReportGenerator::new(ReportConfig {
+            include_charts: false,
+            ..ReportConfig::default()
+        });
+        assert!(!off.generate_html_content(&report).contains("<svg"));
+    }
+
+    #[test]
+    fn var_is_the_empirical_tail_quantile() {
+        // 100 returns: -0.01 .. -0.001 (ten losses), then 90 small gains.
+        let mut rets: Vec<f64> = (1..=10).map(|i| -0.001 * i as f64).collect();
+        rets.resize(100, 0.001);
+        let var = empirical_var(&rets, 0.95).unwrap();
+        // 5% quantile sits at position 0.05 * 99 = 4.95, interpolated
+        // between the 5th and 6th worst losses (-0.006 and -0.005).
+        let expected = -0.006 * 0.05 + -0.005 * 0.95;
+        assert!((var - expected).abs() < 1e-12, "var = {var}");
+
+        let cvar = empirical_cvar(&rets, 0.95).unwrap();
+        assert!(cvar <= var, "cvar {cvar} must be at least as bad as var {var}");
+    }
+
+    #[test]
+    fn too_few_samples_give_no_var() {
+        let rets = vec![0.01; MIN_RISK_SAMPLES - 1];
+        assert!(empirical_var(&rets, 0.95).is_none());
+    }
+
+    #[test]
+    fn minute_equity_resamples_to_hourly_buckets() {
+        // Three hours of minute-spaced, steadily rising equity.
+        let points: Vec<(i64, f64)> =
+            (0..180).map(|i| (i * 60_000, 1000.0 + i as f64)).collect();
+        let hourly = resample_equity(&points, 60 * 60_000);
+        assert_eq!(hourly.len(), 3);
+        let last = &hourly[2];
+        assert_eq!(last.start_ms, 2 * 60 * 60_000);
+        // Last-value semantics: the close is the period's final point,
+        // and a monotone hour pins the extremes to its endpoints.
+        assert_eq!(last.open, 1120.0);
+        assert_eq!(last.close, 1179.0);
+        assert_eq!(last.low, last.open);
+        assert_eq!(last.high, last.close);
+    }
+
+    #[test]
+    fn buy_and_hold_strategy_has_unit_beta_and_zero_alpha() {
+        use crate::simple_engine::test_util::bars_from_closes;
+        // Alternating up/down moves so the benchmark has variance.
+        let mut closes = vec![100.0];
+        for i in 0..40 {
+            let last = *closes.last().unwrap();
+            closes.push(last * if i % 2 == 0 { 1.01 } else { 0.995 });
+        }
+        let bars = bars_from_closes(&closes);
+        // The strategy just holds the symbol: equity is a scaled copy of
+        // the price path.
+        let equity_curve: Vec<(i64, f64)> =
+            bars.iter().map(|k| (k.open_time, k.close * 10.0)).collect();
+        // Both series are at bar frequency, so skip the daily resample.
+        let gen = ReportGenerator::new(ReportConfig {
+            risk_resample_ms: 0,
+            ..ReportConfig::default()
+        });
+        let risk = gen.calculate_risk_metrics(&equity_curve, &buy_and_hold_returns(&bars));
+        let beta = risk.beta.expect("beta");
+        let alpha = risk.alpha.expect("alpha");
+        assert!((beta - 1.0).abs() < 1e-9, "beta = {beta}");
+        assert!(alpha.abs() < 1e-9, "alpha = {alpha}");
+        // Identical series leave no active return to reward.
+        assert_eq!(risk.information_ratio, Some(0.0));
+
+        // No benchmark leaves the relative metrics unreported.
+        assert!(gen.calculate_risk_metrics(&equity_curve, &[]).beta.is_none());
+    }
+
+    #[test]
+    fn ou_success_counts_take_profit_exits() {
+        use mft_engine::engine::{Direction, ExitReason};
+        let record = |reason| TradeRecord {
+            symbol: "BTCUSDT".to_string(),
+            entry_ts: 0,
+            exit_ts: 1,
+            direction: Direction::Long,
+            entry_px: 100.0,
+            exit_px: 101.0,
+            pnl_frac: 0.01,
+            exit_reason: reason,
+            entry_reason: SignalReason::OuReversion,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            regime_at_entry: VolRegime::Normal,
+        };
+        let trades = [
+            record(ExitReason::TakeProfit),
+            record(ExitReason::TakeProfit),
+            record(ExitReason::StopLoss),
+            record(ExitReason::TimeStop),
+        ];
+        let refs: Vec<&TradeRecord> = trades.iter().collect();
+        let gen = ReportGenerator::new(ReportConfig::default());
+        let perf = gen.generate_mft_analytics(&refs, Some(3), &[]);
+        assert_eq!(perf.ou_mean_reversion_success, Some(0.5));
+        assert_eq!(perf.vpin_threshold_hits, Some(3));
+        assert!(perf.garch_calibration_r2.is_none());
+        assert!(gen
+            .generate_mft_analytics(&[], None, &[])
+            .ou_mean_reversion_success
+            .is_none());
+    }
+
+    #[test]
+    fn perfectly_correlated_forecasts_score_r2_of_one() {
+        // Realized vol exactly proportional to the forecast.
+        let pairs: Vec<(f64, f64)> = (1..=50)
+            .map(|i| (i as f64 * 1e-4, i as f64 * 2e-4))
+            .collect();
+        let r2 = garch_calibration_r2(&pairs).unwrap();
+        assert!((r2 - 1.0).abs() < 1e-12, "r2 = {r2}");
+
+        let table = vol_reliability_table(&pairs);
+        assert_eq!(table.len(), 10);
+        assert_eq!(table.iter().map(|r| r.n).sum::<usize>(), pairs.len());
+        // Mean realized vol rises monotonically across forecast deciles.
+        for w in table.windows(2) {
+            assert!(w[1].mean_realized > w[0].mean_realized);
+        }
+    }
+
+    #[test]
+    fn too_few_calibration_pairs_give_no_r2() {
+        let pairs = vec![(1e-4, 2e-4); MIN_RISK_SAMPLES - 1];
+        assert!(garch_calibration_r2(&pairs).is_none());
+    }
+
+    #[test]
+    fn regime_breakdown_groups_by_entry_regime() {
+        use mft_engine::engine::{Direction, ExitReason};
+        let record = |regime, pnl_frac| TradeRecord {
+            symbol: "BTCUSDT".to_string(),
+            entry_ts: 0,
+            exit_ts: 1,
+            direction: Direction::Long,
+            entry_px: 100.0,
+            exit_px: 101.0,
+            pnl_frac,
+            exit_reason: ExitReason::TakeProfit,
+            entry_reason: SignalReason::OuReversion,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            regime_at_entry: regime,
+        };
+        let trades = [
+            record(VolRegime::Low, 0.01),
+            record(VolRegime::Low, -0.01),
+            record(VolRegime::High, 0.02),
+        ];
+        let refs: Vec<&TradeRecord> = trades.iter().collect();
+        let analysis = RegimeAnalysis::from_trades(&refs, 40, 10);
+        assert_eq!(analysis.low_vol_periods, 40);
+        assert_eq!(analysis.high_vol_periods, 10);
+        // Normal has no trades and is omitted.
+        assert_eq!(
+            analysis.win_rate_by_regime,
+            vec![(VolRegime::Low, 2, 0.5), (VolRegime::High, 1, 1.0)]
+        );
+    }
+
+    #[test]
+    fn report_round_trips_through_json() {
+        let report = test_util::minimal_report();
+        let json = serde_json::to_string(&report).unwrap();
+        let back: BacktestReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.symbol, report.symbol);
+        assert_eq!(back.perf.n_trades, report.perf.n_trades);
+        assert_eq!(back.perf.total_return, report.perf.total_return);
+        assert_eq!(back.perf.sharpe, report.perf.sharpe);
+        assert_eq!(back.equity_curve, report.equity_curve);
+        // A JSON document with the wrong shape is rejected up front rather
+        // than deserializing into a half-filled report.
+        assert!(serde_json::from_str::<BacktestReport>("{\"symbol\": 1}").is_err());
+    }
+
+    #[test]
+    fn html_contains_headline_metrics() {
+        let gen = ReportGenerator::new(ReportConfig::default());
+        let html = gen.generate_html_content(&test_util::minimal_report());
+        assert!(html.contains("BTCUSDT"));
+        assert!(html.contains("Sharpe"));
+    }
+}
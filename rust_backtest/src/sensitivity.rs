@@ -0,0 +1,148 @@
+//! "What-if" sensitivity sweeps over [`SimpleBacktestEngine`].
+//!
+//! A strategy that only survives at today's fee tier is not deployable;
+//! [`fee_sweep`] reruns the same kline series under a range of taker fees
+//! and tabulates how net performance degrades as costs rise.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tracing::info;
+
+use mft_engine::config::AppConfig;
+use mft_engine::data::Kline;
+use mft_engine::metrics::compute_metrics;
+
+use crate::simple_engine::{SimpleBacktestConfig, SimpleBacktestEngine};
+
+const BARS_PER_YEAR_1M: f64 = 525_600.0;
+
+/// One fee level of the sweep.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FeeSweepRow {
+    /// Taker fee charged in this run, in basis points.
+    pub fee_bps: f64,
+    pub total_return: f64,
+    pub sharpe: f64,
+    pub n_trades: usize,
+}
+
+/// Rerun the backtest once per taker fee in `fee_bps_range` and return one
+/// row per level, in the order given.
+///
+/// Each run clears `fee_tiers` so the swept flat fee actually applies (a
+/// tiered schedule would supersede it); the maker fee is left at the base
+/// config's value, which only matters for limit-entry setups.
+pub fn fee_sweep(
+    klines: &[Kline],
+    base_cfg: &AppConfig,
+    bt_cfg: &SimpleBacktestConfig,
+    fee_bps_range: &[f64],
+) -> Result<Vec<FeeSweepRow>> {
+    if fee_bps_range.is_empty() {
+        bail!("fee_bps_range is empty");
+    }
+    let mut rows = Vec::with_capacity(fee_bps_range.len());
+    for &fee_bps in fee_bps_range {
+        let cfg = AppConfig {
+            taker_fee: fee_bps / 1e4,
+            fee_tiers: Vec::new(),
+            ..base_cfg.clone()
+        };
+        let mut engine = SimpleBacktestEngine::new(cfg, bt_cfg.clone());
+        let results = engine.run(klines);
+        let equity: Vec<f64> = results.equity_curve.iter().map(|(_, e)| *e).collect();
+        let pnls: Vec<f64> = results.trades.iter().map(|t| t.return_pct).collect();
+        let report = compute_metrics(&equity, &pnls, BARS_PER_YEAR_1M);
+        info!(fee_bps, total_return = report.total_return, "sweep level complete");
+        rows.push(FeeSweepRow {
+            fee_bps,
+            total_return: report.total_return,
+            sharpe: report.sharpe,
+            n_trades: results.trades.len(),
+        });
+    }
+    Ok(rows)
+}
+
+/// Write the sweep table as CSV, one row per fee level.
+pub fn save_fee_sweep_csv(rows: &[FeeSweepRow], path: &str) -> Result<()> {
+    let mut buf = String::from("fee_bps,total_return,sharpe,n_trades\n");
+    for row in rows {
+        buf.push_str(&format!(
+            "{},{:.6},{:.4},{}\n",
+            row.fee_bps, row.total_return, row.sharpe, row.n_trades
+        ));
+    }
+    std::fs::write(path, buf).with_context(|| format!("writing {path}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simple_engine::test_util::bars_from_closes;
+
+    /// An oscillation with periodic ~3.5σ dislocations: the sine keeps the
+    /// AR(1) fit happy, the dips push |z| past the entry threshold.
+    fn oscillating_closes(n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| {
+                if i > 60 && i % 20 == 0 {
+                    95.0
+                } else {
+                    100.0 + 2.0 * ((i as f64) * 0.4).sin()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn net_return_decreases_monotonically_in_fees() {
+        let klines = bars_from_closes(&oscillating_closes(300));
+        let base = AppConfig {
+            ou_window: 30,
+            // Five fixture bars per VPIN bucket, so the alternating
+            // taker sides average out instead of each 100-volume bar tick
+            // filling whole buckets one-sided.
+            vpin_bucket_volume: 500.0,
+            ..AppConfig::default()
+        };
+        // Levels low enough that the EV gate admits the same entries at
+        // each one; past that point the engine stops trading and the
+        // zero-trade return would beat the fee-laden runs.
+        let rows = fee_sweep(
+            &klines,
+            &base,
+            &SimpleBacktestConfig::default(),
+            &[0.0, 5.0, 10.0],
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].n_trades > 0, "sweep series produced no trades");
+        // Only the cost assumption differs between runs, so the drag on
+        // net return grows with the fee.
+        assert!(rows[0].total_return > rows[1].total_return);
+        assert!(rows[1].total_return > rows[2].total_return);
+
+        let dir = std::env::temp_dir().join("mft_sensitivity_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("fee_sweep.csv");
+        save_fee_sweep_csv(&rows, &path.to_string_lossy()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(lines.next(), Some("fee_bps,total_return,sharpe,n_trades"));
+        assert_eq!(lines.count(), rows.len());
+    }
+
+    #[test]
+    fn empty_range_is_rejected() {
+        let klines = bars_from_closes(&oscillating_closes(50));
+        let err = fee_sweep(
+            &klines,
+            &AppConfig::default(),
+            &SimpleBacktestConfig::default(),
+            &[],
+        );
+        assert!(err.is_err());
+    }
+}
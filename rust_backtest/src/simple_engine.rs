@@ -0,0 +1,2038 @@
+//! A fast, dependency-light bar-replay backtest around [`StrategyEngine`].
+//!
+//! Fills execute at a configurable bar price ([`FillMode`], default the
+//! open) with a configurable slippage adjustment ([`SlippageModel`]);
+//! up to `max_concurrent_positions` lots held at once with FIFO closing.
+//! Fill convention: entry signals are computed from bar closes, so they
+//! execute no earlier than the next bar's open (see
+//! [`SimpleBacktestConfig::fill_delay_bars`]); exits triggered by intrabar
+//! levels fill on their trigger bar, like a resting stop would.
+//! This engine trades realism for speed and is the workhorse for parameter
+//! iteration; use the Nautilus path for final validation.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use mft_engine::config::AppConfig;
+use mft_engine::data::{Kline, TradeTick};
+use mft_engine::engine::{
+    Direction, ExitReason, SignalReason, SkipReason, StrategyEngine, TradeSignal,
+};
+use mft_engine::fees::FeeSchedule;
+
+use crate::instruments::find_spec;
+
+/// Which bar price fills execute at (before slippage).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillMode {
+    /// The bar open — the historical default, kept for compatibility.
+    OpenPrice,
+    /// The bar close.
+    ClosePrice,
+    /// A bar-VWAP approximation: `(high + low + 2·close) / 4`.
+    Vwap,
+}
+
+impl FillMode {
+    /// The fill price this mode picks from `kline`.
+    pub fn price(&self, kline: &Kline) -> f64 {
+        match self {
+            FillMode::OpenPrice => kline.open,
+            FillMode::ClosePrice => kline.close,
+            FillMode::Vwap => (kline.high + kline.low + 2.0 * kline.close) / 4.0,
+        }
+    }
+}
+
+/// How fill prices are degraded to model execution costs.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlippageModel {
+    /// Flat basis points regardless of order size.
+    Fixed { bps: f64 },
+    /// Flat base plus market impact growing with order size relative to
+    /// the bar's volume: extra slippage (as a fraction) is
+    /// `impact_coeff * order_qty / bar_volume`.
+    VolumeProportional { base_bps: f64, impact_coeff: f64 },
+}
+
+impl SlippageModel {
+    /// Adverse price adjustment (fraction) for an order of `order_qty`
+    /// filling against a bar that traded `bar_volume`.
+    pub fn fraction(&self, order_qty: f64, bar_volume: f64) -> f64 {
+        match self {
+            SlippageModel::Fixed { bps } => bps / 1e4,
+            SlippageModel::VolumeProportional { base_bps, impact_coeff } => {
+                let impact = if bar_volume > 0.0 {
+                    impact_coeff * order_qty / bar_volume
+                } else {
+                    0.0
+                };
+                base_bps / 1e4 + impact
+            }
+        }
+    }
+}
+
+/// How entry orders are submitted.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryMode {
+    /// Fill immediately at the configured bar price (market order).
+    Market,
+    /// Rest a limit `offset_bps` inside the signal price (below for longs,
+    /// above for shorts). The order fills at the limit price if a later
+    /// bar trades through it within `max_wait_bars`; otherwise it is
+    /// cancelled and the signal is dropped.
+    LimitEntry { offset_bps: f64, max_wait_bars: usize },
+    /// TWAP execution: split the entry into `bars` equal slices filled on
+    /// consecutive bars at each bar's configured fill price. The lot opens
+    /// at the mean child price once the last slice fills.
+    Twap { bars: usize },
+    /// VWAP execution: like `Twap`, but each slice fills at the bar-VWAP
+    /// approximation regardless of `entry_fill`.
+    Vwap { bars: usize },
+}
+
+/// Whether a fill crosses the spread (taker) or rests on the book (maker).
+/// Determines whether the maker or taker rate of the active fee tier (the
+/// flat `AppConfig::{maker_fee, taker_fee}` when no tiers are set) is charged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillKind {
+    Maker,
+    Taker,
+}
+
+/// Engine-level (execution) configuration, separate from the model config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimpleBacktestConfig {
+    pub initial_capital: f64,
+    /// Slippage applied against the fill direction.
+    pub slippage: SlippageModel,
+    pub leverage: f64,
+    /// Fee class charged on entries. Market entries are takers.
+    pub entry_fill_kind: FillKind,
+    /// Fee class charged on exits; limit-style exits can rest as makers.
+    pub exit_fill_kind: FillKind,
+    /// Bar price fills execute at, for entries and exits alike.
+    pub entry_fill: FillMode,
+    /// Market (immediate) or resting-limit entries.
+    pub entry_mode: EntryMode,
+    /// Perpetual funding events as `(timestamp ms, rate)` pairs, sorted by
+    /// time. Positions held across an event pay (long, positive rate) or
+    /// receive the rate on current notional. Empty disables funding.
+    pub funding_schedule: Vec<(i64, f64)>,
+    /// How many lots may be open at once. Additional same-direction
+    /// signals stack new lots until this cap; the default of 1 keeps the
+    /// historical one-position behavior.
+    pub max_concurrent_positions: usize,
+    /// Margin-spot borrow cost charged on open shorts, in basis points per
+    /// day, prorated by bar length and marked on close-price notional.
+    /// Zero (the default) models a perpetual, where funding covers it.
+    pub short_borrow_bps_per_day: f64,
+    /// Per-lot exit rule checked every bar; the default keeps the
+    /// historical fixed 2% stop.
+    pub exit_policy: ExitPolicyKind,
+    /// Bars between a trading decision and its execution, modeling
+    /// signal-to-exchange latency: a decision on bar *i* executes on bar
+    /// *i + max(delay, 1)*, entries at that bar's `entry_fill` price and
+    /// exits at the usual exit price. Signals are computed from bar
+    /// closes, so nothing may fill before the next bar's open regardless
+    /// — 0 (the default) and 1 are equivalent. The queued order executes
+    /// however the market moved in between; that adverse drift is exactly
+    /// the cost being modeled.
+    pub fill_delay_bars: usize,
+    /// Diagnostic mode: assert that every entry fills on a bar that
+    /// opened after the signal's own bar, panicking on look-ahead
+    /// regressions in the execution plumbing. Off by default.
+    pub strict_causality: bool,
+}
+
+impl Default for SimpleBacktestConfig {
+    fn default() -> Self {
+        Self {
+            initial_capital: 5_000.0,
+            slippage: SlippageModel::Fixed { bps: 1.0 },
+            leverage: 3.0,
+            entry_fill_kind: FillKind::Taker,
+            exit_fill_kind: FillKind::Taker,
+            entry_fill: FillMode::OpenPrice,
+            entry_mode: EntryMode::Market,
+            funding_schedule: Vec::new(),
+            max_concurrent_positions: 1,
+            short_borrow_bps_per_day: 0.0,
+            exit_policy: ExitPolicyKind::FixedStop { stop_frac: 0.02 },
+            fill_delay_bars: 0,
+            strict_causality: false,
+        }
+    }
+}
+
+/// An open position inside the simple engine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub direction: Direction,
+    pub entry_time: i64,
+    pub entry_price: f64,
+    pub quantity: f64,
+    pub entry_commission: f64,
+    /// Fee class the entry leg was charged as.
+    pub entry_fill_kind: FillKind,
+    /// Which sub-model produced the entry signal.
+    pub entry_reason: SignalReason,
+    /// Bars this lot has been open, driving the per-lot time stop.
+    pub bars_held: usize,
+    /// An exit decision awaiting its delayed fill under
+    /// `fill_delay_bars`: the reason the lot will close for and how many
+    /// more bars to wait before the closing bar. The lot is not re-judged
+    /// while it waits.
+    pub pending_exit: Option<(ExitReason, usize)>,
+    /// Worst intrabar unrealized fraction seen while open.
+    pub mae_frac: f64,
+    /// Best intrabar unrealized fraction seen while open.
+    pub mfe_frac: f64,
+    /// Child fill prices that built this lot: one element for one-shot
+    /// entries, one per slice for TWAP/VWAP entries.
+    pub child_fills: Vec<f64>,
+}
+
+impl Position {
+    /// Fold a bar's range into the MAE/MFE running extremes.
+    fn update_excursions(&mut self, kline: &Kline) {
+        let frac = |px: f64| self.direction.sign() * (px - self.entry_price) / self.entry_price;
+        let (a, b) = (frac(kline.low), frac(kline.high));
+        self.mae_frac = self.mae_frac.min(a.min(b));
+        self.mfe_frac = self.mfe_frac.max(a.max(b));
+    }
+}
+
+/// A per-lot exit rule, checked against every open lot each bar before new
+/// signals are processed. `engine` exposes the model state so policies can
+/// be model-driven as well as price-driven.
+pub trait ExitPolicy: Send + Sync {
+    /// Whether `position` should be closed on `kline`, and why.
+    fn should_exit(
+        &self,
+        position: &Position,
+        kline: &Kline,
+        engine: &StrategyEngine,
+    ) -> Option<ExitReason>;
+}
+
+/// Protective stop at a fixed fraction of the lot's entry price, checked
+/// against the bar's extremes — the historical hardcoded 2% stop.
+pub struct FixedStopExit {
+    pub stop_frac: f64,
+}
+
+impl ExitPolicy for FixedStopExit {
+    fn should_exit(
+        &self,
+        position: &Position,
+        kline: &Kline,
+        _engine: &StrategyEngine,
+    ) -> Option<ExitReason> {
+        let hit = match position.direction {
+            Direction::Long => kline.low <= position.entry_price * (1.0 - self.stop_frac),
+            Direction::Short => kline.high >= position.entry_price * (1.0 + self.stop_frac),
+        };
+        hit.then_some(ExitReason::StopLoss)
+    }
+}
+
+/// The engine's OU reversion exit applied per lot: close once the z-score
+/// has come back through `ou_exit_z` on the lot's side.
+pub struct OuMeanReversionExit;
+
+impl ExitPolicy for OuMeanReversionExit {
+    fn should_exit(
+        &self,
+        position: &Position,
+        kline: &Kline,
+        engine: &StrategyEngine,
+    ) -> Option<ExitReason> {
+        let z = engine.z_score(kline.close)?;
+        let reverted = match position.direction {
+            Direction::Long => z >= -engine.cfg.ou_exit_z,
+            Direction::Short => z <= engine.cfg.ou_exit_z,
+        };
+        reverted.then_some(ExitReason::ZReversion)
+    }
+}
+
+/// Chains policies; the first one that wants out decides the exit reason.
+pub struct CompositeExit {
+    policies: Vec<Box<dyn ExitPolicy>>,
+}
+
+impl CompositeExit {
+    pub fn new(policies: Vec<Box<dyn ExitPolicy>>) -> Self {
+        Self { policies }
+    }
+}
+
+impl ExitPolicy for CompositeExit {
+    fn should_exit(
+        &self,
+        position: &Position,
+        kline: &Kline,
+        engine: &StrategyEngine,
+    ) -> Option<ExitReason> {
+        self.policies
+            .iter()
+            .find_map(|p| p.should_exit(position, kline, engine))
+    }
+}
+
+/// Exit-policy selection for config files, built into a concrete
+/// [`ExitPolicy`] when the engine is constructed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExitPolicyKind {
+    /// [`FixedStopExit`] with this stop fraction.
+    FixedStop { stop_frac: f64 },
+    /// [`OuMeanReversionExit`] alone — no protective stop.
+    OuMeanReversion,
+    /// [`CompositeExit`] of the fixed stop and the OU reversion exit.
+    StopAndReversion { stop_frac: f64 },
+}
+
+impl ExitPolicyKind {
+    /// Build the concrete policy this variant names.
+    pub fn build(&self) -> Box<dyn ExitPolicy> {
+        match *self {
+            ExitPolicyKind::FixedStop { stop_frac } => Box::new(FixedStopExit { stop_frac }),
+            ExitPolicyKind::OuMeanReversion => Box::new(OuMeanReversionExit),
+            ExitPolicyKind::StopAndReversion { stop_frac } => Box::new(CompositeExit::new(vec![
+                Box::new(FixedStopExit { stop_frac }),
+                Box::new(OuMeanReversionExit),
+            ])),
+        }
+    }
+}
+
+/// A resting limit entry awaiting a fill.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingEntry {
+    signal: TradeSignal,
+    limit_price: f64,
+    bars_waited: usize,
+}
+
+/// A signal held back by `fill_delay_bars`, awaiting its execution bar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DelayedEntry {
+    signal: TradeSignal,
+    /// Bars still to count down before the bar the order executes on.
+    bars_remaining: usize,
+    /// Execute as a market order regardless of `entry_mode` — the flip
+    /// leg of a reversal, where resting a limit would defeat the purpose.
+    force_market: bool,
+}
+
+/// An entry being worked across several bars (TWAP/VWAP execution).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WorkingEntry {
+    signal: TradeSignal,
+    /// Total slices the order is split into.
+    bars: usize,
+    /// Slice fill prices so far, slippage applied.
+    fills: Vec<f64>,
+    /// Fill slices at the bar-VWAP price instead of `entry_fill`.
+    use_vwap: bool,
+}
+
+/// A closed round trip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub entry_time: i64,
+    pub exit_time: i64,
+    pub direction: Direction,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub quantity: f64,
+    /// Fee class of the entry leg.
+    pub entry_fill_kind: FillKind,
+    /// Fee class of the exit leg.
+    pub exit_fill_kind: FillKind,
+    /// Why the lot was closed.
+    pub exit_reason: ExitReason,
+    /// Which sub-model produced the entry signal.
+    pub entry_reason: SignalReason,
+    /// Net PnL in quote currency, after commissions.
+    pub pnl: f64,
+    /// Total commission paid on both legs.
+    pub commission: f64,
+    /// Net return as a fraction of entry notional.
+    pub return_pct: f64,
+    /// Maximum adverse excursion (fraction of entry notional, intrabar).
+    pub mae_frac: f64,
+    /// Maximum favorable excursion (fraction of entry notional, intrabar).
+    pub mfe_frac: f64,
+    /// Child fill prices of the entry leg (see [`Position::child_fills`]).
+    pub child_fills: Vec<f64>,
+}
+
+/// Hooks driven by [`SimpleBacktestEngine::run_with_observer`] as the
+/// replay progresses, for GUIs, progress bars or custom logging. All
+/// methods default to no-ops so implementors override only what they need.
+pub trait BacktestObserver {
+    /// Called after each bar is fully processed, with the bar index and
+    /// the mark-to-market equity.
+    fn on_bar(&mut self, _i: usize, _equity: f64) {}
+    /// Called when a new position is opened (including reversals).
+    fn on_trade_open(&mut self, _position: &Position) {}
+    /// Called for each round trip as it closes.
+    fn on_trade_close(&mut self, _trade: &Trade) {}
+}
+
+/// The observer [`SimpleBacktestEngine::run`] uses: does nothing.
+pub struct NoopObserver;
+
+impl BacktestObserver for NoopObserver {}
+
+/// Everything a run produces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestResults {
+    pub initial_capital: f64,
+    pub final_capital: f64,
+    pub trades: Vec<Trade>,
+    /// (bar open_time, equity) per bar.
+    pub equity_curve: Vec<(i64, f64)>,
+    /// Limit entries placed over the run; zero under `EntryMode::Market`.
+    pub limit_entries_placed: usize,
+    /// Limit entries that filled before `max_wait_bars` expired.
+    pub limit_entries_filled: usize,
+    /// Closed trades per exit reason.
+    pub exit_reason_counts: HashMap<ExitReason, usize>,
+    /// Entries the models wanted but a gate or the execution layer
+    /// refused, by reason — how much more the strategy wanted to trade.
+    pub skipped_signal_counts: HashMap<SkipReason, usize>,
+}
+
+impl BacktestResults {
+    /// Fraction of limit entries that filled; `None` when none were placed.
+    pub fn limit_fill_rate(&self) -> Option<f64> {
+        if self.limit_entries_placed == 0 {
+            return None;
+        }
+        Some(self.limit_entries_filled as f64 / self.limit_entries_placed as f64)
+    }
+
+    /// Mean net PnL (quote currency) per exit reason.
+    pub fn avg_pnl_by_reason(&self) -> HashMap<ExitReason, f64> {
+        let mut sums: HashMap<ExitReason, f64> = HashMap::new();
+        for trade in &self.trades {
+            *sums.entry(trade.exit_reason).or_insert(0.0) += trade.pnl;
+        }
+        sums.into_iter()
+            .map(|(reason, sum)| (reason, sum / self.exit_reason_counts[&reason] as f64))
+            .collect()
+    }
+}
+
+/// A resumable snapshot of a mid-run backtest, produced by
+/// [`SimpleBacktestEngine::checkpoint`]. Opaque on purpose: write it out
+/// with serde and hand it back to [`SimpleBacktestEngine::restore`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineState {
+    engine: mft_engine::engine::EngineSnapshot,
+    capital: f64,
+    positions: Vec<Position>,
+    pending_entry: Option<PendingEntry>,
+    working_entry: Option<WorkingEntry>,
+    delayed_entry: Option<DelayedEntry>,
+    trades: Vec<Trade>,
+    equity_curve: Vec<(i64, f64)>,
+    funding_idx: usize,
+    fees: FeeSchedule,
+    tick_idx: usize,
+    limit_entries_placed: usize,
+    limit_entries_filled: usize,
+}
+
+impl EngineState {
+    /// Bars already replayed when the state was captured (one equity point
+    /// is pushed per bar), so a resume knows how much input to skip.
+    pub fn bars_processed(&self) -> usize {
+        self.equity_curve.len()
+    }
+}
+
+pub struct SimpleBacktestEngine {
+    pub config: SimpleBacktestConfig,
+    pub engine: StrategyEngine,
+    capital: f64,
+    /// Open lots in entry order; closes are FIFO unless a per-lot stop
+    /// fires first.
+    positions: Vec<Position>,
+    pending_entry: Option<PendingEntry>,
+    working_entry: Option<WorkingEntry>,
+    /// A signal waiting out `fill_delay_bars` before it may execute.
+    delayed_entry: Option<DelayedEntry>,
+    trades: Vec<Trade>,
+    equity_curve: Vec<(i64, f64)>,
+    /// Next unapplied entry in `config.funding_schedule`.
+    funding_idx: usize,
+    /// Commission schedule; a single flat tier unless `cfg.fee_tiers` is set.
+    fees: FeeSchedule,
+    /// Real tick stream (sorted by `ts`) driving the flow models; empty
+    /// unless attached with [`with_trade_ticks`](Self::with_trade_ticks).
+    trade_ticks: Vec<TradeTick>,
+    /// Next unfed entry in `trade_ticks`.
+    tick_idx: usize,
+    limit_entries_placed: usize,
+    limit_entries_filled: usize,
+    /// The concrete per-lot exit rule built from `config.exit_policy`.
+    exit_policy: Box<dyn ExitPolicy>,
+}
+
+impl SimpleBacktestEngine {
+    pub fn new(app_cfg: AppConfig, config: SimpleBacktestConfig) -> Self {
+        let capital = config.initial_capital;
+        let fees = if app_cfg.fee_tiers.is_empty() {
+            FeeSchedule::flat(app_cfg.maker_fee, app_cfg.taker_fee)
+        } else {
+            FeeSchedule::new(app_cfg.fee_tiers.clone())
+        };
+        let exit_policy = config.exit_policy.build();
+        Self {
+            config,
+            engine: StrategyEngine::new(app_cfg),
+            capital,
+            positions: Vec::new(),
+            pending_entry: None,
+            working_entry: None,
+            delayed_entry: None,
+            trades: Vec::new(),
+            equity_curve: Vec::new(),
+            funding_idx: 0,
+            fees,
+            trade_ticks: Vec::new(),
+            tick_idx: 0,
+            limit_entries_placed: 0,
+            limit_entries_filled: 0,
+            exit_policy,
+        }
+    }
+
+    /// Attach a real aggTrades stream (sorted by `ts`); each bar's ticks
+    /// are fed to the flow models before the bar itself. Pair this with
+    /// [`TickSource::Trades`](mft_engine::config::TickSource) so the
+    /// engine's bar-level approximation does not count the volume twice.
+    pub fn with_trade_ticks(mut self, ticks: Vec<TradeTick>) -> Self {
+        self.trade_ticks = ticks;
+        self
+    }
+
+    /// Feed attached ticks up to and including `close_time`.
+    fn feed_ticks(&mut self, close_time: i64) {
+        while self.tick_idx < self.trade_ticks.len()
+            && self.trade_ticks[self.tick_idx].ts <= close_time
+        {
+            self.engine.on_tick(&self.trade_ticks[self.tick_idx]);
+            self.tick_idx += 1;
+        }
+    }
+
+    /// Capture everything a resume needs: capital, open lots, closed
+    /// trades, the equity curve and the full model state.
+    pub fn checkpoint(&self) -> EngineState {
+        EngineState {
+            engine: self.engine.snapshot(),
+            capital: self.capital,
+            positions: self.positions.clone(),
+            pending_entry: self.pending_entry.clone(),
+            working_entry: self.working_entry.clone(),
+            delayed_entry: self.delayed_entry.clone(),
+            trades: self.trades.clone(),
+            equity_curve: self.equity_curve.clone(),
+            funding_idx: self.funding_idx,
+            fees: self.fees.clone(),
+            tick_idx: self.tick_idx,
+            limit_entries_placed: self.limit_entries_placed,
+            limit_entries_filled: self.limit_entries_filled,
+        }
+    }
+
+    /// Restore a [`checkpoint`](Self::checkpoint) into this engine, which
+    /// must have been built with the same configs. Feed it the bars the
+    /// checkpoint had not yet seen ([`EngineState::bars_processed`]) and
+    /// the run continues as if never interrupted.
+    pub fn restore(&mut self, state: EngineState) {
+        self.engine.restore(state.engine);
+        self.capital = state.capital;
+        self.positions = state.positions;
+        self.pending_entry = state.pending_entry;
+        self.working_entry = state.working_entry;
+        self.delayed_entry = state.delayed_entry;
+        self.trades = state.trades;
+        self.equity_curve = state.equity_curve;
+        self.funding_idx = state.funding_idx;
+        self.fees = state.fees;
+        self.tick_idx = state.tick_idx;
+        self.limit_entries_placed = state.limit_entries_placed;
+        self.limit_entries_filled = state.limit_entries_filled;
+    }
+
+    /// Replay `klines` in order and return the results.
+    pub fn run(&mut self, klines: &[Kline]) -> BacktestResults {
+        self.run_with_observer(klines, &mut NoopObserver)
+    }
+
+    /// Replay `klines`, invoking `obs` as bars complete and trades open
+    /// and close.
+    pub fn run_with_observer(
+        &mut self,
+        klines: &[Kline],
+        obs: &mut dyn BacktestObserver,
+    ) -> BacktestResults {
+        for (i, kline) in klines.iter().enumerate() {
+            let trades_before = self.trades.len();
+            let positions_before = self.positions.len();
+            self.feed_ticks(kline.close_time);
+            self.fill_delayed_entry(kline);
+            self.try_fill_pending(kline);
+            self.work_split_entry(kline);
+            // Notify opens before exits run: a dislocation that reverts
+            // within the fill bar would otherwise close (and drop) the
+            // position before the observer ever saw it open.
+            for pos in &self.positions[positions_before..] {
+                obs.on_trade_open(pos);
+            }
+            for pos in &mut self.positions {
+                pos.update_excursions(kline);
+                pos.bars_held += 1;
+            }
+            self.check_exit_signals(kline);
+
+            let positions_before = self.positions.len();
+            if let Some(signal) = self.engine.on_bar(kline) {
+                self.handle_signal(signal, kline);
+            }
+            for pos in &self.positions[positions_before..] {
+                obs.on_trade_open(pos);
+            }
+            self.update_equity_curve(kline);
+
+            for trade in &self.trades[trades_before..] {
+                obs.on_trade_close(trade);
+            }
+            let equity = self.equity_curve.last().map_or(self.capital, |(_, e)| *e);
+            obs.on_bar(i, equity);
+        }
+        self.engine.flush_signal_log();
+        // With every lot closed and no carry costs, realized trade PnL is the
+        // only thing that moved capital; any drift means a fee or fill was
+        // counted twice.
+        if self.positions.is_empty()
+            && self.config.funding_schedule.is_empty()
+            && self.config.short_borrow_bps_per_day == 0.0
+        {
+            let realized: f64 = self.trades.iter().map(|t| t.pnl).sum();
+            debug_assert!(
+                (self.capital - (self.config.initial_capital + realized)).abs() < 1e-6,
+                "capital reconciliation drift: final {} vs initial {} + realized {}",
+                self.capital,
+                self.config.initial_capital,
+                realized,
+            );
+        }
+        let mut exit_reason_counts: HashMap<ExitReason, usize> = HashMap::new();
+        for trade in &self.trades {
+            *exit_reason_counts.entry(trade.exit_reason).or_insert(0) += 1;
+        }
+        BacktestResults {
+            initial_capital: self.config.initial_capital,
+            final_capital: self.capital,
+            trades: self.trades.clone(),
+            equity_curve: self.equity_curve.clone(),
+            limit_entries_placed: self.limit_entries_placed,
+            limit_entries_filled: self.limit_entries_filled,
+            exit_reason_counts,
+            skipped_signal_counts: self.engine.skip_counts().clone(),
+        }
+    }
+
+    /// Act on a freshly emitted signal: queue a new entry (nothing fills
+    /// before the next bar — the signal was computed from this bar's
+    /// close) while under `max_concurrent_positions`, or stop-and-reverse
+    /// when one arrives against the open lots and
+    /// `reverse_on_opposite_signal` is set. Opposite-direction signals
+    /// never stack as hedges.
+    fn handle_signal(&mut self, signal: TradeSignal, kline: &Kline) {
+        if let Some(first) = self.positions.first() {
+            if signal.direction != first.direction {
+                if self.engine.cfg.reverse_on_opposite_signal {
+                    // Flatten every lot now — the exit is judged on
+                    // prices already printed — and queue the flip leg
+                    // like any other entry, displacing any stale
+                    // same-direction order still waiting.
+                    while !self.positions.is_empty() {
+                        self.close_lot(0, kline, ExitReason::Reversal);
+                    }
+                    self.queue_entry(signal, true);
+                }
+                return;
+            }
+        }
+        if self.positions.len() < self.config.max_concurrent_positions
+            && self.pending_entry.is_none()
+            && self.working_entry.is_none()
+            && self.delayed_entry.is_none()
+        {
+            self.queue_entry(signal, false);
+        }
+    }
+
+    /// Queue `signal` for execution on a later bar. The earliest fill is
+    /// the next bar's open — a close-computed decision can never fill on
+    /// its own bar — and `fill_delay_bars` beyond 1 push it further out.
+    fn queue_entry(&mut self, signal: TradeSignal, force_market: bool) {
+        self.delayed_entry = Some(DelayedEntry {
+            signal,
+            bars_remaining: self.config.fill_delay_bars.saturating_sub(1),
+            force_market,
+        });
+    }
+
+    /// Hand a signal to the configured entry mode against `kline` — the
+    /// signal bar when fills are instant, the delayed bar otherwise.
+    fn submit_entry(&mut self, signal: TradeSignal, kline: &Kline) {
+        match self.config.entry_mode {
+            EntryMode::Market => {
+                self.open_position(&signal, kline);
+                self.engine.open_position(&signal);
+            }
+            EntryMode::LimitEntry { offset_bps, .. } => {
+                self.place_limit_entry(signal, offset_bps);
+            }
+            EntryMode::Twap { bars } => {
+                self.start_split_entry(signal, bars, false, kline);
+            }
+            EntryMode::Vwap { bars } => {
+                self.start_split_entry(signal, bars, true, kline);
+            }
+        }
+    }
+
+    /// Count down a queued delayed entry and submit it once its delay has
+    /// elapsed, executing against this bar.
+    fn fill_delayed_entry(&mut self, kline: &Kline) {
+        let Some(delayed) = &mut self.delayed_entry else {
+            return;
+        };
+        if delayed.bars_remaining > 0 {
+            delayed.bars_remaining -= 1;
+            return;
+        }
+        let delayed = self.delayed_entry.take().expect("checked above");
+        if delayed.force_market {
+            self.open_position(&delayed.signal, kline);
+            self.engine.open_position(&delayed.signal);
+        } else {
+            self.submit_entry(delayed.signal, kline);
+        }
+    }
+
+    /// Rest a limit `offset_bps` inside the signal price.
+    fn place_limit_entry(&mut self, signal: TradeSignal, offset_bps: f64) {
+        let offset = offset_bps / 1e4;
+        let limit_price = match signal.direction {
+            Direction::Long => signal.price * (1.0 - offset),
+            Direction::Short => signal.price * (1.0 + offset),
+        };
+        self.pending_entry = Some(PendingEntry {
+            signal,
+            limit_price,
+            bars_waited: 0,
+        });
+        self.limit_entries_placed += 1;
+    }
+
+    /// Fill the resting limit if this bar trades through it, or cancel it
+    /// once `max_wait_bars` have passed without a fill.
+    fn try_fill_pending(&mut self, kline: &Kline) {
+        let Some(pending) = &self.pending_entry else {
+            return;
+        };
+        let crossed = match pending.signal.direction {
+            Direction::Long => kline.low <= pending.limit_price,
+            Direction::Short => kline.high >= pending.limit_price,
+        };
+        if crossed {
+            let pending = self.pending_entry.take().expect("checked above");
+            self.limit_entries_filled += 1;
+            // A resting limit fills at its own price and rests as a maker.
+            self.open_position_at(pending.limit_price, FillKind::Maker, &pending.signal, kline);
+            self.engine.open_position(&pending.signal);
+            return;
+        }
+        let pending = self.pending_entry.as_mut().expect("checked above");
+        pending.bars_waited += 1;
+        if let EntryMode::LimitEntry { max_wait_bars, .. } = self.config.entry_mode {
+            if pending.bars_waited >= max_wait_bars {
+                self.pending_entry = None;
+            }
+        }
+    }
+
+    /// Begin working a TWAP/VWAP entry and take its first slice on the
+    /// signal bar; [`Self::work_split_entry`] takes one more per bar.
+    fn start_split_entry(
+        &mut self,
+        signal: TradeSignal,
+        bars: usize,
+        use_vwap: bool,
+        kline: &Kline,
+    ) {
+        self.working_entry = Some(WorkingEntry {
+            signal,
+            bars: bars.max(1),
+            fills: Vec::new(),
+            use_vwap,
+        });
+        self.work_split_entry(kline);
+    }
+
+    /// Fill the next slice of the working entry at this bar's price. When
+    /// the last slice fills, the lot opens at the mean child price with
+    /// the full size, and the slice prices are kept on the position.
+    fn work_split_entry(&mut self, kline: &Kline) {
+        let Some(working) = &mut self.working_entry else {
+            return;
+        };
+        let raw_price = if working.use_vwap {
+            FillMode::Vwap.price(kline)
+        } else {
+            self.config.entry_fill.price(kline)
+        };
+        // Each slice carries its share of the order for the impact model.
+        let slice_qty = self.capital * working.signal.size_frac * self.config.leverage
+            / raw_price
+            / working.bars as f64;
+        let frac = self.config.slippage.fraction(slice_qty, kline.volume);
+        let sign = match working.signal.direction {
+            Direction::Long => 1.0,
+            Direction::Short => -1.0,
+        };
+        working.fills.push(raw_price * (1.0 + sign * frac));
+        if working.fills.len() < working.bars {
+            return;
+        }
+        let working = self.working_entry.take().expect("checked above");
+        let avg_price = working.fills.iter().sum::<f64>() / working.fills.len() as f64;
+        let before = self.positions.len();
+        self.open_position_at(avg_price, self.config.entry_fill_kind, &working.signal, kline);
+        // Unless the lot was skipped by the exchange filters, keep the
+        // slice prices on it.
+        if self.positions.len() > before {
+            self.positions.last_mut().expect("just pushed").child_fills = working.fills;
+            self.engine.open_position(&working.signal);
+        }
+    }
+
+    fn slip(
+        &self,
+        price: f64,
+        direction: Direction,
+        is_entry: bool,
+        order_qty: f64,
+        bar_volume: f64,
+    ) -> f64 {
+        let frac = self.config.slippage.fraction(order_qty, bar_volume);
+        // Slippage always works against us.
+        let sign = match (direction, is_entry) {
+            (Direction::Long, true) | (Direction::Short, false) => 1.0,
+            (Direction::Long, false) | (Direction::Short, true) => -1.0,
+        };
+        price * (1.0 + sign * frac)
+    }
+
+    /// The fee rate for a fill of `kind` at `now_ms`, from the volume tier
+    /// currently earned (the flat config fees when no tiers are set).
+    fn fee_rate(&mut self, kind: FillKind, now_ms: i64) -> f64 {
+        self.fees.roll_to(now_ms);
+        match kind {
+            FillKind::Maker => self.fees.maker_fee(),
+            FillKind::Taker => self.fees.taker_fee(),
+        }
+    }
+
+    fn open_position(&mut self, signal: &TradeSignal, kline: &Kline) {
+        let raw_price = self.config.entry_fill.price(kline);
+        // Size the order at the pre-slippage price for the impact estimate.
+        let order_qty = self.capital * signal.size_frac * self.config.leverage / raw_price;
+        let entry_price =
+            self.slip(raw_price, signal.direction, true, order_qty, kline.volume);
+        self.open_position_at(entry_price, self.config.entry_fill_kind, signal, kline);
+    }
+
+    fn open_position_at(
+        &mut self,
+        entry_price: f64,
+        fill_kind: FillKind,
+        signal: &TradeSignal,
+        kline: &Kline,
+    ) {
+        // Entries must fill on a bar that opened after the close-computed
+        // signal's bar; an earlier fill prices in information from before
+        // the decision existed.
+        if self.config.strict_causality {
+            assert!(
+                kline.open_time > signal.ts,
+                "look-ahead fill: entry bar opens at {} but the signal came from the bar at {}",
+                kline.open_time,
+                signal.ts,
+            );
+        }
+        // A blown account cannot margin anything; count the refusal rather
+        // than sizing an order off non-positive capital.
+        if self.capital <= 0.0 {
+            self.engine.count_skip(SkipReason::InsufficientMargin);
+            return;
+        }
+        let position_value = self.capital * signal.size_frac * self.config.leverage;
+        let mut entry_price = entry_price;
+        let mut quantity = position_value / entry_price;
+        // Snap to the exchange filters so simulated fills are exchange-valid;
+        // quantities floor to the lot step, so an order below one step is
+        // simply not placed.
+        if let Some(spec) = self
+            .engine
+            .cfg
+            .snap_to_filters
+            .then(|| find_spec(&self.engine.cfg.symbol))
+            .flatten()
+        {
+            entry_price = spec.snap_price(entry_price);
+            quantity = spec.snap_qty(quantity);
+            if quantity <= 0.0 {
+                self.engine.count_skip(SkipReason::BelowMinSize);
+                return;
+            }
+        }
+        let commission = quantity * entry_price * self.fee_rate(fill_kind, kline.open_time);
+        self.capital -= commission;
+        self.fees.record_fill(kline.open_time, quantity * entry_price);
+        self.positions.push(Position {
+            direction: signal.direction,
+            entry_time: kline.open_time,
+            entry_price,
+            quantity,
+            entry_commission: commission,
+            entry_fill_kind: fill_kind,
+            entry_reason: signal.reason,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: vec![entry_price],
+        });
+    }
+
+    fn check_exit_signals(&mut self, kline: &Kline) {
+        let delay = self.config.fill_delay_bars;
+        // Per-lot exits via the configured policy; each lot is judged on
+        // its own entry price and holding clock.
+        let mut i = 0;
+        while i < self.positions.len() {
+            // A queued delayed exit executes once its countdown runs out.
+            if let Some((reason, remaining)) = self.positions[i].pending_exit {
+                if remaining == 0 {
+                    self.close_lot(i, kline, reason);
+                } else {
+                    self.positions[i].pending_exit = Some((reason, remaining - 1));
+                    i += 1;
+                }
+                continue;
+            }
+            let pos = &self.positions[i];
+            // Per-lot time stop: the model's own time stop only covers its
+            // single notional position, so stacked lots each run their own
+            // clock against `max_hold_bars`. 0 disables.
+            let max_hold = self.engine.cfg.max_hold_bars;
+            let time_hit = max_hold > 0 && pos.bars_held >= max_hold;
+            let exit = self
+                .exit_policy
+                .should_exit(pos, kline, &self.engine)
+                .or_else(|| time_hit.then_some(ExitReason::TimeStop));
+            match exit {
+                Some(reason) if delay == 0 => self.close_lot(i, kline, reason),
+                Some(reason) => {
+                    self.positions[i].pending_exit = Some((reason, delay - 1));
+                    i += 1;
+                }
+                None => i += 1,
+            }
+        }
+        // The model exit closes one lot per bar, oldest first (FIFO).
+        if !self.positions.is_empty() && self.positions[0].pending_exit.is_none() {
+            if let Some(reason) = self.engine.check_exit(kline.close) {
+                if delay == 0 {
+                    self.close_lot(0, kline, reason);
+                } else {
+                    self.positions[0].pending_exit = Some((reason, delay - 1));
+                }
+            }
+        }
+    }
+
+    /// Close the oldest open lot (FIFO). Production exits go through
+    /// [`close_lot`](Self::close_lot) directly; tests keep this wrapper.
+    #[cfg(test)]
+    fn close_position(&mut self, kline: &Kline, reason: ExitReason) {
+        if !self.positions.is_empty() {
+            self.close_lot(0, kline, reason);
+        }
+    }
+
+    /// Close the lot at `idx`, realizing its PnL and fees.
+    fn close_lot(&mut self, idx: usize, kline: &Kline, reason: ExitReason) {
+        let pos = self.positions.remove(idx);
+        let exit_price = self.slip(
+            self.config.entry_fill.price(kline),
+            pos.direction,
+            false,
+            pos.quantity,
+            kline.volume,
+        );
+        let notional = pos.quantity * pos.entry_price;
+        let gross = pos.direction.sign() * (exit_price - pos.entry_price) * pos.quantity;
+        let exit_commission = pos.quantity
+            * exit_price
+            * self.fee_rate(self.config.exit_fill_kind, kline.open_time);
+        self.fees.record_fill(kline.open_time, pos.quantity * exit_price);
+        let pnl = gross - pos.entry_commission - exit_commission;
+        // The entry commission was already debited when the lot opened, so
+        // only the exit leg settles here; crediting the full net `pnl` would
+        // charge the entry fee twice.
+        self.capital += gross - exit_commission;
+        self.engine.close_position(kline.close);
+        self.trades.push(Trade {
+            entry_time: pos.entry_time,
+            exit_time: kline.open_time,
+            direction: pos.direction,
+            entry_price: pos.entry_price,
+            exit_price,
+            quantity: pos.quantity,
+            entry_fill_kind: pos.entry_fill_kind,
+            exit_fill_kind: self.config.exit_fill_kind,
+            exit_reason: reason,
+            entry_reason: pos.entry_reason,
+            pnl,
+            commission: pos.entry_commission + exit_commission,
+            return_pct: pnl / notional,
+            mae_frac: pos.mae_frac,
+            mfe_frac: pos.mfe_frac,
+            child_fills: pos.child_fills,
+        });
+    }
+
+    fn update_equity_curve(&mut self, kline: &Kline) {
+        self.apply_funding(kline);
+        self.apply_borrow_cost(kline);
+        let mut equity = self.capital;
+        for pos in &self.positions {
+            equity += pos.direction.sign() * (kline.close - pos.entry_price) * pos.quantity;
+        }
+        self.equity_curve.push((kline.open_time, equity));
+    }
+
+    /// Settle any funding events this bar covers against the open position.
+    /// Longs pay a positive rate, shorts receive it; notional is marked at
+    /// the bar close.
+    fn apply_funding(&mut self, kline: &Kline) {
+        while self.funding_idx < self.config.funding_schedule.len()
+            && self.config.funding_schedule[self.funding_idx].0 <= kline.close_time
+        {
+            let (_, rate) = self.config.funding_schedule[self.funding_idx];
+            for pos in &self.positions {
+                let notional = pos.quantity * kline.close;
+                self.capital -= pos.direction.sign() * notional * rate;
+            }
+            self.funding_idx += 1;
+        }
+    }
+
+    /// Debit the short-borrow cost for the bar just elapsed. Only short
+    /// lots pay; longs hold the asset itself and borrow nothing.
+    fn apply_borrow_cost(&mut self, kline: &Kline) {
+        if self.config.short_borrow_bps_per_day == 0.0 {
+            return;
+        }
+        let day_frac = (kline.close_time - kline.open_time + 1) as f64 / 86_400_000.0;
+        let rate = self.config.short_borrow_bps_per_day / 1e4 * day_frac;
+        for pos in &self.positions {
+            if pos.direction == Direction::Short {
+                self.capital -= pos.quantity * kline.close * rate;
+            }
+        }
+    }
+
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades
+    }
+
+    pub fn equity_curve(&self) -> &[(i64, f64)] {
+        &self.equity_curve
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use mft_engine::data::Kline;
+
+    /// Build a bar series from closes, one minute apart.
+    pub fn bars_from_closes(closes: &[f64]) -> Vec<Kline> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Kline {
+                open_time: i as i64 * 60_000,
+                open: close,
+                high: close * 1.0005,
+                low: close * 0.9995,
+                close,
+                volume: 100.0,
+                close_time: i as i64 * 60_000 + 59_999,
+                quote_volume: close * 100.0,
+                n_trades: 50,
+                // Alternate the taker side bar to bar so the synthetic
+                // flow is two-sided: an exact 50/50 split would classify
+                // every bar tick as a sell (`to_tick` wants a strict buy
+                // majority) and saturate VPIN at its toxic extreme.
+                taker_buy_volume: if i % 2 == 0 { 70.0 } else { 30.0 },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::bars_from_closes;
+
+    #[test]
+    fn fill_modes_pick_distinct_prices() {
+        let mut bars = bars_from_closes(&[100.0]);
+        // Make the bar asymmetric so all three prices differ.
+        bars[0].open = 99.0;
+        bars[0].high = 102.0;
+        bars[0].low = 97.0;
+        let k = &bars[0];
+        let open = FillMode::OpenPrice.price(k);
+        let close = FillMode::ClosePrice.price(k);
+        let vwap = FillMode::Vwap.price(k);
+        assert_eq!(open, 99.0);
+        assert_eq!(close, 100.0);
+        assert!((vwap - 99.75).abs() < 1e-12);
+        assert!(vwap != open && vwap != close);
+    }
+
+    #[test]
+    fn large_orders_slip_more_under_the_proportional_model() {
+        let prop = SlippageModel::VolumeProportional {
+            base_bps: 1.0,
+            impact_coeff: 0.001,
+        };
+        // 5% vs 50% of the bar's volume.
+        let small = prop.fraction(5.0, 100.0);
+        let large = prop.fraction(50.0, 100.0);
+        assert!(large > small);
+        assert!((small - (1e-4 + 0.001 * 0.05)).abs() < 1e-15);
+        assert!((large - (1e-4 + 0.001 * 0.5)).abs() < 1e-15);
+
+        // The fixed model ignores order size entirely.
+        let fixed = SlippageModel::Fixed { bps: 1.0 };
+        assert_eq!(fixed.fraction(5.0, 100.0), fixed.fraction(50.0, 100.0));
+        // A zero-volume bar cannot blow up the impact term.
+        assert_eq!(prop.fraction(5.0, 0.0), 1e-4);
+    }
+
+    #[test]
+    fn maker_exit_pays_less_fee_than_taker_exit() {
+        let run_exit = |kind: FillKind| {
+            let bt_cfg = SimpleBacktestConfig {
+                exit_fill_kind: kind,
+                ..SimpleBacktestConfig::default()
+            };
+            let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+            engine.positions.push(Position {
+                direction: Direction::Long,
+                entry_time: 0,
+                entry_price: 100.0,
+                quantity: 1.0,
+                entry_commission: 0.05,
+                entry_fill_kind: FillKind::Taker,
+                entry_reason: SignalReason::OuReversion,
+                bars_held: 0,
+                pending_exit: None,
+                mae_frac: 0.0,
+                mfe_frac: 0.0,
+                child_fills: Vec::new(),
+            });
+            let bars = bars_from_closes(&[101.0]);
+            engine.close_position(&bars[0], ExitReason::TakeProfit);
+            engine.trades[0].clone()
+        };
+        let maker = run_exit(FillKind::Maker);
+        let taker = run_exit(FillKind::Taker);
+        assert!(maker.commission < taker.commission);
+        assert_eq!(maker.exit_fill_kind, FillKind::Maker);
+        assert!(maker.pnl > taker.pnl);
+    }
+
+    #[test]
+    fn crossing_a_volume_tier_lowers_the_taker_fee() {
+        use mft_engine::config::FeeTier;
+        let tier = |volume, maker_fee, taker_fee| FeeTier {
+            volume,
+            maker_fee,
+            taker_fee,
+        };
+        let app_cfg = AppConfig {
+            fee_tiers: vec![tier(0.0, 0.0002, 0.0005), tier(2_500.0, 0.00016, 0.0004)],
+            ..AppConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(app_cfg, SimpleBacktestConfig::default());
+        let bars = bars_from_closes(&[100.0]);
+        let rate = |pos: &Position| pos.entry_commission / (pos.quantity * pos.entry_price);
+
+        // Two ~1 500-notional entries stay inside the base tier; together
+        // they push rolling volume past 2 500, so the third pays the lower
+        // taker rate.
+        engine.open_position_at(100.0, FillKind::Taker, &long_sig(100.0), &bars[0]);
+        engine.open_position_at(100.0, FillKind::Taker, &long_sig(100.0), &bars[0]);
+        engine.open_position_at(100.0, FillKind::Taker, &long_sig(100.0), &bars[0]);
+        assert!((rate(&engine.positions[0]) - 0.0005).abs() < 1e-12);
+        assert!((rate(&engine.positions[1]) - 0.0005).abs() < 1e-12);
+        assert!((rate(&engine.positions[2]) - 0.0004).abs() < 1e-12);
+    }
+
+    #[test]
+    fn real_ticks_give_a_different_vpin_than_the_bar_approximation() {
+        let closes: Vec<f64> = (0..80).map(|i| 100.0 + (i % 5) as f64 * 0.1).collect();
+        let mut bars = bars_from_closes(&closes);
+        // Force every bar taker-buy-heavy: the whole-bar ticks then all
+        // classify as buys, so the bar approximation saturates VPIN even
+        // though the intra-bar tape below is perfectly balanced.
+        for bar in &mut bars {
+            bar.taker_buy_volume = bar.volume;
+        }
+        // Balanced two-sided tape: ten alternating ticks per bar.
+        let ticks: Vec<TradeTick> = bars
+            .iter()
+            .flat_map(|k| {
+                (0..10).map(move |j| TradeTick {
+                    ts: k.open_time + j as i64 * 5_000,
+                    price: k.close,
+                    qty: 10.0,
+                    is_buy: j % 2 == 0,
+                })
+            })
+            .collect();
+        let cfg = AppConfig {
+            ou_window: 30,
+            vpin_bucket_volume: 200.0,
+            ..AppConfig::default()
+        };
+
+        let mut approx = SimpleBacktestEngine::new(cfg.clone(), SimpleBacktestConfig::default());
+        approx.run(&bars);
+        let mut real = SimpleBacktestEngine::new(
+            AppConfig {
+                tick_source: mft_engine::config::TickSource::Trades,
+                ..cfg
+            },
+            SimpleBacktestConfig::default(),
+        )
+        .with_trade_ticks(ticks);
+        real.run(&bars);
+
+        // The one-sided bars saturate the bar-approximated VPIN; the
+        // alternating real tape is balanced.
+        let approx_vpin = approx.engine.flow_signal().vpin.expect("approx vpin");
+        let real_vpin = real.engine.flow_signal().vpin.expect("real vpin");
+        assert!(approx_vpin > 0.8, "approx vpin = {approx_vpin}");
+        assert!(real_vpin < 0.2, "real vpin = {real_vpin}");
+    }
+
+    fn sig(direction: Direction, price: f64) -> TradeSignal {
+        let cfg = AppConfig::default();
+        TradeSignal {
+            ts: 0,
+            direction,
+            price,
+            z_score: -direction.sign() * 2.5,
+            ev: 0.001,
+            vpin: None,
+            ofi: None,
+            garch_sigma_bar: 0.001,
+            size_frac: 0.1,
+            risk: mft_engine::risk::RiskLevels::from_entry(price, direction, &cfg),
+            reason: SignalReason::OuReversion,
+        }
+    }
+
+    fn long_sig(price: f64) -> TradeSignal {
+        sig(Direction::Long, price)
+    }
+
+    #[test]
+    fn unreached_limit_entry_never_fills() {
+        let bt_cfg = SimpleBacktestConfig {
+            entry_mode: EntryMode::LimitEntry {
+                offset_bps: 50.0,
+                max_wait_bars: 2,
+            },
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        engine.place_limit_entry(long_sig(100.0), 50.0);
+
+        // The limit rests at 99.5; the bars only trade down to 99.8.
+        let mut bars = bars_from_closes(&[100.0, 100.0]);
+        for bar in &mut bars {
+            bar.low = 99.8;
+        }
+        engine.try_fill_pending(&bars[0]);
+        assert!(engine.pending_entry.is_some());
+        engine.try_fill_pending(&bars[1]);
+
+        assert!(engine.positions.is_empty());
+        assert!(engine.pending_entry.is_none(), "cancelled after max_wait_bars");
+        assert_eq!(engine.limit_entries_placed, 1);
+        assert_eq!(engine.limit_entries_filled, 0);
+    }
+
+    #[test]
+    fn limit_entry_fills_at_its_price_as_maker() {
+        let bt_cfg = SimpleBacktestConfig {
+            entry_mode: EntryMode::LimitEntry {
+                offset_bps: 50.0,
+                max_wait_bars: 5,
+            },
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        engine.place_limit_entry(long_sig(100.0), 50.0);
+
+        let mut bars = bars_from_closes(&[100.0]);
+        bars[0].low = 99.4; // trades through the 99.5 limit
+        engine.try_fill_pending(&bars[0]);
+
+        let pos = engine.positions.first().expect("filled");
+        assert!((pos.entry_price - 99.5).abs() < 1e-12);
+        assert_eq!(pos.entry_fill_kind, FillKind::Maker);
+        assert_eq!(engine.limit_entries_filled, 1);
+    }
+
+    #[test]
+    fn three_lots_close_fifo_with_blended_pnl() {
+        let bt_cfg = SimpleBacktestConfig {
+            max_concurrent_positions: 3,
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        for (t, entry) in [(0i64, 100.0), (1, 102.0), (2, 104.0)] {
+            engine.positions.push(Position {
+                direction: Direction::Long,
+                entry_time: t,
+                entry_price: entry,
+                quantity: 1.0,
+                entry_commission: 0.0,
+                entry_fill_kind: FillKind::Taker,
+                entry_reason: SignalReason::OuReversion,
+                bars_held: 0,
+                pending_exit: None,
+                mae_frac: 0.0,
+                mfe_frac: 0.0,
+                child_fills: Vec::new(),
+            });
+        }
+        let before = engine.capital;
+        let bars = bars_from_closes(&[110.0]);
+        while !engine.positions.is_empty() {
+            engine.close_position(&bars[0], ExitReason::TakeProfit);
+        }
+
+        assert_eq!(engine.trades.len(), 3);
+        // FIFO: the oldest lot closes first.
+        assert_eq!(engine.trades[0].entry_price, 100.0);
+        assert_eq!(engine.trades[2].entry_price, 104.0);
+        // Each lot exits at the open less 1 bp of slippage and pays the
+        // taker fee on its own exit notional.
+        let exit = 110.0 * (1.0 - 1e-4);
+        let expected: f64 = [100.0, 102.0, 104.0]
+            .iter()
+            .map(|e| (exit - e) - exit * 0.0005)
+            .sum();
+        let realized: f64 = engine.trades.iter().map(|t| t.pnl).sum();
+        assert!((realized - expected).abs() < 1e-9);
+        assert!((engine.capital - before - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn capital_reconciles_with_realized_pnl_over_many_trades() {
+        let mut engine =
+            SimpleBacktestEngine::new(AppConfig::default(), SimpleBacktestConfig::default());
+        let bars = bars_from_closes(&[100.0, 101.0]);
+        for _ in 0..5 {
+            engine.open_position_at(100.0, FillKind::Taker, &long_sig(100.0), &bars[0]);
+            engine.close_position(&bars[1], ExitReason::TakeProfit);
+        }
+
+        assert_eq!(engine.trades.len(), 5);
+        // Each entry fee is charged exactly once — at open, not again inside
+        // the closing PnL — so capital reconciles with the blotter.
+        let realized: f64 = engine.trades.iter().map(|t| t.pnl).sum();
+        assert!((engine.capital - (engine.config.initial_capital + realized)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn twap_entry_averages_the_slice_prices() {
+        let app_cfg = AppConfig {
+            // Keep the lot filters out of the price comparison.
+            snap_to_filters: false,
+            ..AppConfig::default()
+        };
+        let bt_cfg = SimpleBacktestConfig {
+            entry_mode: EntryMode::Twap { bars: 3 },
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(app_cfg, bt_cfg);
+        let bars = bars_from_closes(&[100.0, 102.0, 104.0, 106.0]);
+
+        engine.handle_signal(long_sig(100.0), &bars[0]);
+        assert!(engine.positions.is_empty(), "queued for the next bar");
+        engine.fill_delayed_entry(&bars[1]);
+        assert!(engine.positions.is_empty(), "first slice only");
+        engine.work_split_entry(&bars[2]);
+        assert!(engine.positions.is_empty(), "two of three slices");
+        engine.work_split_entry(&bars[3]);
+
+        let pos = engine.positions.first().expect("opened on the last slice");
+        // Mean of the three bar opens, each slipped 1 bp against the long.
+        let expected = (102.0 + 104.0 + 106.0) / 3.0 * (1.0 + 1e-4);
+        assert!((pos.entry_price - expected).abs() < 1e-9);
+        assert_eq!(pos.child_fills.len(), 3);
+        assert!((pos.child_fills[1] - 104.0 * (1.0 + 1e-4)).abs() < 1e-9);
+        assert_eq!(pos.entry_time, bars[3].open_time);
+    }
+
+    #[test]
+    fn vwap_slices_fill_at_the_bar_vwap_price() {
+        let app_cfg = AppConfig {
+            snap_to_filters: false,
+            ..AppConfig::default()
+        };
+        let bt_cfg = SimpleBacktestConfig {
+            entry_mode: EntryMode::Vwap { bars: 1 },
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(app_cfg, bt_cfg);
+        let mut bars = bars_from_closes(&[100.0, 100.0]);
+        bars[1].open = 99.0;
+        bars[1].high = 102.0;
+        bars[1].low = 98.0;
+
+        engine.handle_signal(long_sig(100.0), &bars[0]);
+        engine.fill_delayed_entry(&bars[1]);
+        let pos = engine.positions.first().expect("single-slice fill");
+        let vwap = (102.0 + 98.0 + 2.0 * 100.0) / 4.0;
+        assert!((pos.entry_price - vwap * (1.0 + 1e-4)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn results_count_trades_by_exit_reason() {
+        let mut engine =
+            SimpleBacktestEngine::new(AppConfig::default(), SimpleBacktestConfig::default());
+        let lot = |entry: f64| Position {
+            direction: Direction::Long,
+            entry_time: 0,
+            entry_price: entry,
+            quantity: 1.0,
+            entry_commission: 0.0,
+            entry_fill_kind: FillKind::Taker,
+            entry_reason: SignalReason::OuReversion,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        };
+        // Two winners at 99, one loser at 101, all exiting near 100.
+        engine.positions.push(lot(99.0));
+        engine.positions.push(lot(99.0));
+        engine.positions.push(lot(101.0));
+        let bars = bars_from_closes(&[100.0]);
+        engine.close_lot(0, &bars[0], ExitReason::TakeProfit);
+        engine.close_lot(0, &bars[0], ExitReason::TakeProfit);
+        engine.close_lot(0, &bars[0], ExitReason::StopLoss);
+
+        let results = engine.run(&[]);
+        assert_eq!(results.exit_reason_counts[&ExitReason::TakeProfit], 2);
+        assert_eq!(results.exit_reason_counts[&ExitReason::StopLoss], 1);
+        let avg = results.avg_pnl_by_reason();
+        assert!(avg[&ExitReason::TakeProfit] > 0.0);
+        assert!(avg[&ExitReason::StopLoss] < 0.0);
+    }
+
+    #[test]
+    fn time_stop_closes_an_unstopped_lot_at_max_hold_bars() {
+        let app_cfg = AppConfig {
+            max_hold_bars: 10,
+            ..AppConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(app_cfg, SimpleBacktestConfig::default());
+        engine.positions.push(Position {
+            direction: Direction::Long,
+            entry_time: 0,
+            entry_price: 100.0,
+            quantity: 1.0,
+            entry_commission: 0.0,
+            entry_fill_kind: FillKind::Taker,
+            entry_reason: SignalReason::OuReversion,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        });
+        // Flat bars never touch the 2% stop; only the clock can close.
+        let bars = bars_from_closes(&[100.0; 15]);
+        let results = engine.run(&bars);
+
+        assert_eq!(results.trades.len(), 1);
+        assert_eq!(results.trades[0].exit_reason, ExitReason::TimeStop);
+        // Held through ten bars, closed on the tenth (index 9).
+        assert_eq!(results.trades[0].exit_time, bars[9].open_time);
+    }
+
+    fn lot(direction: Direction, entry: f64) -> Position {
+        Position {
+            direction,
+            entry_time: 0,
+            entry_price: entry,
+            quantity: 1.0,
+            entry_commission: 0.0,
+            entry_fill_kind: FillKind::Taker,
+            entry_reason: SignalReason::OuReversion,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        }
+    }
+
+    /// A model engine warmed on a gentle oscillation around 100, so its OU
+    /// window is full and z-scores are meaningful. The slow sine keeps the
+    /// closes positively autocorrelated (a bar-by-bar alternation would fit
+    /// an AR(1) slope near −1, which the estimator rejects); 68 bars end
+    /// the warm-up near the sine's mean, so z(100) sits inside the exit
+    /// band.
+    fn warm_engine() -> StrategyEngine {
+        let cfg = AppConfig {
+            ou_window: 30,
+            vpin_bucket_volume: 25.0,
+            ..AppConfig::default()
+        };
+        let mut eng = StrategyEngine::new(cfg);
+        let closes: Vec<f64> = (0..68)
+            .map(|i| 100.0 + 0.5 * (i as f64 / 3.0).sin())
+            .collect();
+        for bar in bars_from_closes(&closes) {
+            eng.on_bar(&bar);
+        }
+        eng
+    }
+
+    #[test]
+    fn fixed_stop_policy_trips_on_the_bar_extremes() {
+        let eng = StrategyEngine::new(AppConfig::default());
+        let policy = FixedStopExit { stop_frac: 0.02 };
+        let mut bars = bars_from_closes(&[100.0]);
+        assert_eq!(policy.should_exit(&lot(Direction::Long, 100.0), &bars[0], &eng), None);
+        bars[0].low = 97.9;
+        assert_eq!(
+            policy.should_exit(&lot(Direction::Long, 100.0), &bars[0], &eng),
+            Some(ExitReason::StopLoss)
+        );
+        // The short side watches the high instead.
+        bars[0].high = 102.1;
+        assert_eq!(
+            policy.should_exit(&lot(Direction::Short, 100.0), &bars[0], &eng),
+            Some(ExitReason::StopLoss)
+        );
+    }
+
+    #[test]
+    fn ou_reversion_policy_follows_the_z_score() {
+        let eng = warm_engine();
+        let policy = OuMeanReversionExit;
+        let dip = &bars_from_closes(&[95.0])[0];
+        let back = &bars_from_closes(&[100.0])[0];
+        // Still dislocated: a long holds through the dip...
+        assert_eq!(policy.should_exit(&lot(Direction::Long, 96.0), dip, &eng), None);
+        // ...and closes once z is back inside the exit band.
+        assert_eq!(
+            policy.should_exit(&lot(Direction::Long, 96.0), back, &eng),
+            Some(ExitReason::ZReversion)
+        );
+        // A short is already reverted on its side at the dip.
+        assert_eq!(
+            policy.should_exit(&lot(Direction::Short, 96.0), dip, &eng),
+            Some(ExitReason::ZReversion)
+        );
+    }
+
+    #[test]
+    fn composite_exit_reports_the_first_firing_policy() {
+        let eng = warm_engine();
+        let policy = ExitPolicyKind::StopAndReversion { stop_frac: 0.02 }.build();
+        // A stop breach while still dislocated: the stop decides.
+        let mut dip = bars_from_closes(&[95.0])[0];
+        dip.low = 94.0;
+        assert_eq!(
+            policy.should_exit(&lot(Direction::Long, 96.5), &dip, &eng),
+            Some(ExitReason::StopLoss)
+        );
+        // No breach, z reverted: the reversion exit decides.
+        let back = &bars_from_closes(&[100.0])[0];
+        assert_eq!(
+            policy.should_exit(&lot(Direction::Long, 96.5), back, &eng),
+            Some(ExitReason::ZReversion)
+        );
+        // Neither fires: the lot stays open.
+        let quiet = &bars_from_closes(&[95.0])[0];
+        assert_eq!(policy.should_exit(&lot(Direction::Long, 96.5), quiet, &eng), None);
+    }
+
+    #[test]
+    fn delayed_entries_fill_at_the_delay_bars_open() {
+        let run = |delay: usize| {
+            let bt_cfg = SimpleBacktestConfig {
+                slippage: SlippageModel::Fixed { bps: 0.0 },
+                fill_delay_bars: delay,
+                ..SimpleBacktestConfig::default()
+            };
+            let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+            // Each bar after the signal gaps further against the long.
+            let bars = bars_from_closes(&[100.0, 103.0, 106.0]);
+            engine.handle_signal(long_sig(100.0), &bars[0]);
+            assert!(engine.positions.is_empty(), "nothing fills on the signal bar");
+            engine.fill_delayed_entry(&bars[1]);
+            engine.fill_delayed_entry(&bars[2]);
+            engine.positions[0].entry_price
+        };
+        // The baseline fills at the next bar's open; an extra bar of
+        // latency pays up for one more adverse gap.
+        assert_eq!(run(0), 103.0);
+        assert_eq!(run(2), 106.0);
+    }
+
+    #[test]
+    fn close_computed_signals_fill_at_the_next_bars_open() {
+        let bt_cfg = SimpleBacktestConfig {
+            slippage: SlippageModel::Fixed { bps: 0.0 },
+            strict_causality: true,
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        let bars = bars_from_closes(&[100.0, 101.0]);
+        let mut signal = long_sig(100.0);
+        signal.ts = bars[0].open_time;
+        engine.handle_signal(signal, &bars[0]);
+        assert!(engine.positions.is_empty(), "no same-bar fill");
+        engine.fill_delayed_entry(&bars[1]);
+        let pos = &engine.positions[0];
+        assert_eq!(pos.entry_time, bars[1].open_time);
+        assert_eq!(pos.entry_price, bars[1].open);
+        assert!(pos.entry_time > bars[0].open_time, "fill strictly follows the decision");
+    }
+
+    #[test]
+    #[should_panic(expected = "look-ahead fill")]
+    fn strict_causality_flags_a_same_bar_fill() {
+        let bt_cfg = SimpleBacktestConfig {
+            strict_causality: true,
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        let bars = bars_from_closes(&[100.0]);
+        // Filling directly on the signal's own bar is exactly the bug the
+        // diagnostic exists to catch.
+        engine.open_position_at(100.0, FillKind::Taker, &long_sig(100.0), &bars[0]);
+    }
+
+    #[test]
+    fn delayed_exits_close_one_bar_after_the_decision() {
+        let bt_cfg = SimpleBacktestConfig {
+            slippage: SlippageModel::Fixed { bps: 0.0 },
+            fill_delay_bars: 1,
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        engine.positions.push(lot(Direction::Long, 100.0));
+
+        // Bar 0 trips the 2% stop; the fill waits for bar 1 and takes its
+        // open, not the stop bar's price.
+        let bars = bars_from_closes(&[97.0, 99.0]);
+        engine.check_exit_signals(&bars[0]);
+        assert!(engine.positions[0].pending_exit.is_some());
+        assert!(engine.trades.is_empty());
+        engine.check_exit_signals(&bars[1]);
+        assert!(engine.positions.is_empty());
+        assert_eq!(engine.trades[0].exit_reason, ExitReason::StopLoss);
+        assert_eq!(engine.trades[0].exit_price, bars[1].open);
+    }
+
+    #[test]
+    fn order_quantities_floor_to_the_lot_step() {
+        // Default symbol is BTCUSDT (size_incr 0.001, snap_to_filters on).
+        let mut engine =
+            SimpleBacktestEngine::new(AppConfig::default(), SimpleBacktestConfig::default());
+        let bars = bars_from_closes(&[100.0]);
+
+        // Sized for 0.00149 BTC at 100 — floors to one 0.001 lot.
+        let mut small = long_sig(100.0);
+        small.size_frac = 0.149 / (5_000.0 * 3.0);
+        engine.open_position_at(100.0, FillKind::Taker, &small, &bars[0]);
+        let pos = engine.positions.first().expect("lot placed");
+        assert!((pos.quantity - 0.001).abs() < 1e-12);
+        // Commission is charged on the floored notional, not the raw one.
+        assert!((pos.entry_commission - 0.001 * 100.0 * 0.0005).abs() < 1e-12);
+
+        // A sub-step order is skipped outright: no lot, no fee.
+        let before = engine.capital;
+        let mut tiny = long_sig(100.0);
+        tiny.size_frac = 0.05 / (5_000.0 * 3.0); // 0.0005 BTC
+        engine.open_position_at(100.0, FillKind::Taker, &tiny, &bars[0]);
+        assert_eq!(engine.positions.len(), 1);
+        assert_eq!(engine.capital, before);
+    }
+
+    #[test]
+    fn sub_minimum_signals_are_counted_not_traded() {
+        let mut engine =
+            SimpleBacktestEngine::new(AppConfig::default(), SimpleBacktestConfig::default());
+        let bars = bars_from_closes(&[100.0]);
+        // 0.0005 BTC orders floor below BTCUSDT's 0.001 lot step.
+        let mut tiny = long_sig(100.0);
+        tiny.size_frac = 0.05 / (5_000.0 * 3.0);
+        engine.open_position_at(100.0, FillKind::Taker, &tiny, &bars[0]);
+        engine.open_position_at(100.0, FillKind::Taker, &tiny, &bars[0]);
+
+        let results = engine.run(&[]);
+        assert!(results.trades.is_empty());
+        assert_eq!(results.skipped_signal_counts[&SkipReason::BelowMinSize], 2);
+        assert!(!results
+            .skipped_signal_counts
+            .contains_key(&SkipReason::InsufficientMargin));
+
+        // A blown account refuses even a valid order, under its own reason.
+        engine.capital = -1.0;
+        engine.open_position_at(100.0, FillKind::Taker, &long_sig(100.0), &bars[0]);
+        assert!(engine.positions.is_empty());
+        assert_eq!(
+            engine.engine.skip_counts()[&SkipReason::InsufficientMargin],
+            1
+        );
+    }
+
+    #[test]
+    fn same_direction_signals_stack_lots_up_to_the_cap() {
+        let bt_cfg = SimpleBacktestConfig {
+            max_concurrent_positions: 2,
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        let bars = bars_from_closes(&[100.0, 100.0, 100.0, 100.0]);
+        for pair in bars.windows(2) {
+            engine.handle_signal(long_sig(100.0), &pair[0]);
+            engine.fill_delayed_entry(&pair[1]);
+        }
+        assert_eq!(engine.positions.len(), 2, "third lot exceeds the cap");
+    }
+
+    #[test]
+    fn opposite_signal_closes_now_and_flips_at_the_next_open() {
+        let app_cfg = AppConfig {
+            reverse_on_opposite_signal: true,
+            ..AppConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(app_cfg, SimpleBacktestConfig::default());
+        engine.positions.push(Position {
+            direction: Direction::Long,
+            entry_time: 0,
+            entry_price: 100.0,
+            quantity: 1.0,
+            entry_commission: 0.05,
+            entry_fill_kind: FillKind::Taker,
+            entry_reason: SignalReason::OuReversion,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        });
+        let bars = bars_from_closes(&[100.0, 100.0]);
+        engine.handle_signal(sig(Direction::Short, 100.0), &bars[0]);
+
+        // The long is flattened on the signal bar (one logged trade, fees
+        // on both of its legs); the flip leg fills at the next bar's open.
+        assert_eq!(engine.trades.len(), 1);
+        assert!(engine.trades[0].commission > 0.05);
+        assert!(engine.positions.is_empty(), "flip waits for the next bar");
+        engine.fill_delayed_entry(&bars[1]);
+        let pos = engine.positions.first().expect("reversed");
+        assert_eq!(pos.direction, Direction::Short);
+        assert!(pos.entry_commission > 0.0);
+    }
+
+    #[test]
+    fn opposite_signal_is_dropped_when_reversal_is_off() {
+        let mut engine =
+            SimpleBacktestEngine::new(AppConfig::default(), SimpleBacktestConfig::default());
+        engine.positions.push(Position {
+            direction: Direction::Long,
+            entry_time: 0,
+            entry_price: 100.0,
+            quantity: 1.0,
+            entry_commission: 0.05,
+            entry_fill_kind: FillKind::Taker,
+            entry_reason: SignalReason::OuReversion,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        });
+        let bars = bars_from_closes(&[100.0]);
+        engine.handle_signal(sig(Direction::Short, 100.0), &bars[0]);
+        assert!(engine.trades.is_empty());
+        assert_eq!(engine.positions[0].direction, Direction::Long);
+    }
+
+    #[test]
+    fn long_pays_positive_funding() {
+        let bt_cfg = SimpleBacktestConfig {
+            funding_schedule: vec![(90_000, 0.0001)],
+            ..SimpleBacktestConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+        engine.positions.push(Position {
+            direction: Direction::Long,
+            entry_time: 0,
+            entry_price: 100.0,
+            quantity: 1.0,
+            entry_commission: 0.0,
+            entry_fill_kind: FillKind::Taker,
+            entry_reason: SignalReason::OuReversion,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        });
+        let before = engine.capital;
+        // Bar covering [60s, 120s) crosses the 90s funding timestamp.
+        let bars = bars_from_closes(&[100.0, 100.0]);
+        engine.update_equity_curve(&bars[1]);
+        let expected = 100.0 * 1.0 * 0.0001;
+        assert!((before - engine.capital - expected).abs() < 1e-12);
+        // The event settles once.
+        engine.update_equity_curve(&bars[1]);
+        assert!((before - engine.capital - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn one_day_short_pays_the_borrow_fee_the_long_does_not() {
+        let run_day = |direction: Direction| {
+            let bt_cfg = SimpleBacktestConfig {
+                short_borrow_bps_per_day: 10.0,
+                ..SimpleBacktestConfig::default()
+            };
+            let mut engine = SimpleBacktestEngine::new(AppConfig::default(), bt_cfg);
+            engine.positions.push(Position {
+                direction,
+                entry_time: 0,
+                entry_price: 100.0,
+                quantity: 1.0,
+                entry_commission: 0.0,
+                entry_fill_kind: FillKind::Taker,
+                entry_reason: SignalReason::OuReversion,
+                bars_held: 0,
+                pending_exit: None,
+                mae_frac: 0.0,
+                mfe_frac: 0.0,
+                child_fills: Vec::new(),
+            });
+            let before = engine.capital;
+            // A full day of one-minute bars at a flat price.
+            let closes: Vec<f64> = (0..1440).map(|_| 100.0).collect();
+            for bar in bars_from_closes(&closes) {
+                engine.update_equity_curve(&bar);
+            }
+            before - engine.capital
+        };
+
+        let short_cost = run_day(Direction::Short);
+        let long_cost = run_day(Direction::Long);
+        // 10 bp/day on a 100-notional short = 0.1 over the day.
+        assert!((short_cost - 0.1).abs() < 1e-9, "cost = {short_cost}");
+        assert_eq!(long_cost, 0.0);
+    }
+
+    #[test]
+    fn mae_is_the_worst_intrabar_excursion() {
+        let mut engine =
+            SimpleBacktestEngine::new(AppConfig::default(), SimpleBacktestConfig::default());
+        engine.positions.push(Position {
+            direction: Direction::Long,
+            entry_time: 0,
+            entry_price: 100.0,
+            quantity: 1.0,
+            entry_commission: 0.0,
+            entry_fill_kind: FillKind::Taker,
+            entry_reason: SignalReason::OuReversion,
+            bars_held: 0,
+            pending_exit: None,
+            mae_frac: 0.0,
+            mfe_frac: 0.0,
+            child_fills: Vec::new(),
+        });
+        // Three bars: the deepest low is 97 (-3%), the highest high 103 (+3%).
+        let mut bars = bars_from_closes(&[100.0, 100.0, 100.0]);
+        for (bar, (lo, hi)) in bars.iter_mut().zip([(99.0, 101.0), (97.0, 102.0), (98.0, 103.0)]) {
+            bar.low = lo;
+            bar.high = hi;
+        }
+        for bar in &bars {
+            engine.positions[0].update_excursions(bar);
+        }
+        engine.close_position(&bars[2], ExitReason::TakeProfit);
+        let trade = &engine.trades[0];
+        assert!((trade.mae_frac - (-0.03)).abs() < 1e-12);
+        assert!((trade.mfe_frac - 0.03).abs() < 1e-12);
+    }
+
+    #[derive(Default)]
+    struct CountingObserver {
+        bars: usize,
+        opens: usize,
+        closes: usize,
+    }
+
+    impl BacktestObserver for CountingObserver {
+        fn on_bar(&mut self, _i: usize, _equity: f64) {
+            self.bars += 1;
+        }
+        fn on_trade_open(&mut self, _position: &Position) {
+            self.opens += 1;
+        }
+        fn on_trade_close(&mut self, _trade: &Trade) {
+            self.closes += 1;
+        }
+    }
+
+    #[test]
+    fn observer_counts_match_bars_and_trades() {
+        let app_cfg = AppConfig {
+            ou_window: 30,
+            // Five fixture bars per VPIN bucket, so the alternating
+            // taker sides average out instead of each 100-volume bar tick
+            // filling whole buckets one-sided.
+            vpin_bucket_volume: 500.0,
+            ..AppConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(app_cfg, SimpleBacktestConfig::default());
+        // Oscillation with periodic dislocations so trades actually happen.
+        // The slow sine keeps the closes positively autocorrelated so the
+        // AR(1) fit accepts the tape; the 98.5 prints are ~4 sigma dips.
+        let closes: Vec<f64> = (0..300)
+            .map(|i| {
+                if i > 60 && i % 20 == 0 {
+                    98.5
+                } else {
+                    100.0 + 0.5 * (i as f64 / 3.0).sin()
+                }
+            })
+            .collect();
+        let mut obs = CountingObserver::default();
+        let results = engine.run_with_observer(&bars_from_closes(&closes), &mut obs);
+
+        assert_eq!(obs.bars, closes.len());
+        assert_eq!(obs.closes, results.trades.len());
+        assert!(obs.opens >= 1, "the series should open at least one trade");
+        // Every close was preceded by an open; at most one trade is still
+        // open when the replay ends.
+        assert!(obs.opens == obs.closes || obs.opens == obs.closes + 1);
+    }
+
+    #[test]
+    fn checkpoint_resume_matches_an_uninterrupted_run() {
+        let app_cfg = AppConfig {
+            ou_window: 30,
+            // Five fixture bars per VPIN bucket, so the alternating
+            // taker sides average out instead of each 100-volume bar tick
+            // filling whole buckets one-sided.
+            vpin_bucket_volume: 500.0,
+            ..AppConfig::default()
+        };
+        // Same dislocation tape as the observer test: positively
+        // autocorrelated with ~4 sigma dips, so both halves trade.
+        let closes: Vec<f64> = (0..300)
+            .map(|i| {
+                if i > 60 && i % 20 == 0 {
+                    98.5
+                } else {
+                    100.0 + 0.5 * (i as f64 / 3.0).sin()
+                }
+            })
+            .collect();
+        let bars = bars_from_closes(&closes);
+
+        let mut full =
+            SimpleBacktestEngine::new(app_cfg.clone(), SimpleBacktestConfig::default());
+        let expected = full.run(&bars);
+        assert!(!expected.trades.is_empty(), "series should trade");
+
+        // Interrupt halfway and round-trip the checkpoint through JSON so
+        // the model buffers (OU window, VPIN buckets) must serialize
+        // faithfully, not just clone.
+        let mut first =
+            SimpleBacktestEngine::new(app_cfg.clone(), SimpleBacktestConfig::default());
+        first.run(&bars[..150]);
+        let json = serde_json::to_string(&first.checkpoint()).unwrap();
+        let state: EngineState = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.bars_processed(), 150);
+
+        let mut resumed = SimpleBacktestEngine::new(app_cfg, SimpleBacktestConfig::default());
+        resumed.restore(state);
+        let results = resumed.run(&bars[150..]);
+
+        assert_eq!(results.trades.len(), expected.trades.len());
+        assert_eq!(results.final_capital, expected.final_capital);
+        assert_eq!(results.equity_curve, expected.equity_curve);
+    }
+
+    #[test]
+    fn flat_market_produces_no_trades() {
+        let app_cfg = AppConfig {
+            ou_window: 30,
+            ..AppConfig::default()
+        };
+        let mut engine = SimpleBacktestEngine::new(app_cfg, SimpleBacktestConfig::default());
+        let closes: Vec<f64> = (0..100).map(|_| 100.0).collect();
+        let results = engine.run(&bars_from_closes(&closes));
+        assert!(results.trades.is_empty());
+        assert_eq!(results.final_capital, results.initial_capital);
+        assert_eq!(results.equity_curve.len(), 100);
+    }
+}
@@ -0,0 +1,529 @@
+//! Simple bar-replay backtest CLI: load a kline parquet, run
+//! [`SimpleBacktestEngine`] and export the equity curve.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use polars::prelude::*;
+use tracing::info;
+
+use mft_engine::config::{AppConfig, TickSource};
+use mft_engine::data::Kline;
+use mft_engine::metrics::compute_metrics;
+use rust_backtest::reporting::{buy_and_hold_returns, ReportConfig, ReportGenerator};
+use rust_backtest::simple_engine::{
+    BacktestResults, EngineState, SimpleBacktestConfig, SimpleBacktestEngine,
+};
+
+#[derive(Parser)]
+#[command(name = "simple_backtest", about = "Fast bar-replay backtest")]
+struct Cli {
+    /// Parquet file of klines (bar length comes from `MFT_INTERVAL`).
+    #[arg(long)]
+    data: String,
+    /// Symbol name (for output naming).
+    #[arg(long, default_value = "BTCUSDT")]
+    symbol: String,
+    /// Initial balance.
+    #[arg(long, default_value_t = 5_000.0)]
+    balance: f64,
+    /// Output directory for CSVs.
+    #[arg(long, default_value = "output")]
+    out_dir: String,
+    /// TOML config path; falls back to the environment/defaults.
+    #[arg(long)]
+    config: Option<String>,
+    /// Write a resumable checkpoint to this path every `checkpoint_every`
+    /// bars.
+    #[arg(long)]
+    checkpoint: Option<String>,
+    /// Checkpoint interval in bars; 0 disables periodic checkpoints.
+    #[arg(long, default_value_t = 0)]
+    checkpoint_every: usize,
+    /// Resume from a checkpoint file written by a previous run.
+    #[arg(long)]
+    resume: Option<String>,
+}
+
+/// Canonical column name → accepted aliases, as written by the various
+/// downloaders and third-party tools we have seen in the wild.
+const KLINE_COLUMN_ALIASES: &[(&str, &[&str])] = &[
+    ("open_time", &["timestamp", "time", "ts", "date"]),
+    ("open", &["o"]),
+    ("high", &["h"]),
+    ("low", &["l"]),
+    ("close", &["c"]),
+    ("volume", &["vol", "v", "base_volume"]),
+    ("close_time", &["end_time"]),
+    ("quote_asset_volume", &["quote_volume", "quote_av"]),
+    ("n_trades", &["trades", "num_trades", "count"]),
+    ("taker_buy_volume", &["taker_buy_base_asset_volume", "taker_base_vol"]),
+];
+
+const REQUIRED_KLINE_COLUMNS: &[&str] = &["open_time", "open", "high", "low", "close", "volume"];
+
+/// Check that a kline DataFrame (after alias renaming) carries every
+/// required column with a dtype we know how to coerce, naming the offending
+/// column instead of letting polars produce a cryptic downstream error.
+pub fn validate_kline_schema(df: &DataFrame) -> Result<()> {
+    let missing: Vec<&str> = REQUIRED_KLINE_COLUMNS
+        .iter()
+        .filter(|c| df.column(c).is_err())
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "kline parquet is missing required columns: {} (found: {:?})",
+            missing.join(", "),
+            df.get_column_names()
+        );
+    }
+
+    for col in ["open_time", "close_time"] {
+        let Ok(series) = df.column(col) else { continue };
+        match series.dtype() {
+            DataType::Int64 | DataType::Int32 | DataType::UInt64 | DataType::Datetime(_, _) => {}
+            other => anyhow::bail!(
+                "column {col} has dtype {other} but an integer or datetime timestamp is required"
+            ),
+        }
+    }
+    for col in REQUIRED_KLINE_COLUMNS.iter().filter(|c| **c != "open_time") {
+        let series = df.column(col).expect("checked above");
+        if !series.dtype().is_numeric() {
+            anyhow::bail!(
+                "column {col} has dtype {} but a numeric type is required",
+                series.dtype()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Normalize a kline DataFrame to the canonical schema: rename known column
+/// aliases, validate via [`validate_kline_schema`], then cast timestamps
+/// (Datetime → i64 epoch ms) and prices/volumes to f64.
+pub fn normalize_kline_schema(mut df: DataFrame) -> Result<DataFrame> {
+    // Map aliases onto canonical names.
+    let existing: Vec<String> = df.get_column_names().iter().map(|s| s.to_string()).collect();
+    for (canonical, aliases) in KLINE_COLUMN_ALIASES {
+        if existing.iter().any(|c| c == canonical) {
+            continue;
+        }
+        if let Some(alias) = aliases.iter().find(|a| existing.iter().any(|c| c == *a)) {
+            df.rename(alias, (*canonical).into())?;
+        }
+    }
+
+    validate_kline_schema(&df)?;
+
+    // Cast timestamps to i64 epoch milliseconds.
+    for col in ["open_time", "close_time"] {
+        let Ok(column) = df.column(col) else { continue };
+        let series = column.as_materialized_series();
+        let casted = match series.dtype() {
+            DataType::Int64 => series.clone(),
+            DataType::Datetime(TimeUnit::Milliseconds, _) => series.cast(&DataType::Int64)?,
+            DataType::Datetime(TimeUnit::Microseconds, _) => series.cast(&DataType::Int64)? / 1_000,
+            DataType::Datetime(TimeUnit::Nanoseconds, _) => {
+                series.cast(&DataType::Int64)? / 1_000_000
+            }
+            _ => series
+                .cast(&DataType::Int64)
+                .with_context(|| format!("column {col} cannot be cast to i64 ms"))?,
+        };
+        df.replace(col, casted)?;
+    }
+
+    // Cast numeric columns to f64 (some writers emit f32 or decimal).
+    for col in [
+        "open",
+        "high",
+        "low",
+        "close",
+        "volume",
+        "quote_asset_volume",
+        "taker_buy_volume",
+    ] {
+        if let Ok(column) = df.column(col) {
+            if column.dtype() != &DataType::Float64 {
+                let casted = column
+                    .as_materialized_series()
+                    .cast(&DataType::Float64)
+                    .with_context(|| format!("column {col} cannot be cast to f64"))?;
+                df.replace(col, casted)?;
+            }
+        }
+    }
+    Ok(df)
+}
+
+/// Load klines from a parquet, tolerating schema variants via
+/// [`normalize_kline_schema`]. `interval` sets the bar length used when a
+/// file carries no `close_time` column; unrecognized intervals fall back
+/// to one minute.
+pub fn load_parquet_data(path: &str, interval: &str) -> Result<Vec<Kline>> {
+    let bar_ms = mft_engine::data::interval_ms(interval).unwrap_or(60_000);
+    let df = LazyFrame::scan_parquet(path, Default::default())?
+        .collect()
+        .with_context(|| format!("reading {path}"))?;
+    let df = normalize_kline_schema(df).with_context(|| format!("normalizing {path}"))?;
+
+    let open_time = df.column("open_time")?.i64()?;
+    let open = df.column("open")?.f64()?;
+    let high = df.column("high")?.f64()?;
+    let low = df.column("low")?.f64()?;
+    let close = df.column("close")?.f64()?;
+    let volume = df.column("volume")?.f64()?;
+    let close_time = df.column("close_time").and_then(|s| s.i64().cloned()).ok();
+    let quote_volume = df
+        .column("quote_asset_volume")
+        .and_then(|s| s.f64().cloned())
+        .ok();
+    let taker_buy = df
+        .column("taker_buy_volume")
+        .and_then(|s| s.f64().cloned())
+        .ok();
+
+    let mut out = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        let ot = open_time.get(i).context("null open_time")?;
+        let vol = volume.get(i).context("null volume")?;
+        out.push(Kline {
+            open_time: ot,
+            open: open.get(i).context("null open")?,
+            high: high.get(i).context("null high")?,
+            low: low.get(i).context("null low")?,
+            close: close.get(i).context("null close")?,
+            volume: vol,
+            close_time: close_time
+                .as_ref()
+                .and_then(|s| s.get(i))
+                .unwrap_or(ot + bar_ms - 1),
+            quote_volume: quote_volume.as_ref().and_then(|s| s.get(i)).unwrap_or(0.0),
+            n_trades: 0,
+            taker_buy_volume: taker_buy
+                .as_ref()
+                .and_then(|s| s.get(i))
+                .unwrap_or(vol * 0.5),
+        });
+    }
+    Ok(out)
+}
+
+/// Window (in bars) for the rolling Sharpe / drawdown columns.
+const ROLLING_WINDOW: usize = 30;
+
+/// Write the equity curve with point-in-time drawdown plus rolling
+/// 30-period Sharpe and max-drawdown columns (NaN until each window fills).
+pub fn save_equity_curve_csv(
+    results: &BacktestResults,
+    out_dir: &str,
+    symbol: &str,
+    run_ts: i64,
+) -> Result<String> {
+    std::fs::create_dir_all(out_dir)?;
+    let path = format!("{out_dir}/{symbol}_{run_ts}_equity.csv");
+
+    let equity: Vec<f64> = results.equity_curve.iter().map(|(_, e)| *e).collect();
+    let rets = mft_engine::metrics::returns(&equity);
+    let roll_sharpe = mft_engine::metrics::rolling_sharpe(&rets, ROLLING_WINDOW);
+    let roll_dd = mft_engine::metrics::rolling_max_drawdown(&equity, ROLLING_WINDOW);
+
+    let mut buf = String::from("open_time,equity,drawdown,rolling_sharpe,rolling_max_dd\n");
+    let mut peak = f64::MIN;
+    for (i, (ts, equity)) in results.equity_curve.iter().enumerate() {
+        if *equity > peak {
+            peak = *equity;
+        }
+        let dd = (peak - equity) / peak;
+        // Return index i-1 corresponds to the step into equity point i.
+        let rs = if i == 0 { f64::NAN } else { roll_sharpe[i - 1] };
+        buf.push_str(&format!(
+            "{ts},{equity:.6},{dd:.6},{rs:.6},{:.6}\n",
+            roll_dd[i]
+        ));
+    }
+    std::fs::write(&path, buf)?;
+    Ok(path)
+}
+
+/// Column set written by [`save_trade_blotter_csv`]; kept as a constant so
+/// the test and the writer cannot drift apart.
+const BLOTTER_HEADER: &str =
+    "entry_time,exit_time,direction,entry_reason,entry_price,exit_price,quantity,pnl,commission,return_pct";
+
+/// Write one row per closed trade. Timestamps are RFC3339 so the file loads
+/// cleanly into pandas/polars without epoch guessing.
+pub fn save_trade_blotter_csv(
+    results: &BacktestResults,
+    out_dir: &str,
+    symbol: &str,
+    run_ts: i64,
+) -> Result<String> {
+    std::fs::create_dir_all(out_dir)?;
+    let path = format!("{out_dir}/{symbol}_{run_ts}_trades.csv");
+
+    let rfc3339 = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_else(|| ms.to_string())
+    };
+    let mut buf = format!("{BLOTTER_HEADER}\n");
+    for t in &results.trades {
+        buf.push_str(&format!(
+            "{},{},{:?},{:?},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6}\n",
+            rfc3339(t.entry_time),
+            rfc3339(t.exit_time),
+            t.direction,
+            t.entry_reason,
+            t.entry_price,
+            t.exit_price,
+            t.quantity,
+            t.pnl,
+            t.commission,
+            t.return_pct,
+        ));
+    }
+    std::fs::write(&path, buf)?;
+    Ok(path)
+}
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
+
+    let mut app_cfg = match &cli.config {
+        Some(path) => AppConfig::from_toml_file(path)?,
+        None => AppConfig::from_env(),
+    };
+    app_cfg.symbol = cli.symbol.clone();
+    app_cfg.initial_capital = cli.balance;
+
+    let klines = load_parquet_data(&cli.data, &app_cfg.kline_interval)?;
+    info!(bars = klines.len(), "data loaded");
+
+    // Real tick stream: expected next to the kline parquet, as written by
+    // the complete-data collector. Missing file falls back to the bar
+    // approximation rather than starving the flow models.
+    let mut ticks = Vec::new();
+    if app_cfg.tick_source == TickSource::Trades {
+        let trades_path = std::path::Path::new(&cli.data)
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."))
+            .join(format!("{}_trades.parquet", cli.symbol));
+        if trades_path.exists() {
+            ticks = rust_backtest::complete_data::load_trades_parquet(
+                &trades_path.to_string_lossy(),
+            )?;
+            info!(ticks = ticks.len(), "real tick stream attached");
+        } else {
+            tracing::warn!(
+                path = %trades_path.display(),
+                "tick_source = trades but no trades parquet; using bar approximation"
+            );
+            app_cfg.tick_source = TickSource::Synthetic;
+        }
+    }
+
+    let bt_cfg = SimpleBacktestConfig {
+        initial_capital: cli.balance,
+        ..SimpleBacktestConfig::default()
+    };
+
+    let mut engine = SimpleBacktestEngine::new(app_cfg.clone(), bt_cfg);
+    if !ticks.is_empty() {
+        engine = engine.with_trade_ticks(ticks);
+    }
+
+    let mut start_bar = 0;
+    if let Some(path) = &cli.resume {
+        let state: EngineState = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("reading checkpoint {path}"))?;
+        start_bar = state.bars_processed().min(klines.len());
+        engine.restore(state);
+        info!(bars = start_bar, "resumed from checkpoint");
+    }
+    let remaining = &klines[start_bar..];
+
+    let results = match (&cli.checkpoint, cli.checkpoint_every) {
+        (Some(path), every) if every > 0 => {
+            let mut results = None;
+            for chunk in remaining.chunks(every) {
+                results = Some(engine.run(chunk));
+                std::fs::write(path, serde_json::to_string(&engine.checkpoint())?)
+                    .with_context(|| format!("writing checkpoint {path}"))?;
+            }
+            // An already-complete resume still needs a results object.
+            results.unwrap_or_else(|| engine.run(&[]))
+        }
+        _ => engine.run(remaining),
+    };
+
+    let equity: Vec<f64> = results.equity_curve.iter().map(|(_, e)| *e).collect();
+    let pnls: Vec<f64> = results.trades.iter().map(|t| t.return_pct).collect();
+    let maes: Vec<f64> = results.trades.iter().map(|t| t.mae_frac).collect();
+    let mfes: Vec<f64> = results.trades.iter().map(|t| t.mfe_frac).collect();
+    let bars_per_year =
+        mft_engine::data::bars_per_year(&app_cfg.kline_interval).unwrap_or(525_600.0);
+    let reasons: Vec<_> = results
+        .trades
+        .iter()
+        .map(|t| (t.exit_reason, t.return_pct))
+        .collect();
+    let report = compute_metrics(&equity, &pnls, bars_per_year)
+        .with_excursions(&maes, &mfes)
+        .with_exit_reasons(&reasons);
+    println!("{report}");
+    println!(
+        "Final capital: {:.2} (from {:.2})",
+        results.final_capital, results.initial_capital
+    );
+    if !results.skipped_signal_counts.is_empty() {
+        let mut skipped: Vec<_> = results.skipped_signal_counts.iter().collect();
+        skipped.sort_by_key(|(reason, _)| format!("{reason:?}"));
+        for (reason, n) in skipped {
+            println!("Skipped signals ({reason:?}): {n}");
+        }
+    }
+
+    // Measure the run against simply holding the symbol over the same
+    // bars; both series stay at bar frequency, annualized by bar count.
+    let risk = ReportGenerator::new(ReportConfig {
+        periods_per_year: bars_per_year,
+        risk_resample_ms: 0,
+        ..ReportConfig::default()
+    })
+    .calculate_risk_metrics(&results.equity_curve, &buy_and_hold_returns(&klines));
+    if let (Some(beta), Some(alpha), Some(ir)) = (risk.beta, risk.alpha, risk.information_ratio) {
+        println!("Vs buy-and-hold: beta {beta:.2}, alpha {alpha:.4}, IR {ir:.2}");
+    }
+
+    let run_ts = chrono::Utc::now().timestamp();
+    let path = save_equity_curve_csv(&results, &cli.out_dir, &cli.symbol, run_ts)?;
+    info!(path, "equity curve saved");
+    let path = save_trade_blotter_csv(&results, &cli.out_dir, &cli.symbol, run_ts)?;
+    info!(path, "trade blotter saved");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_parquet(mut df: DataFrame, name: &str) -> String {
+        let dir = std::env::temp_dir().join("mft_schema_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name).to_string_lossy().to_string();
+        let file = std::fs::File::create(&path).unwrap();
+        ParquetWriter::new(file).finish(&mut df).unwrap();
+        path
+    }
+
+    fn canonical_df() -> DataFrame {
+        df![
+            "open_time" => [0i64, 60_000, 120_000],
+            "open" => [100.0, 101.0, 102.0],
+            "high" => [100.5, 101.5, 102.5],
+            "low" => [99.5, 100.5, 101.5],
+            "close" => [101.0, 102.0, 103.0],
+            "volume" => [10.0, 11.0, 12.0],
+            "quote_asset_volume" => [1000.0, 1100.0, 1200.0],
+        ]
+        .unwrap()
+    }
+
+    #[test]
+    fn loads_equivalent_files_with_different_schemas() {
+        let path_a = write_parquet(canonical_df(), "canonical.parquet");
+
+        // Same data written by a different tool: aliased names, datetime
+        // timestamps, f32 prices, no quote volume.
+        let mut variant = df![
+            "timestamp" => [0i64, 60_000, 120_000],
+            "o" => [100.0f32, 101.0, 102.0],
+            "h" => [100.5f32, 101.5, 102.5],
+            "l" => [99.5f32, 100.5, 101.5],
+            "c" => [101.0f32, 102.0, 103.0],
+            "vol" => [10.0, 11.0, 12.0],
+        ]
+        .unwrap();
+        let dt = variant
+            .column("timestamp")
+            .unwrap()
+            .as_materialized_series()
+            .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))
+            .unwrap();
+        variant.replace("timestamp", dt).unwrap();
+        let path_b = write_parquet(variant, "variant.parquet");
+
+        let a = load_parquet_data(&path_a, "1m").unwrap();
+        let b = load_parquet_data(&path_b, "1m").unwrap();
+        assert_eq!(a.len(), b.len());
+        for (ka, kb) in a.iter().zip(&b) {
+            assert_eq!(ka.open_time, kb.open_time);
+            assert!((ka.close - kb.close).abs() < 1e-6);
+            assert!((ka.volume - kb.volume).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn blotter_writes_one_row_per_trade_with_the_declared_header() {
+        use mft_engine::engine::Direction;
+        use rust_backtest::simple_engine::{FillKind, Trade};
+
+        let trade = |entry_time: i64, pnl: f64| Trade {
+            entry_time,
+            exit_time: entry_time + 300_000,
+            direction: Direction::Long,
+            entry_price: 100.0,
+            exit_price: 101.0,
+            quantity: 2.0,
+            entry_fill_kind: FillKind::Taker,
+            exit_fill_kind: FillKind::Taker,
+            exit_reason: mft_engine::engine::ExitReason::TakeProfit,
+            entry_reason: mft_engine::engine::SignalReason::OuReversion,
+            pnl,
+            commission: 0.1,
+            return_pct: pnl / 200.0,
+            mae_frac: -0.001,
+            mfe_frac: 0.012,
+            child_fills: vec![100.0],
+        };
+        let results = BacktestResults {
+            initial_capital: 5_000.0,
+            final_capital: 5_003.0,
+            trades: vec![trade(0, 2.0), trade(600_000, 1.0)],
+            equity_curve: vec![(0, 5_000.0), (600_000, 5_003.0)],
+            limit_entries_placed: 0,
+            limit_entries_filled: 0,
+            exit_reason_counts: std::collections::HashMap::new(),
+            skipped_signal_counts: std::collections::HashMap::new(),
+        };
+
+        let dir = std::env::temp_dir().join("mft_blotter_tests");
+        let path =
+            save_trade_blotter_csv(&results, &dir.to_string_lossy(), "TESTUSDT", 1).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(lines.next(), Some(BLOTTER_HEADER));
+        assert_eq!(lines.count(), results.trades.len());
+        // Timestamps are RFC3339, not raw epoch millis.
+        assert!(content.contains("1970-01-01T00:00:00+00:00"), "{content}");
+    }
+
+    #[test]
+    fn missing_required_column_is_named_in_error() {
+        let df = canonical_df().drop("volume").unwrap();
+        let err = normalize_kline_schema(df).unwrap_err();
+        assert!(err.to_string().contains("volume"), "{err}");
+    }
+
+    #[test]
+    fn wrong_dtype_is_named_in_error() {
+        let mut df = canonical_df();
+        let text = Series::new("close".into(), ["101", "102", "103"]);
+        df.replace("close", text).unwrap();
+        let err = validate_kline_schema(&df).unwrap_err();
+        assert!(err.to_string().contains("close"), "{err}");
+    }
+}
@@ -0,0 +1,259 @@
+//! Walk-forward parameter optimisation over [`SimpleBacktestEngine`].
+//!
+//! The kline series is split into rolling folds; in each fold the leading
+//! slice is in-sample (grid search, best Sharpe wins) and the remainder is
+//! out-of-sample, run once with the chosen parameters. Only the stitched
+//! out-of-sample performance is reported, which is the honest number.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use mft_engine::config::AppConfig;
+use mft_engine::data::Kline;
+use mft_engine::metrics::{compute_metrics, PerfReport};
+
+use crate::simple_engine::{SimpleBacktestConfig, SimpleBacktestEngine};
+
+const BARS_PER_YEAR_1M: f64 = 525_600.0;
+
+/// Ranges searched in-sample; the cartesian product is evaluated.
+#[derive(Debug, Clone)]
+pub struct ParamGrid {
+    pub ou_entry_z: Vec<f64>,
+    pub ou_exit_z: Vec<f64>,
+    pub vpin_threshold: Vec<f64>,
+    pub min_ev: Vec<f64>,
+}
+
+impl Default for ParamGrid {
+    fn default() -> Self {
+        Self {
+            ou_entry_z: vec![1.5, 2.0, 2.5],
+            ou_exit_z: vec![0.25, 0.5],
+            vpin_threshold: vec![0.6, 0.7, 0.8],
+            min_ev: vec![0.0, 0.0005],
+        }
+    }
+}
+
+/// One candidate drawn from the grid.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ParamSet {
+    pub ou_entry_z: f64,
+    pub ou_exit_z: f64,
+    pub vpin_threshold: f64,
+    pub min_ev: f64,
+}
+
+impl ParamSet {
+    fn apply(&self, base: &AppConfig) -> AppConfig {
+        AppConfig {
+            ou_entry_z: self.ou_entry_z,
+            ou_exit_z: self.ou_exit_z,
+            vpin_threshold: self.vpin_threshold,
+            min_ev: self.min_ev,
+            ..base.clone()
+        }
+    }
+}
+
+impl ParamGrid {
+    /// The full cartesian product of the ranges.
+    pub fn candidates(&self) -> Vec<ParamSet> {
+        let mut out = Vec::new();
+        for &ou_entry_z in &self.ou_entry_z {
+            for &ou_exit_z in &self.ou_exit_z {
+                for &vpin_threshold in &self.vpin_threshold {
+                    for &min_ev in &self.min_ev {
+                        out.push(ParamSet {
+                            ou_entry_z,
+                            ou_exit_z,
+                            vpin_threshold,
+                            min_ev,
+                        });
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Outcome of one fold: the chosen parameters and both-side performance.
+#[derive(Debug, Clone, Serialize)]
+pub struct FoldResult {
+    pub fold: usize,
+    pub params: ParamSet,
+    pub is_sharpe: f64,
+    pub oos_report: PerfReport,
+}
+
+/// The full walk-forward outcome.
+#[derive(Debug, Clone, Serialize)]
+pub struct WalkForwardResult {
+    pub folds: Vec<FoldResult>,
+    /// Metrics over the stitched out-of-sample equity across all folds.
+    pub oos_perf: PerfReport,
+}
+
+/// Run one config over a slice and return the report plus raw series.
+fn run_once(
+    cfg: &AppConfig,
+    bt_cfg: &SimpleBacktestConfig,
+    klines: &[Kline],
+) -> (PerfReport, Vec<f64>, Vec<f64>) {
+    let mut engine = SimpleBacktestEngine::new(cfg.clone(), bt_cfg.clone());
+    let results = engine.run(klines);
+    let equity: Vec<f64> = results.equity_curve.iter().map(|(_, e)| *e).collect();
+    let pnls: Vec<f64> = results.trades.iter().map(|t| t.return_pct).collect();
+    let report = compute_metrics(&equity, &pnls, BARS_PER_YEAR_1M);
+    (report, equity, pnls)
+}
+
+/// Sharpe used for in-sample ranking; NaN (no trades / zero variance)
+/// sorts below everything.
+fn rank_sharpe(report: &PerfReport) -> f64 {
+    if report.sharpe.is_finite() {
+        report.sharpe
+    } else {
+        f64::MIN
+    }
+}
+
+/// Walk the series in `n_folds` rolling windows, optimising on the first
+/// `is_frac` of each and evaluating out-of-sample on the rest.
+pub fn walk_forward(
+    base_cfg: &AppConfig,
+    bt_cfg: &SimpleBacktestConfig,
+    klines: &[Kline],
+    grid: &ParamGrid,
+    n_folds: usize,
+    is_frac: f64,
+) -> Result<WalkForwardResult> {
+    if n_folds == 0 {
+        bail!("n_folds must be at least 1");
+    }
+    if !(0.0..1.0).contains(&is_frac) || is_frac == 0.0 {
+        bail!("is_frac must be in (0, 1), got {is_frac}");
+    }
+    let fold_len = klines.len() / n_folds;
+    if fold_len < 10 {
+        bail!(
+            "{} bars is too few for {n_folds} folds",
+            klines.len()
+        );
+    }
+    let candidates = grid.candidates();
+
+    let mut folds = Vec::with_capacity(n_folds);
+    let mut oos_equity = vec![bt_cfg.initial_capital];
+    let mut oos_pnls = Vec::new();
+
+    for fold in 0..n_folds {
+        let seg = &klines[fold * fold_len..(fold + 1) * fold_len];
+        let split = (seg.len() as f64 * is_frac) as usize;
+        let (is_bars, oos_bars) = seg.split_at(split);
+
+        let mut best: Option<(ParamSet, f64)> = None;
+        for params in &candidates {
+            let (report, _, _) = run_once(&params.apply(base_cfg), bt_cfg, is_bars);
+            let score = rank_sharpe(&report);
+            if best.is_none_or(|(_, s)| score > s) {
+                best = Some((*params, score));
+            }
+        }
+        let (params, is_sharpe) = best.context("empty parameter grid")?;
+
+        let (oos_report, equity, pnls) = run_once(&params.apply(base_cfg), bt_cfg, oos_bars);
+        info!(fold, ?params, oos_sharpe = oos_report.sharpe, "fold complete");
+
+        // Stitch this fold's curve onto the running out-of-sample equity.
+        let scale = oos_equity.last().unwrap() / bt_cfg.initial_capital;
+        oos_equity.extend(equity.iter().map(|e| e * scale));
+        oos_pnls.extend(pnls);
+
+        folds.push(FoldResult {
+            fold,
+            params,
+            is_sharpe,
+            oos_report,
+        });
+    }
+
+    let oos_perf = compute_metrics(&oos_equity, &oos_pnls, BARS_PER_YEAR_1M);
+    Ok(WalkForwardResult { folds, oos_perf })
+}
+
+/// Write the fold-by-fold chosen parameters and out-of-sample Sharpe.
+pub fn save_folds_csv(result: &WalkForwardResult, path: &str) -> Result<()> {
+    let mut buf =
+        String::from("fold,ou_entry_z,ou_exit_z,vpin_threshold,min_ev,is_sharpe,oos_sharpe\n");
+    for f in &result.folds {
+        buf.push_str(&format!(
+            "{},{},{},{},{},{:.4},{:.4}\n",
+            f.fold,
+            f.params.ou_entry_z,
+            f.params.ou_exit_z,
+            f.params.vpin_threshold,
+            f.params.min_ev,
+            f.is_sharpe,
+            f.oos_report.sharpe
+        ));
+    }
+    std::fs::write(path, buf).with_context(|| format!("writing {path}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simple_engine::test_util::bars_from_closes;
+
+    fn oscillating_closes(n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| 100.0 + 2.0 * ((i as f64) * 0.4).sin())
+            .collect()
+    }
+
+    #[test]
+    fn two_folds_cover_the_series() {
+        let klines = bars_from_closes(&oscillating_closes(400));
+        let grid = ParamGrid {
+            ou_entry_z: vec![1.5, 2.0],
+            ou_exit_z: vec![0.5],
+            vpin_threshold: vec![0.7],
+            min_ev: vec![0.0],
+        };
+        let base = AppConfig {
+            ou_window: 30,
+            ..AppConfig::default()
+        };
+        let result = walk_forward(
+            &base,
+            &SimpleBacktestConfig::default(),
+            &klines,
+            &grid,
+            2,
+            0.5,
+        )
+        .unwrap();
+        assert_eq!(result.folds.len(), 2);
+        for f in &result.folds {
+            assert!(grid.ou_entry_z.contains(&f.params.ou_entry_z));
+        }
+        // The stitched curve covers both out-of-sample halves.
+        assert!(result.oos_perf.total_return.is_finite());
+    }
+
+    #[test]
+    fn rejects_degenerate_splits() {
+        let klines = bars_from_closes(&oscillating_closes(40));
+        let grid = ParamGrid::default();
+        let base = AppConfig::default();
+        let bt = SimpleBacktestConfig::default();
+        assert!(walk_forward(&base, &bt, &klines, &grid, 0, 0.5).is_err());
+        assert!(walk_forward(&base, &bt, &klines, &grid, 2, 1.5).is_err());
+        assert!(walk_forward(&base, &bt, &klines, &grid, 8, 0.5).is_err());
+    }
+}